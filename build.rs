@@ -51,7 +51,17 @@ fn cc_build() -> Build {
     build
 }
 
+/// only the portable C code, for the core on the host
+fn host_build() {
+    println!("cargo:rerun-if-changed=./c/util.c");
+    cc::Build::new().file("./c/util.c").compile("libutil.a");
+}
+
 fn main() {
+    if env::var_os("CARGO_FEATURE_3DS").is_none() {
+        host_build();
+        return;
+    }
     build_image();
     // copy_font();
 
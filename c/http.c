@@ -38,7 +38,38 @@ static char *read_whole_file(const char *path, size_t *size) {
   return content;
 }
 
+// transfers are aborted if set, see `http_set_cancelled`
+static volatile bool http_cancelled = false;
+
+void http_set_cancelled(bool cancelled) { http_cancelled = cancelled; }
+
+// bytes of the running transfer, see `http_get_progress`
+static volatile long long http_progress_now = 0;
+static volatile long long http_progress_total = 0;
+
+void http_get_progress(long long *now, long long *total) {
+  *now = http_progress_now;
+  *total = http_progress_total;
+}
+
+// status of the last request, http status or curl code, see `http_last_status`
+static volatile long http_status = 0;
+
+long http_get_last_status() { return http_status; }
+
+// options of requests, see `http_set_options`
+static long http_connect_timeout = 30;
+static long http_buffer_size = 128 * 1024;
+
+void http_set_request_options(long connect_timeout, long buffer_size) {
+  http_connect_timeout = connect_timeout;
+  http_buffer_size = buffer_size;
+}
+
 typedef struct HttpData {
+  int (*progress_cb)(void *clientp, long long dltotal, long long dlnow,
+                     long long ultotal, long long ulnow);
+  void *clientp;
   FILE *download_file_fd;
   unsigned char *download_buffer;
   size_t current_buf_size;
@@ -57,6 +88,27 @@ typedef struct HttpResponse {
   char *header;
 } HttpResponse;
 
+static int _curl_progress_cb(void *clientp, curl_off_t dltotal,
+                             curl_off_t dlnow, curl_off_t ultotal,
+                             curl_off_t ulnow) {
+  HttpData *data = (HttpData *)clientp;
+  // non zero aborts the transfer
+  if (http_cancelled) {
+    return 1;
+  }
+  if (ultotal > 0) {
+    http_progress_now = ulnow;
+    http_progress_total = ultotal;
+  } else {
+    http_progress_now = dlnow;
+    http_progress_total = dltotal;
+  }
+  if (data->progress_cb != NULL) {
+    return data->progress_cb(data->clientp, dltotal, dlnow, ultotal, ulnow);
+  }
+  return 0;
+}
+
 static size_t _curl_cb_header(void *data, size_t size, size_t nmemb,
                               void *client_data_ptr) {
   HttpData *mem = (HttpData *)client_data_ptr;
@@ -128,6 +180,10 @@ HttpResponse *http_request(
 
   // data ptr;
   HttpData *data = malloc(sizeof(HttpData));
+  http_progress_now = 0;
+  http_progress_total = 0;
+  data->progress_cb = progress_cb;
+  data->clientp = clientp;
   data->download_buffer = NULL;
   data->current_buf_size = 0;
   data->download_file_fd = NULL;
@@ -148,6 +204,7 @@ HttpResponse *http_request(
     if (!data->download_file_fd) {
       response->status = -2;
       response->message = "创建文件失败";
+      http_status = response->status;
       free(data);
       return NULL;
     }
@@ -158,7 +215,8 @@ HttpResponse *http_request(
 
   if (curl) {
     curl_easy_setopt(curl, CURLOPT_URL, url);
-    curl_easy_setopt(curl, CURLOPT_BUFFERSIZE, 128 * 1024);
+    curl_easy_setopt(curl, CURLOPT_BUFFERSIZE, http_buffer_size);
+    curl_easy_setopt(curl, CURLOPT_CONNECTTIMEOUT, http_connect_timeout);
     if (file_to_upload_name != NULL) {
       form = curl_mime_init(curl);
       field = curl_mime_addpart(form);
@@ -176,11 +234,10 @@ HttpResponse *http_request(
         curl_easy_setopt(curl, CURLOPT_COPYPOSTFIELDS, body);
       }
     }
-    if (progress_cb != NULL) {
-      curl_easy_setopt(curl, CURLOPT_NOPROGRESS, 0L);
-      curl_easy_setopt(curl, CURLOPT_XFERINFODATA, clientp);
-      curl_easy_setopt(curl, CURLOPT_XFERINFOFUNCTION, progress_cb);
-    }
+    // always on, so the transfer can be cancelled
+    curl_easy_setopt(curl, CURLOPT_NOPROGRESS, 0L);
+    curl_easy_setopt(curl, CURLOPT_XFERINFODATA, data);
+    curl_easy_setopt(curl, CURLOPT_XFERINFOFUNCTION, _curl_progress_cb);
 
     // ssl
     if (ssl_verify) {
@@ -245,6 +302,7 @@ HttpResponse *http_request(
   response->header_size = data->header_size;
   response->header = data->header;
   free(data);
+  http_status = response->status;
 
   return response;
 }
@@ -64,7 +64,7 @@ char *pl_get_smdh_short_desc(smdh_s *smdh) {
   return desc_short;
 }
 
-/* u8 pl_get_wifi_strength() { return osGetWifiStrength(); } */
+u8 pl_get_wifi_strength() { return osGetWifiStrength(); }
 
 const u16 *pl_get_icon_buffer_from_smdh(smdh_s *smdh) {
   return smdh->bigIconData;
@@ -92,6 +92,28 @@ Result pl_delete_sv(const FS_ArchiveID arch, const u32 unique_id) {
   return res;
 }
 
+Result pl_get_sv(const u32 unique_id, bool *exists, u64 *value) {
+  return FSUSER_GetSaveDataSecureValue(exists, value, SECUREVALUE_SLOT_SD,
+                                       unique_id, 0);
+}
+
+Result pl_set_sv(const u32 unique_id, const u64 value) {
+  return FSUSER_SetSaveDataSecureValue(value, SECUREVALUE_SLOT_SD, unique_id,
+                                       0);
+}
+
+Result pl_create_ext_data(const u32 ext_id, const u64 title_id, const u8 media,
+                          const u32 dirs, const u32 files, const u64 size) {
+  FS_ExtSaveDataInfo info = {.mediaType = MEDIATYPE_SD, .saveId = ext_id};
+  // the icon of the title is shown in data management of the system settings
+  smdh_s *smdh = pl_get_smdh(title_id, media);
+  Result res = FSUSER_CreateExtSaveData(info, dirs, files, size,
+                                        smdh != NULL ? sizeof(smdh_s) : 0,
+                                        (u8 *)smdh);
+  pl_free(smdh);
+  return res;
+}
+
 Result pl_open_title(const u64 title_id, const FS_MediaType media,
                      const char *path) {
   Result res = 0;
@@ -108,6 +130,22 @@ Result pl_open_title(const u64 title_id, const FS_MediaType media,
 
 bool pl_env_is_homebrew() { return envIsHomebrew(); }
 
+Result pl_get_battery(u8 *level, u8 *charging) {
+  Result res = ptmuInit();
+  if (R_SUCCEEDED(res)) {
+    res = PTMU_GetBatteryLevel(level);
+    if (R_SUCCEEDED(res)) {
+      res = PTMU_GetBatteryChargeState(charging);
+    }
+    ptmuExit();
+  }
+  return res;
+}
+
+Result pl_get_free_bytes(const FS_Archive arch, u64 *free) {
+  return FSUSER_GetFreeBytes(free, arch);
+}
+
 Result pl_get_storage_info(u64 *free, u64 *total) {
   FS_SystemMediaType mediatype = SYSTEM_MEDIATYPE_SD;
   FS_ArchiveResource resource = {0};
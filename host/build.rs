@@ -0,0 +1,5 @@
+/// only the portable C code of `../c`
+fn main() {
+    println!("cargo:rerun-if-changed=../c/util.c");
+    cc::Build::new().file("../c/util.c").compile("libutil.a");
+}
@@ -19,6 +19,7 @@ pub mod list_display_status;
 pub mod list_wrap_display_status;
 pub mod loading;
 pub mod no_data;
+pub mod settings;
 pub mod tips;
 pub mod titles;
 pub mod top_bar;
@@ -30,6 +31,7 @@ use crate::{
         confirm::use_confirm,
         list_wrap_display_status::ListDisplayStatus,
         loading::use_page_loading,
+        settings::Settings,
         tips::use_tips,
         titles::{title_selected::TitleSelected, Titles},
     },
@@ -63,10 +65,21 @@ impl AppExit {
 
 pub struct AuthState(pub bool);
 
+impl AuthState {
+    /// clear the expired token, the auth page is shown to login again
+    pub fn expire(mut auth_state: SyncSignal<AuthState>) {
+        Api::update_auth_data(Api::get_read().api_type, None);
+        if let Ok(mut state) = auth_state.try_write() {
+            state.0 = false;
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum Panel {
     Device,
     Cloud,
+    Settings,
 }
 
 pub fn Main() -> Element {
@@ -134,8 +147,10 @@ pub fn Main() -> Element {
 
             if *selected_panel.read() == Panel::Device {
                 Titles {}
-            } else {
+            } else if *selected_panel.read() == Panel::Cloud {
                 Cloud {}
+            } else {
+                Settings {}
             }
         }
     }
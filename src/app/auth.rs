@@ -6,6 +6,7 @@ use log::{error, info};
 use crate::{
     api::{Api, AuthData},
     app::{loading::Loading, tips::TipsVisible, AuthState},
+    t,
 };
 
 pub fn Auth() -> Element {
@@ -37,7 +38,7 @@ pub fn Auth() -> Element {
                 }
                 Err(err) => {
                     error!("auth error: {:?}", err);
-                    toast(format!("获取授权失败"));
+                    toast(t!("auth.fetch_failed").to_string());
                     None
                 }
             };
@@ -58,11 +59,11 @@ pub fn Auth() -> Element {
                                 if let Ok(mut auth_state) = auth_state.try_write() {
                                     auth_state.0 = true;
                                 }
-                                toast("登录成功！".to_string());
+                                toast(t!("auth.login_success").to_string());
                             }
                             Err(err) => {
                                 error!("fetch profile failed: {:?}", err);
-                                toast("登录失败，获取用户信息失败！".to_string());
+                                toast(t!("auth.login_failed").to_string());
                             }
                         }
                         break;
@@ -99,7 +100,7 @@ pub fn Auth() -> Element {
                     margin_bottom: 10.0,
                 }
 
-                "百度云 App 扫码登录"
+                {t!("auth.scan_tips")}
             }
         } else {
             Loading {
@@ -6,7 +6,7 @@ use std::{
     error::Error,
     fmt::{Display, Formatter},
     fs,
-    io::{Read, Write},
+    io::{self, Write},
     ops::Deref,
     os::unix::fs::MetadataExt,
     path::Path,
@@ -34,17 +34,26 @@ use crate::{
         loading::{PageLoading, PageLoadingVisible},
         no_data::NoData,
         tips::{Tips, TipsVisible},
+        titles::title_selected::{SaveTypes, TitleSelected},
         top_bar::NavBar,
         AppExit, AuthState,
     },
     constant::{
-        FBI_SC_TITLE_ID, HOME_LOCAL_PATH_CACHE, HOME_PAGE_URL, INVALID_EAT_PANCAKE,
-        SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT, SCREEN_TOP_WIDTH,
+        FBI_SC_TITLE_ID, HOME_LOCAL_PATH_CACHE, HOME_PAGE_URL, SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT,
+        SCREEN_TOP_WIDTH,
     },
+    error::{with_network_retry, AppError, AppResult},
     fsu,
     loader::loader_file,
-    platform::{pl_is_fbi_title_exists, pl_is_homebrew, pl_show_swkbd, pl_storage_info},
-    resource::Resource,
+    platform::{
+        pl_commit_arch_data, pl_is_fbi_title_exists, pl_is_homebrew, pl_show_swkbd, pl_storage_info,
+    },
+    progress::{self, ProgressStage},
+    recovery::{
+        get_recovery_data, recovery_data_path, remove_recovery_data, RecoveryData, StorageInfo,
+    },
+    resource::{Resource, TitleInfo},
+    t,
     utils::{
         copy_dir_all, copy_file, create_parent_if_not_exists, ease_out_expo,
         get_current_format_time, join_path, sleep_micros, storage_size_to_info, zip_dir,
@@ -76,17 +85,17 @@ impl Deref for Actions {
 
     fn deref(&self) -> &Self::Target {
         match self {
-            Self::NewDir => "新建文件夹",
-            Self::Delete => "删除",
-            Self::Rename => "重命名",
-            Self::Copy => "复制",
-            Self::Move => "移动",
-            Self::Upload => "上传",
-            Self::Download => "下载",
-            Self::Zip => "压缩",
-            Self::Unzip => "解压",
-            Self::ZipAndUpload => "压缩并上传",
-            Self::InstallWithFBI => "调用 FBI 安装",
+            Self::NewDir => t!("cloud.action.new_dir"),
+            Self::Delete => t!("cloud.action.delete"),
+            Self::Rename => t!("cloud.action.rename"),
+            Self::Copy => t!("cloud.action.copy"),
+            Self::Move => t!("cloud.action.move"),
+            Self::Upload => t!("cloud.action.upload"),
+            Self::Download => t!("cloud.action.download"),
+            Self::Zip => t!("cloud.action.zip"),
+            Self::Unzip => t!("cloud.action.unzip"),
+            Self::ZipAndUpload => t!("cloud.action.zip_and_upload"),
+            Self::InstallWithFBI => t!("cloud.action.install_with_fbi"),
         }
     }
 }
@@ -102,12 +111,15 @@ pub enum Panels {
     Local,
     Cloud,
     LocalRight,
+    /// the save archive of the selected title
+    Archive,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum ChildItem {
     Local(String, bool),
     Cloud(String, u64, bool, u64),
+    Archive(String, bool),
 }
 
 impl ChildItem {
@@ -115,6 +127,7 @@ impl ChildItem {
         match self {
             ChildItem::Local(_, _) => true,
             ChildItem::Cloud(_, _, _, _) => false,
+            ChildItem::Archive(_, _) => false,
         }
     }
 
@@ -122,6 +135,7 @@ impl ChildItem {
         match self {
             ChildItem::Local(_, is_dir) => *is_dir,
             ChildItem::Cloud(_, _, is_dir, _) => *is_dir,
+            ChildItem::Archive(_, is_dir) => *is_dir,
         }
     }
 }
@@ -131,6 +145,7 @@ impl AsRef<str> for ChildItem {
         match self {
             ChildItem::Local(s, _) => s,
             ChildItem::Cloud(s, _, _, _) => s,
+            ChildItem::Archive(s, _) => s,
         }
     }
 }
@@ -269,59 +284,19 @@ impl List {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct RecoveryData {
-    local_list: String,
-    local_list_right: String,
-    cloud_list: String,
-    storage_info: (Option<(f64, f64)>, Option<(f64, f64)>),
-}
-
-pub fn remove_recovery_data() {
-    let path = join_path(HOME_LOCAL_PATH_CACHE, "recovery");
-    if Path::new(&path).exists() {
-        fs::remove_file(&path).ok();
-    }
-}
-
 pub fn create_recovery_data(
     local_list: SyncSignal<List>,
     local_list_right: SyncSignal<List>,
     cloud_list: SyncSignal<List>,
-    storage_info: SyncSignal<(Option<(f64, f64)>, Option<(f64, f64)>)>,
+    storage_info: SyncSignal<StorageInfo>,
 ) -> Result<(), Box<dyn Error>> {
-    let data = RecoveryData {
+    RecoveryData {
         local_list: to_string(&local_list.read().items)?,
         local_list_right: to_string(&local_list_right.read().items)?,
         cloud_list: to_string(&cloud_list.read().items)?,
         storage_info: *storage_info.read(),
-    };
-
-    let data = to_string(&data)?;
-
-    let path = join_path(HOME_LOCAL_PATH_CACHE, "recovery");
-    create_parent_if_not_exists(&path).ok();
-    let mut file = fs::File::create(path)?;
-    file.write_all(data.as_bytes())?;
-
-    Ok(())
-}
-
-pub fn get_recovery_data() -> Result<RecoveryData, Box<dyn Error>> {
-    let path = join_path(HOME_LOCAL_PATH_CACHE, "recovery");
-    if !Path::new(&path).exists() {
-        return Err("recovery data not found".into());
     }
-
-    let mut file = fs::File::open(&path)?;
-    let mut data = String::new();
-    file.read_to_string(&mut data)?;
-    let data: RecoveryData = serde_json::from_str(&data)?;
-    drop(file);
-
-    remove_recovery_data();
-
-    Ok(data)
+    .save(&recovery_data_path())
 }
 
 #[derive(Clone)]
@@ -329,11 +304,14 @@ pub struct ListGlobalState {
     local_list: SyncSignal<List>,
     local_list_right: SyncSignal<List>,
     cloud_list: SyncSignal<List>,
+    archive_list: SyncSignal<List>,
+    /// the title and the save type mounted in the archive panel
+    mounted_archive: SyncSignal<Option<(TitleInfo, SaveTypes)>>,
     selected_panel: SyncSignal<(Panels, Instant)>,
     right_panel: SyncSignal<Panels>,
     right_panel_left: SyncSignal<f64>,
     menu_list_state: SyncSignal<ListState>,
-    storage_info: SyncSignal<(Option<(f64, f64)>, Option<(f64, f64)>)>,
+    storage_info: SyncSignal<StorageInfo>,
 }
 
 pub fn use_list_global_state(is_auth: bool) -> ListGlobalState {
@@ -362,6 +340,8 @@ pub fn use_list_global_state(is_auth: bool) -> ListGlobalState {
         }
         List::new()
     });
+    let archive_list = use_signal_sync(List::new);
+    let mounted_archive = use_signal_sync(|| None);
     let selected_panel = use_signal_sync(|| {
         (
             if !data.is_ok() && is_auth {
@@ -381,7 +361,7 @@ pub fn use_list_global_state(is_auth: bool) -> ListGlobalState {
         }
     });
     let menu_list_state = use_signal_sync(|| ListState::new(9));
-    let storage_info = use_signal_sync::<(Option<(f64, f64)>, Option<(f64, f64)>)>(|| {
+    let storage_info = use_signal_sync::<StorageInfo>(|| {
         if let Ok(data) = &data {
             return data.storage_info.clone();
         }
@@ -392,6 +372,8 @@ pub fn use_list_global_state(is_auth: bool) -> ListGlobalState {
         local_list,
         local_list_right,
         cloud_list,
+        archive_list,
+        mounted_archive,
         selected_panel,
         right_panel,
         right_panel_left,
@@ -422,7 +404,7 @@ fn cloud_enter_dir(
     path: &str,
     is_refresh: bool,
     mut auth_state: SyncSignal<AuthState>,
-) -> Result<(), Box<dyn Error>> {
+) -> AppResult<()> {
     if !Api::get_read().is_login() {
         auth_state.write().0 = false;
         return Ok(());
@@ -459,16 +441,78 @@ fn cloud_enter_dir(
             Ok(())
         }
         Err(e) => {
-            return Err(e);
+            let err = AppError::from_api(e);
+            if err.needs_reauth() {
+                AuthState::expire(auth_state);
+            }
+            Err(err)
         }
     }
 }
 
-fn local_enter_dir(mut list: SyncSignal<List>, path: &str, is_refresh: bool) {
-    let mut children = get_dir_list(path)
+fn local_enter_dir(list: SyncSignal<List>, path: &str, is_refresh: bool) {
+    let children = get_dir_list(path)
         .into_iter()
         .map(|(name, is_dir)| ChildItem::Local(name, is_dir))
         .collect::<Vec<ChildItem>>();
+    push_dir_children(list, path, children, is_refresh);
+}
+
+/// open the save archive mounted in the archive panel
+fn archive_open((title, save_type): (TitleInfo, SaveTypes)) -> AppResult<fsu::Archive> {
+    fsu::arch(
+        save_type.arch_id(),
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    )
+    .map_err(|e| AppError::archive_open(save_type.arch_id(), e))
+}
+
+/// run `f` on the mounted archive and commit the changes
+fn archive_write(
+    mounted: (TitleInfo, SaveTypes),
+    f: impl FnOnce(&fsu::Archive) -> io::Result<()>,
+) -> AppResult<()> {
+    let arch = archive_open(mounted)?;
+    let res = f(&arch);
+    // the cached capacity of the archive is stale now
+    SaveTypes::clear_arch_info(mounted.0.id);
+    res?;
+    if !pl_commit_arch_data(&arch) {
+        return Err(t!("utils.commit_failed").into());
+    }
+    Ok(())
+}
+
+fn archive_enter_dir(
+    list: SyncSignal<List>,
+    mounted: (TitleInfo, SaveTypes),
+    path: &str,
+    is_refresh: bool,
+) -> AppResult<()> {
+    let arch = archive_open(mounted)?;
+    let mut children = vec![];
+    for entry in fsu::read_dir(&arch, path)? {
+        let entry = entry?;
+        if let Some(name) = entry.file_name().to_str() {
+            children.push(ChildItem::Archive(
+                name.to_string(),
+                entry.metadata()?.is_dir(),
+            ));
+        }
+    }
+    push_dir_children(list, path, children, is_refresh);
+    Ok(())
+}
+
+/// sort the children, dirs first, and push them as the current dir of the list
+fn push_dir_children(
+    mut list: SyncSignal<List>,
+    path: &str,
+    mut children: Vec<ChildItem>,
+    is_refresh: bool,
+) {
     children.sort_by(|a, b| {
         if a.is_dir() && !b.is_dir() {
             return std::cmp::Ordering::Less;
@@ -505,6 +549,7 @@ pub fn Cloud() -> Element {
     let mut tips_visible = use_context::<SyncSignal<TipsVisible>>();
     let mut loading = use_context::<SyncSignal<PageLoadingVisible>>();
     let mut confirm_visible = use_context::<Signal<ConfirmVisible>>();
+    let title_selected = use_context::<Signal<Option<TitleSelected>>>();
     let mut actions = use_signal::<Option<(Vec<Actions>, String, String)>>(|| None);
     let mut dialog_visible = use_dialog(
         false,
@@ -521,6 +566,8 @@ pub fn Cloud() -> Element {
         mut local_list,
         mut local_list_right,
         mut cloud_list,
+        mut archive_list,
+        mut mounted_archive,
         mut selected_panel,
         mut right_panel,
         mut right_panel_left,
@@ -604,7 +651,7 @@ pub fn Cloud() -> Element {
 
                 if cloud_is_not_init {
                     if let Err(err) = cloud_enter_dir(cloud_list, "/", false, auth_state) {
-                        toast(format!("获取云端文件列表失败: {}", err));
+                        toast(t!("cloud.list_failed", err = err));
                     }
                 }
 
@@ -632,14 +679,19 @@ pub fn Cloud() -> Element {
             Panels::Local => local_list,
             Panels::LocalRight => local_list_right,
             Panels::Cloud => cloud_list,
+            Panels::Archive => archive_list,
         };
         let to_list = match to_panel {
             Panels::Local => local_list,
             Panels::LocalRight => local_list_right,
             Panels::Cloud => cloud_list,
+            Panels::Archive => archive_list,
         };
         let is_from_local = from_panel == Panels::Local || from_panel == Panels::LocalRight;
         let is_to_local = to_panel == Panels::Local || to_panel == Panels::LocalRight;
+        let mounted = *mounted_archive.read();
+        let is_from_archive = from_panel == Panels::Archive;
+        let is_to_archive = to_panel == Panels::Archive;
         let (from_dir, from_is_dir, fs_id) = {
             let list = from_list.read();
             (
@@ -664,17 +716,17 @@ pub fn Cloud() -> Element {
             | Actions::Download
             | Actions::ZipAndUpload
             | Actions::InstallWithFBI
-                if (!is_from_local
+                if (from_panel == Panels::Cloud
                     || action == Actions::Upload
                     || action == Actions::ZipAndUpload)
                     && (!Api::get_read().is_login() || !Api::is_eat_pancake_valid()) =>
             {
                 if !Api::get_read().is_login() {
                     auth_state.write().0 = false;
-                    toast("未登录，请重新登录！".to_string());
+                    toast(t!("common.not_logged_in").to_string());
                 } else {
                     confirm_visible.write().show_qrcode(
-                        INVALID_EAT_PANCAKE.to_string(),
+                        t!("common.invalid_eat_pancake").to_string(),
                         HOME_PAGE_URL.to_string(),
                         Rc::new(RefCell::new(Box::new(move || {}))),
                     );
@@ -687,34 +739,43 @@ pub fn Cloud() -> Element {
             Actions::NewDir => {
                 if let Some(input_name) = pl_show_swkbd(Kind::Normal, &resource, "") {
                     if from_list.read().is_exists(&input_name) {
-                        toast("文件夹已存在！".to_string());
+                        toast(t!("cloud.dir_exists").to_string());
                     } else {
                         loading.write().show();
                         tokio::task::spawn_blocking(move || {
                             let new_name_path = join_path(&from_dir, &input_name);
                             if is_from_local {
                                 if let Err(err) = fs::create_dir_all(&new_name_path) {
-                                    toast(format!("新建文件夹失败: {}", err));
+                                    toast(t!("cloud.new_dir_failed", err = err));
                                 } else {
                                     local_enter_dir(from_list, &from_dir, true);
                                     if from_dir == to_dir && is_to_local {
                                         local_enter_dir(to_list, &to_dir, true);
                                     }
-                                    toast("新建文件夹成功！".to_string());
+                                    toast(t!("cloud.new_dir_done").to_string());
+                                }
+                            } else if let Some(mounted) = mounted.filter(|_| is_from_archive) {
+                                if let Err(err) = archive_write(mounted, |arch| {
+                                    fsu::create_dir(arch, &new_name_path)
+                                }) {
+                                    toast(t!("cloud.new_dir_failed", err = err));
+                                } else {
+                                    archive_enter_dir(from_list, mounted, &from_dir, true).ok();
+                                    toast(t!("cloud.new_dir_done").to_string());
                                 }
                             } else {
                                 notify(
-                                    Some("正在新建文件夹".to_string()),
+                                    Some(t!("cloud.new_dir_running").to_string()),
                                     Some(input_name.clone()),
                                 );
                                 match Api::start_create_dir(&from_dir, &input_name) {
                                     Ok(_) => {
                                         cloud_enter_dir(from_list, &from_dir, true, auth_state)
                                             .ok();
-                                        toast("新建文件夹成功！".to_string());
+                                        toast(t!("cloud.new_dir_done").to_string());
                                     }
                                     Err(e) => {
-                                        toast(format!("新建文件夹失败: {}", e));
+                                        toast(t!("cloud.new_dir_failed", err = e));
                                     }
                                 }
                             }
@@ -722,7 +783,7 @@ pub fn Cloud() -> Element {
                         });
                     }
                 } else {
-                    toast("新建文件夹取消！".to_string());
+                    toast(t!("cloud.new_dir_cancelled").to_string());
                 }
             }
             Actions::Delete => {
@@ -734,16 +795,33 @@ pub fn Cloud() -> Element {
                         } else {
                             fs::remove_file(&join_path(&from_dir, &name))
                         } {
-                            toast(format!("删除失败: {}", err));
+                            toast(t!("common.delete_failed", err = err));
                         } else {
                             local_enter_dir(from_list, &from_dir, true);
                             if from_dir == to_dir && is_to_local {
                                 local_enter_dir(to_list, &to_dir, true);
                             }
-                            toast("删除成功！".to_string());
+                            toast(t!("common.delete_done").to_string());
+                        }
+                    } else if let Some(mounted) = mounted.filter(|_| is_from_archive) {
+                        let path = join_path(&from_dir, &name);
+                        if let Err(err) = archive_write(mounted, |arch| {
+                            if from_is_dir {
+                                fsu::remove_dir_all(arch, &path)
+                            } else {
+                                fsu::remove_file(arch, &path)
+                            }
+                        }) {
+                            toast(t!("common.delete_failed", err = err));
+                        } else {
+                            archive_enter_dir(from_list, mounted, &from_dir, true).ok();
+                            toast(t!("common.delete_done").to_string());
                         }
                     } else {
-                        notify(Some("正在删除".to_string()), Some(name.to_string()));
+                        notify(
+                            Some(t!("cloud.delete_running").to_string()),
+                            Some(name.to_string()),
+                        );
                         match Api::start_file_manager(
                             &utf8_percent_encode(&join_path(&from_dir, &name), NON_ALPHANUMERIC)
                                 .to_string(),
@@ -753,10 +831,10 @@ pub fn Cloud() -> Element {
                         ) {
                             Ok(_) => {
                                 cloud_enter_dir(from_list, &from_dir, true, auth_state).ok();
-                                toast("删除成功！".to_string());
+                                toast(t!("common.delete_done").to_string());
                             }
                             Err(e) => {
-                                toast(format!("删除失败: {}", e));
+                                toast(t!("common.delete_failed", err = e));
                             }
                         }
                     }
@@ -766,7 +844,7 @@ pub fn Cloud() -> Element {
             Actions::Rename => {
                 if let Some(input_name) = pl_show_swkbd(Kind::Normal, &resource, &name) {
                     if from_list.read().is_exists(&input_name) {
-                        toast("已存在同名文件！".to_string());
+                        toast(t!("cloud.file_exists").to_string());
                     } else {
                         loading.write().show();
                         tokio::task::spawn_blocking(move || {
@@ -775,16 +853,28 @@ pub fn Cloud() -> Element {
                                 if let Err(err) =
                                     fs::rename(&from_path, join_path(&from_dir, &input_name))
                                 {
-                                    toast(format!("重命名失败: {}", err));
+                                    toast(t!("cloud.rename_failed", err = err));
                                 } else {
                                     local_enter_dir(from_list, &from_dir, true);
                                     if from_dir == to_dir && is_to_local {
                                         local_enter_dir(to_list, &to_dir, true);
                                     }
-                                    toast("重命名成功！".to_string());
+                                    toast(t!("cloud.rename_done").to_string());
+                                }
+                            } else if let Some(mounted) = mounted.filter(|_| is_from_archive) {
+                                if let Err(err) = archive_write(mounted, |arch| {
+                                    fsu::rename(arch, &from_path, join_path(&from_dir, &input_name))
+                                }) {
+                                    toast(t!("cloud.rename_failed", err = err));
+                                } else {
+                                    archive_enter_dir(from_list, mounted, &from_dir, true).ok();
+                                    toast(t!("cloud.rename_done").to_string());
                                 }
                             } else {
-                                notify(Some("正在重命名".to_string()), Some(name.to_string()));
+                                notify(
+                                    Some(t!("cloud.rename_running").to_string()),
+                                    Some(name.to_string()),
+                                );
                                 match Api::start_file_manager(
                                     &utf8_percent_encode(&from_path, NON_ALPHANUMERIC).to_string(),
                                     None,
@@ -797,10 +887,10 @@ pub fn Cloud() -> Element {
                                     Ok(_) => {
                                         cloud_enter_dir(from_list, &from_dir, true, auth_state)
                                             .ok();
-                                        toast("重命名成功！".to_string());
+                                        toast(t!("cloud.rename_done").to_string());
                                     }
                                     Err(e) => {
-                                        toast(format!("重命名失败: {}", e));
+                                        toast(t!("cloud.rename_failed", err = e));
                                     }
                                 }
                             }
@@ -808,7 +898,7 @@ pub fn Cloud() -> Element {
                         });
                     }
                 } else {
-                    toast("重命名取消！".to_string());
+                    toast(t!("cloud.rename_cancelled").to_string());
                 }
             }
             Actions::Copy => {
@@ -816,7 +906,7 @@ pub fn Cloud() -> Element {
                     loading.write().show();
                     tokio::task::spawn_blocking(move || {
                         if to_list.read().is_exists(&name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
                             if let Err(err) = if from_is_dir {
                                 copy_dir_all(
@@ -826,11 +916,53 @@ pub fn Cloud() -> Element {
                             } else {
                                 copy_file(&join_path(&from_dir, &name), &join_path(&to_dir, &name))
                             } {
-                                toast(format!("复制失败: {}", err));
+                                toast(t!("cloud.copy_failed", err = err));
                             } else {
                                 local_enter_dir(from_list, &from_dir, true);
                                 local_enter_dir(to_list, &to_dir, true);
-                                toast("复制成功！".to_string());
+                                toast(t!("cloud.copy_done").to_string());
+                            }
+                        }
+                        loading.write().hide();
+                    });
+                } else if let Some(mounted) = mounted.filter(|_| is_from_archive || is_to_archive) {
+                    loading.write().show();
+                    tokio::task::spawn_blocking(move || {
+                        if to_list.read().is_exists(&name) {
+                            toast(t!("cloud.file_exists").to_string());
+                        } else {
+                            notify(
+                                Some(t!("cloud.copy_running").to_string()),
+                                Some(name.to_string()),
+                            );
+                            let from_path = join_path(&from_dir, &name);
+                            let to_path = join_path(&to_dir, &name);
+                            let copy = |from: &fsu::Archive, to: &fsu::Archive| {
+                                if from_is_dir {
+                                    fsu::copy_dir_all((&from_path, from), (&to_path, to))
+                                } else {
+                                    fsu::copy((&from_path, from), (&to_path, to))
+                                }
+                                .map(|_| ())
+                            };
+                            if let Err(err) = fsu::arch(ArchiveID::Sdmc, MediaType::Sd, 0, 0)
+                                .map_err(|err| AppError::archive_open(ArchiveID::Sdmc, err))
+                                .and_then(|sdmc| {
+                                    if is_to_archive {
+                                        archive_write(mounted, |arch| copy(&sdmc, arch))
+                                    } else {
+                                        Ok(copy(&archive_open(mounted)?, &sdmc)?)
+                                    }
+                                })
+                            {
+                                toast(t!("cloud.copy_failed", err = err));
+                            } else {
+                                if is_to_archive {
+                                    archive_enter_dir(to_list, mounted, &to_dir, true).ok();
+                                } else {
+                                    local_enter_dir(to_list, &to_dir, true);
+                                }
+                                toast(t!("cloud.copy_done").to_string());
                             }
                         }
                         loading.write().hide();
@@ -842,16 +974,16 @@ pub fn Cloud() -> Element {
                     loading.write().show();
                     tokio::task::spawn_blocking(move || {
                         if to_list.read().is_exists(&name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
                             if let Err(err) =
                                 fs::rename(join_path(&from_dir, &name), join_path(&to_dir, &name))
                             {
-                                toast(format!("移动失败: {}", err));
+                                toast(t!("cloud.move_failed", err = err));
                             } else {
                                 local_enter_dir(from_list, &from_dir, true);
                                 local_enter_dir(to_list, &to_dir, true);
-                                toast("移动成功！".to_string());
+                                toast(t!("cloud.move_done").to_string());
                             }
                         }
                         loading.write().hide();
@@ -863,17 +995,24 @@ pub fn Cloud() -> Element {
                     loading.write().show();
                     tokio::task::spawn_blocking(move || {
                         if to_list.read().is_exists(&name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
-                            notify(Some("正在上传".to_string()), Some(name.to_string()));
+                            notify(
+                                Some(t!("common.uploading").to_string()),
+                                Some(name.to_string()),
+                            );
                             let from_path = join_path(&from_dir, &name);
-                            if let Err(err) =
+                            progress::start(ProgressStage::Upload, 1, 0);
+                            if let Err(err) = with_network_retry(|| {
                                 Api::upload_to_cloud(&to_dir, &name, &from_path, false, notify)
-                            {
-                                toast(format!("上传失败: {}", err));
+                            }) {
+                                if err.needs_reauth() {
+                                    AuthState::expire(auth_state);
+                                }
+                                toast(t!("cloud.upload_failed", err = err));
                             } else {
                                 cloud_enter_dir(to_list, &to_dir, true, auth_state).ok();
-                                toast("上传成功！".to_string());
+                                toast(t!("cloud.upload_done").to_string());
                             }
                         }
                         loading.write().hide();
@@ -886,19 +1025,28 @@ pub fn Cloud() -> Element {
                     loading.write().show();
                     tokio::task::spawn_blocking(move || {
                         if to_list.read().is_exists(&name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
-                            notify(Some("正在下载".to_string()), Some(name.to_string()));
-                            if let Err(err) = Api::start_download(
-                                fs_id,
-                                &join_path(&to_dir, &name),
-                                Some(progress),
-                            ) {
+                            notify(
+                                Some(t!("common.downloading").to_string()),
+                                Some(name.to_string()),
+                            );
+                            progress::start(ProgressStage::Download, 1, 0);
+                            if let Err(err) = with_network_retry(|| {
+                                Api::start_download(
+                                    fs_id,
+                                    &join_path(&to_dir, &name),
+                                    Some(progress),
+                                )
+                            }) {
+                                if err.needs_reauth() {
+                                    AuthState::expire(auth_state);
+                                }
                                 local_enter_dir(to_list, &to_dir, true);
-                                toast(format!("下载失败: {}", err));
+                                toast(t!("cloud.download_failed", err = err));
                             } else {
                                 local_enter_dir(to_list, &to_dir, true);
-                                toast("下载成功！".to_string());
+                                toast(t!("cloud.download_done").to_string());
                             }
                         }
                         loading.write().hide();
@@ -915,21 +1063,27 @@ pub fn Cloud() -> Element {
                         Some(new_name)
                     } {
                         if from_list.read().is_exists(&zip_name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
                             loading.write().show();
                             tokio::task::spawn_blocking(move || {
                                 let zip_name_path = join_path(&from_dir, &zip_name);
-                                notify(Some("正在压缩".to_string()), Some(name.to_string()));
+                                notify(
+                                    Some(t!("cloud.zip_running").to_string()),
+                                    Some(name.to_string()),
+                                );
                                 if let Err(err) = if from_is_dir {
                                     match fsu::arch(ArchiveID::Sdmc, MediaType::Sd, 0, 0) {
                                         Ok(arch) => zip_dir(
                                             (&join_path(&from_dir, &name), &arch),
                                             (&zip_name_path, &arch),
                                             &[],
+                                            None,
                                             notify,
                                         ),
-                                        Err(err) => Err(format!("{}", err).into()),
+                                        Err(err) => {
+                                            Err(AppError::archive_open(ArchiveID::Sdmc, err))
+                                        }
                                     }
                                 } else {
                                     zip_file(&from_dir, &name, &zip_name_path)
@@ -937,13 +1091,13 @@ pub fn Cloud() -> Element {
                                     if Path::new(&zip_name_path).exists() {
                                         fs::remove_file(&zip_name_path).ok();
                                     }
-                                    toast(format!("压缩失败: {}", err));
+                                    toast(t!("cloud.zip_failed", err = err));
                                 } else {
                                     local_enter_dir(from_list, &from_dir, true);
                                     if from_dir == to_dir {
                                         local_enter_dir(to_list, &to_dir, true);
                                     }
-                                    toast("压缩成功！".to_string());
+                                    toast(t!("cloud.zip_done").to_string());
                                 }
                                 loading.write().hide();
                             });
@@ -960,29 +1114,35 @@ pub fn Cloud() -> Element {
                         Some(new_name)
                     } {
                         if from_list.read().is_exists(&new_name) {
-                            toast("已存在同名文件！".to_string());
+                            toast(t!("cloud.file_exists").to_string());
                         } else {
                             loading.write().show();
                             tokio::task::spawn_blocking(move || {
-                                notify(Some("正在解压".to_string()), Some(name.to_string()));
+                                notify(
+                                    Some(t!("cloud.unzip_running").to_string()),
+                                    Some(name.to_string()),
+                                );
                                 let new_name_path = join_path(&from_dir, &new_name);
                                 if let Err(err) =
                                     match fsu::arch(ArchiveID::Sdmc, MediaType::Sd, 0, 0) {
                                         Ok(arch) => zip_extract(
                                             (&join_path(&from_dir, &name), &arch),
                                             (&new_name_path, &arch),
+                                            &[],
                                             notify,
                                         ),
-                                        Err(err) => Err(format!("{}", err).into()),
+                                        Err(err) => {
+                                            Err(AppError::archive_open(ArchiveID::Sdmc, err))
+                                        }
                                     }
                                 {
-                                    toast(format!("解压失败: {}", err));
+                                    toast(t!("cloud.unzip_failed", err = err));
                                 } else {
                                     local_enter_dir(from_list, &from_dir, true);
                                     if from_dir == to_dir {
                                         local_enter_dir(to_list, &to_dir, true);
                                     }
-                                    toast("解压成功！".to_string());
+                                    toast(t!("cloud.unzip_done").to_string());
                                 }
                                 loading.write().hide();
                             });
@@ -994,34 +1154,49 @@ pub fn Cloud() -> Element {
                 if is_from_local && !is_to_local {
                     let zip_name = format!("{}.zip", name);
                     if to_list.read().is_exists(&zip_name) {
-                        toast("已存在同名文件！".to_string());
+                        toast(t!("cloud.file_exists").to_string());
                     } else {
                         let zip_path =
                             join_path(&from_dir, &format!("{}.zip", get_current_format_time()));
                         loading.write().show();
                         tokio::task::spawn_blocking(move || {
-                            notify(Some("正在压缩".to_string()), Some(name.to_string()));
+                            notify(
+                                Some(t!("cloud.zip_running").to_string()),
+                                Some(name.to_string()),
+                            );
                             if let Err(err) = (if from_is_dir {
                                 match fsu::arch(ArchiveID::Sdmc, MediaType::Sd, 0, 0) {
                                     Ok(arch) => zip_dir(
                                         (&join_path(&from_dir, &name), &arch),
                                         (&zip_path, &arch),
                                         &[],
+                                        None,
                                         notify,
                                     ),
-                                    Err(err) => Err(format!("{}", err).into()),
+                                    Err(err) => Err(AppError::archive_open(ArchiveID::Sdmc, err)),
                                 }
                             } else {
                                 zip_file(&from_dir, &name, &zip_path)
                             })
-                            .map(|_| {
-                                notify(Some("正在上传".to_string()), Some(zip_path.to_string()));
-                                Api::upload_to_cloud(&to_dir, &zip_name, &zip_path, false, notify)
+                            .and_then(|_| {
+                                notify(
+                                    Some(t!("common.uploading").to_string()),
+                                    Some(zip_path.to_string()),
+                                );
+                                progress::start(ProgressStage::Upload, 1, 0);
+                                with_network_retry(|| {
+                                    Api::upload_to_cloud(
+                                        &to_dir, &zip_name, &zip_path, false, notify,
+                                    )
+                                })
                             }) {
-                                toast(format!("压缩上传失败: {}", err));
+                                if err.needs_reauth() {
+                                    AuthState::expire(auth_state);
+                                }
+                                toast(t!("cloud.zip_and_upload_failed", err = err));
                             } else {
                                 cloud_enter_dir(to_list, &to_dir, true, auth_state).ok();
-                                toast("压缩上传成功！".to_string());
+                                toast(t!("cloud.zip_and_upload_done").to_string());
                             }
                             if Path::new(&zip_path).exists() {
                                 fs::remove_file(&zip_path).ok();
@@ -1043,7 +1218,7 @@ pub fn Cloud() -> Element {
                                 if let Err(err) = fs::File::create(&path)
                                     .map(|mut file| file.write_all(link.as_bytes()))
                                 {
-                                    toast(format!("调用 FBI 失败: {}", err));
+                                    toast(t!("cloud.fbi_failed_with", err = err));
                                 } else {
                                     if pl_is_homebrew() {
                                         // save cloud path
@@ -1080,10 +1255,10 @@ pub fn Cloud() -> Element {
                                                 *app_exit_inner.lock().unwrap() = (1, None);
                                             } else {
                                                 remove_recovery_data();
-                                                toast("调用 FBI 失败".to_string());
+                                                toast(t!("cloud.fbi_failed").to_string());
                                             }
                                         } else {
-                                            toast("未找到 FBI: /3ds/fbi-sc.3dsx".to_string());
+                                            toast(t!("cloud.fbi_not_found").to_string());
                                         }
                                     } else {
                                         if pl_is_fbi_title_exists() {
@@ -1097,13 +1272,13 @@ pub fn Cloud() -> Element {
                                             *app_exit_inner.lock().unwrap() =
                                                 (FBI_SC_TITLE_ID, Some((MediaType::Sd, path)));
                                         } else {
-                                            toast("未安装 FBI".to_string());
+                                            toast(t!("cloud.fbi_not_installed").to_string());
                                         }
                                     }
                                 }
                             }
                             Err(err) => {
-                                toast(format!("获取下载链接失败: {}", err));
+                                toast(t!("cloud.download_link_failed", err = err));
                             }
                         }
                         loading.write().hide();
@@ -1146,6 +1321,10 @@ pub fn Cloud() -> Element {
                             local_list_right.with_mut(|list| {
                                 list.list_do_scroll(ScrollAction::Up);
                             });
+                        } else if panel == Panels::Archive {
+                            archive_list.with_mut(|list| {
+                                list.list_do_scroll(ScrollAction::Up);
+                            });
                         } else {
                             cloud_list.with_mut(|list| {
                                 list.list_do_scroll(ScrollAction::Up);
@@ -1161,6 +1340,10 @@ pub fn Cloud() -> Element {
                             local_list_right.with_mut(|list| {
                                 list.list_do_scroll(ScrollAction::Down);
                             });
+                        } else if panel == Panels::Archive {
+                            archive_list.with_mut(|list| {
+                                list.list_do_scroll(ScrollAction::Down);
+                            });
                         } else {
                             cloud_list.with_mut(|list| {
                                 list.list_do_scroll(ScrollAction::Down);
@@ -1168,32 +1351,51 @@ pub fn Cloud() -> Element {
                         }
                     }
                     Code::KeyY => {
-                        if *right_panel.read() == Panels::LocalRight {
-                            right_panel.set(Panels::Cloud);
-                            if panel != Panels::Local {
-                                selected_panel.set((Panels::Cloud, Instant::now()));
-                            }
+                        // the archive panel is skipped if no title with a user, ext or boss save is selected
+                        let mount = title_selected.read().as_ref().and_then(|selected| {
+                            selected
+                                .save_type
+                                .filter(|&t| t != SaveTypes::Sys)
+                                .or(selected.saves.iter().copied().find(|&t| t != SaveTypes::Sys))
+                                .map(|save_type| (selected.title, save_type))
+                        });
+                        let next = match *right_panel.read() {
+                            Panels::LocalRight => Panels::Cloud,
+                            Panels::Cloud if mount.is_some() => Panels::Archive,
+                            _ => Panels::LocalRight,
+                        };
+                        right_panel.set(next);
+                        if panel != Panels::Local {
+                            selected_panel.set((next, Instant::now()));
+                        }
+                        if next == Panels::Cloud {
                             if cloud_list.read().is_not_init() {
                                 loading.write().show();
                                 tokio::task::spawn_blocking(move || {
                                     if let Err(err) = cloud_enter_dir(cloud_list, "/", false, auth_state) {
-                                        toast(format!("获取云端文件列表失败: {}", err));
+                                        toast(t!("cloud.list_failed", err = err));
                                     }
                                     loading.write().hide();
                                 });
                             }
-                        } else {
-                            right_panel.set(Panels::LocalRight);
-                            if panel != Panels::Local {
-                                selected_panel.set((Panels::LocalRight, Instant::now()));
-                            }
-                            if local_list_right.read().is_not_init() {
+                        } else if let Some(mounted) = mount.filter(|_| next == Panels::Archive) {
+                            if *mounted_archive.peek() != mount || archive_list.read().is_not_init() {
+                                mounted_archive.set(mount);
+                                archive_list.set(List::new());
                                 loading.write().show();
                                 tokio::task::spawn_blocking(move || {
-                                    local_enter_dir(local_list_right, "/", false);
+                                    if let Err(err) = archive_enter_dir(archive_list, mounted, "/", false) {
+                                        toast(t!("cloud.list_failed", err = err));
+                                    }
                                     loading.write().hide();
                                 });
                             }
+                        } else if local_list_right.read().is_not_init() {
+                            loading.write().show();
+                            tokio::task::spawn_blocking(move || {
+                                local_enter_dir(local_list_right, "/", false);
+                                loading.write().hide();
+                            });
                         }
                     }
                     Code::KeyA => {
@@ -1202,6 +1404,7 @@ pub fn Cloud() -> Element {
                                 Panels::Local => local_list,
                                 Panels::LocalRight => local_list_right,
                                 Panels::Cloud => cloud_list,
+                                Panels::Archive => archive_list,
                             };
                             let r = list.read();
                             if !r.is_selected_item_dir() {
@@ -1210,13 +1413,18 @@ pub fn Cloud() -> Element {
                             (list, r.current_selected_abs_path())
                         };
                         loading.write().show();
+                        let mounted = *mounted_archive.peek();
                         tokio::task::spawn_blocking(move || {
-                            if panel != Panels::Cloud {
-                                local_enter_dir(list, &path, false);
-                            } else {
+                            if panel == Panels::Cloud {
                                 if let Err(err) = cloud_enter_dir(list, &path, false, auth_state) {
-                                    toast(format!("获取云端文件列表失败: {}", err));
+                                    toast(t!("cloud.list_failed", err = err));
+                                }
+                            } else if let Some(mounted) = mounted.filter(|_| panel == Panels::Archive) {
+                                if let Err(err) = archive_enter_dir(list, mounted, &path, false) {
+                                    toast(t!("cloud.list_failed", err = err));
                                 }
+                            } else {
+                                local_enter_dir(list, &path, false);
                             }
                             loading.write().hide();
                         });
@@ -1226,12 +1434,18 @@ pub fn Cloud() -> Element {
                             Panels::Local => local_list,
                             Panels::LocalRight => local_list_right,
                             Panels::Cloud => cloud_list,
+                            Panels::Archive => archive_list,
                         };
-                        if list.write().pop() && (panel == Panels::Local || panel == Panels::LocalRight) {
+                        if list.write().pop() && panel != Panels::Cloud {
+                            let mounted = *mounted_archive.peek();
                             loading.write().show();
                             tokio::task::spawn_blocking(move || {
                                 let path = list.read().current_abs_path();
-                                local_enter_dir(list, &path, true);
+                                if let Some(mounted) = mounted.filter(|_| panel == Panels::Archive) {
+                                    archive_enter_dir(list, mounted, &path, true).ok();
+                                } else {
+                                    local_enter_dir(list, &path, true);
+                                }
                                 loading.write().hide();
                             });
                         }
@@ -1241,12 +1455,13 @@ pub fn Cloud() -> Element {
                             Panels::Local => local_list.read(),
                             Panels::LocalRight => local_list_right.read(),
                             Panels::Cloud => cloud_list.read(),
+                            Panels::Archive => archive_list.read(),
                         };
                         // 列表为空，只有新建文件夹
                         if !list.is_not_init() && list.total_items() == 0 {
                             let actions_list = vec![Actions::NewDir];
                             menu_list_state.write().update(actions_list.len() as i32);
-                            actions.set(Some((actions_list, String::new(), "空文件夹".to_string())));
+                            actions.set(Some((actions_list, String::new(), t!("cloud.empty_dir").to_string())));
                         } else {
                             list.selected_item().map(|item| {
                                 let actions_list = match &item {
@@ -1260,6 +1475,8 @@ pub fn Cloud() -> Element {
                                         if right_panel.try_read().is_ok_and(|r| *r == Panels::LocalRight) {
                                             res.push(Actions::Copy);
                                             res.push(Actions::Move);
+                                        } else if right_panel.try_read().is_ok_and(|r| *r == Panels::Archive) {
+                                            res.push(Actions::Copy);
                                         } else {
                                             if !*is_dir {
                                                 res.push(Actions::Upload);
@@ -1271,14 +1488,14 @@ pub fn Cloud() -> Element {
                                             }
                                         }
                                         if *is_dir {
-                                            (res, name.to_string(), "文件夹".to_string())
+                                            (res, name.to_string(), t!("cloud.dir").to_string())
                                         } else {
                                             let abs_path = list.current_selected_abs_path();
                                             if let Ok(size) = fs::File::open(abs_path).and_then(|f| f.metadata()).map(|m| m.size()) {
                                                 let (p, unit) = storage_size_to_info(size as f64);
-                                                (res, name.to_string(), format!("文件：{:.2} {}", size as f64 / p, unit))
+                                                (res, name.to_string(), t!("cloud.file_size", size = format!("{:.2}", size as f64 / p), unit = unit))
                                             } else {
-                                                (res, name.to_string(), "文件".to_string())
+                                                (res, name.to_string(), t!("cloud.file").to_string())
                                             }
                                         }
                                     }
@@ -1291,10 +1508,24 @@ pub fn Cloud() -> Element {
                                             }
                                         }
                                         if *is_dir {
-                                            (res, name.to_string(), "文件夹".to_string())
+                                            (res, name.to_string(), t!("cloud.dir").to_string())
                                         } else {
                                             let (p, unit) = storage_size_to_info(*size as f64);
-                                            (res, name.to_string(), format!("文件：{:.2} {}", *size as f64 / p, unit))
+                                            (res, name.to_string(), t!("cloud.file_size", size = format!("{:.2}", *size as f64 / p), unit = unit))
+                                        }
+                                    }
+                                    ChildItem::Archive(name, is_dir) => {
+                                        let res = vec![Actions::NewDir, Actions::Rename, Actions::Delete, Actions::Copy];
+                                        if *is_dir {
+                                            (res, name.to_string(), t!("cloud.dir").to_string())
+                                        } else {
+                                            let abs_path = join_path(&list.current_abs_path(), name);
+                                            if let Some(size) = (*mounted_archive.peek()).and_then(|mounted| archive_open(mounted).ok()).and_then(|arch| fsu::metadata(&arch, abs_path).ok()).map(|m| m.len()) {
+                                                let (p, unit) = storage_size_to_info(size as f64);
+                                                (res, name.to_string(), t!("cloud.file_size", size = format!("{:.2}", size as f64 / p), unit = unit))
+                                            } else {
+                                                (res, name.to_string(), t!("cloud.file").to_string())
+                                            }
                                         }
                                     }
                                 };
@@ -1403,6 +1634,36 @@ pub fn Cloud() -> Element {
                                     NoData {}
                                 }
                             }
+                        } else if *right_panel == Panels::Archive {
+                            if let Ok(list) = archive_list.try_read() {
+                                if list.total_items() > 0 {
+                                    for idx in 0i32..12i32 {
+                                        if let Some(item) = list.get(idx as usize) {
+                                            div {
+                                                display: "flex",
+                                                align_items: "center",
+                                                height: 18.83,
+                                                padding_left: 1.0,
+                                                background_color: if list.is_selected(idx) { "green" } else { "selected_bg_dark" },
+
+                                                div {
+                                                    flex: 1,
+                                                    display: "flex",
+                                                    align_items: "center",
+                                                    height: 16.83,
+                                                    padding_left: 5.0,
+                                                    padding_top: 1.0,
+                                                    color: if item.is_dir() { "dir" } else { "main-text" },
+                                                    background_color: "selected_bg_dark",
+                                                    "{item.as_ref()}"
+                                                }
+                                            }
+                                        }
+                                    }
+                                } else if !list.is_not_init() {
+                                    NoData {}
+                                }
+                            }
                         } else if let Ok(list) = cloud_list.try_read() {
                             if auth_state.read().0 {
                                 if list.total_items() > 0 {
@@ -1473,13 +1734,13 @@ pub fn Cloud() -> Element {
                             let (p, unit) = storage_size_to_info(total);
                             (free as f64 / p, total as f64 / p, unit)
                         }) {
-                            "本地: {free:.2} / {total:.2} {unit}"
+                            {t!("cloud.storage_local", free = format!("{free:.2}"), total = format!("{total:.2}"), unit = unit)}
                         }
                     } else if let Some((free, total, unit)) = storage_info.read().1.map(|(free, total)| {
                             let (p, unit) = storage_size_to_info(total);
                             (free as f64 / p, total as f64 / p, unit)
                         }) {
-                            "云盘: {free:.2} / {total:.2} {unit}"
+                            {t!("cloud.storage_cloud", free = format!("{free:.2}"), total = format!("{total:.2}"), unit = unit)}
                     }
 
                 }
@@ -1499,7 +1760,7 @@ pub fn Cloud() -> Element {
                     max_width: SCREEN_BOTTOM_WIDTH as f64 - 12.0,
 
                     if let Ok(list) = local_list.try_read() {
-                        "左：本地  → {list.current_idx()} / {list.total_items()}"
+                        {t!("cloud.header_left_local", idx = list.current_idx(), total = list.total_items())}
 
                         div {
                             margin_top: 5.0,
@@ -1518,7 +1779,16 @@ pub fn Cloud() -> Element {
 
                         if *panel == Panels::LocalRight {
                             if let Ok(list) = local_list_right.try_read() {
-                                "右：本地  → {list.current_idx()} / {list.total_items()}"
+                                {t!("cloud.header_right_local", idx = list.current_idx(), total = list.total_items())}
+
+                                div {
+                                    margin_top: 5.0,
+                                    "{list.current_abs_path()}"
+                                }
+                            }
+                        } else if *panel == Panels::Archive {
+                            if let (Ok(list), Some((title, save_type))) = (archive_list.try_read(), *mounted_archive.read()) {
+                                {t!("cloud.header_right_archive", save_type = save_type, id = title.id_hex_str(), idx = list.current_idx(), total = list.total_items())}
 
                                 div {
                                     margin_top: 5.0,
@@ -1527,7 +1797,7 @@ pub fn Cloud() -> Element {
                             }
                         } else {
                             if let Ok(list) = cloud_list.try_read() {
-                               "右：云盘  → {list.current_idx()} / {list.total_items()}"
+                               {t!("cloud.header_right_cloud", idx = list.current_idx(), total = list.total_items())}
 
                                 div {
                                     margin_top: 5.0,
@@ -1541,7 +1811,7 @@ pub fn Cloud() -> Element {
 
             ActionBar {
                 version: false,
-                tips: "(START) 退出   (X) 操作   (Y) 切换   (B) 返回   (A) 选择",
+                tips: t!("cloud.tips"),
                 onkeypress: move |e: KeyboardEvent| {
                     if is_pending() || app_exit.is_exit() {
                         return;
@@ -10,6 +10,7 @@ use crate::app::{
     dialog::DialogVisible,
     list_wrap_display_status::ScrollAction,
 };
+use crate::t;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct MenuProps {
@@ -136,7 +137,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
             }
 
             ActionBar {
-                tips: "(B) 关闭   (A) 选择"
+                tips: t!("common.tips_close_select")
             }
         }
     }
@@ -5,6 +5,7 @@ use dioxus::prelude::*;
 use crate::{
     app::{button::Button, line::Line},
     constant::{SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT, SELECTED_BG_COLOR},
+    t,
 };
 
 use super::dialog::{use_dialog, DialogVisible};
@@ -13,6 +14,7 @@ pub struct ConfirmState {
     title: String,
     qrcode: Option<String>,
     on_confirm: Rc<RefCell<Box<dyn FnMut()>>>,
+    on_cancel: Rc<RefCell<Box<dyn FnMut()>>>,
 }
 
 #[derive(Props, Clone, PartialEq)]
@@ -29,11 +31,22 @@ pub struct ConfirmVisible {
 
 impl ConfirmVisible {
     pub fn show(&mut self, title: String, on_confirm: Rc<RefCell<Box<dyn FnMut()>>>) {
+        self.show_with_cancel(title, on_confirm, Rc::new(RefCell::new(Box::new(|| {}))));
+    }
+
+    /// `on_cancel` runs if the confirm is canceled
+    pub fn show_with_cancel(
+        &mut self,
+        title: String,
+        on_confirm: Rc<RefCell<Box<dyn FnMut()>>>,
+        on_cancel: Rc<RefCell<Box<dyn FnMut()>>>,
+    ) {
         self.dialog.write().show();
         self.confirm.with_mut(|confirm| {
             confirm.title = title;
             confirm.qrcode = None;
             confirm.on_confirm = on_confirm;
+            confirm.on_cancel = on_cancel;
         });
         *self.action_fired.write() = false;
     }
@@ -49,6 +62,7 @@ impl ConfirmVisible {
             confirm.title = title;
             confirm.qrcode = Some(qrcode);
             confirm.on_confirm = on_confirm;
+            confirm.on_cancel = Rc::new(RefCell::new(Box::new(|| {})));
         });
         *self.action_fired.write() = false;
     }
@@ -56,6 +70,15 @@ impl ConfirmVisible {
     pub fn hide(&mut self) {
         self.dialog.write().hide();
     }
+
+    /// title of the confirm, `None` if it is not shown
+    pub fn title(&self) -> Option<String> {
+        if self.dialog.read().is_show() {
+            Some(self.confirm.read().title.clone())
+        } else {
+            None
+        }
+    }
 }
 
 pub fn use_confirm() -> Signal<ConfirmVisible> {
@@ -74,6 +97,7 @@ pub fn use_confirm() -> Signal<ConfirmVisible> {
             title: "".to_string(),
             qrcode: None,
             on_confirm: Rc::new(RefCell::new(Box::new(|| {}))),
+            on_cancel: Rc::new(RefCell::new(Box::new(|| {}))),
         }),
     };
     use_signal(move || res)
@@ -86,6 +110,11 @@ pub fn Confirm(mut props: ConfirmProps) -> Element {
         }
         *props.visible.write().action_fired.write() = true;
         props.visible.write().dialog.write().hide();
+        {
+            let mut confirm = props.visible.peek().confirm;
+            let f = confirm.write().on_cancel.clone();
+            f.borrow_mut()();
+        }
     };
 
     let mut confirm = move || {
@@ -167,7 +196,7 @@ pub fn Confirm(mut props: ConfirmProps) -> Element {
                             cancel();
                         },
 
-                        "(B) 取消"
+                        {t!("confirm.cancel")}
                     }
 
                     div {
@@ -186,7 +215,7 @@ pub fn Confirm(mut props: ConfirmProps) -> Element {
                         onclick: move |_| {
                             confirm();
                         },
-                        "(A) 确定"
+                        {t!("confirm.ok")}
                     }
                 }
             }
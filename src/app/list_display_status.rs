@@ -54,3 +54,56 @@ impl ListState {
         self.selected_idx = idx;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_wraps_and_keeps_selected_visible() {
+        let mut state = ListState::new(3);
+        state.do_scroll(5, ScrollAction::Up);
+        assert_eq!((state.selected_idx, state.top_row), (4, 2));
+        state.do_scroll(5, ScrollAction::Down);
+        assert_eq!((state.selected_idx, state.top_row), (0, 0));
+        for _ in 0..3 {
+            state.do_scroll(5, ScrollAction::Down);
+        }
+        assert_eq!((state.selected_idx, state.top_row), (3, 1));
+        // other actions only check the scope
+        state.do_scroll(2, ScrollAction::Left);
+        assert_eq!((state.selected_idx, state.top_row), (0, 0));
+    }
+
+    #[test]
+    fn scroll_empty() {
+        let mut state = ListState::new(3);
+        state.do_scroll(0, ScrollAction::Up);
+        assert_eq!((state.selected_idx, state.top_row), (0, 0));
+        state.do_scroll(0, ScrollAction::Down);
+        assert_eq!((state.selected_idx, state.top_row), (0, 0));
+    }
+
+    #[test]
+    fn update_clamps_selected() {
+        let mut state = ListState::new(3);
+        state.set_selected_idx(7);
+        state.update(5);
+        assert_eq!(state.selected_idx, 4);
+        state.set_selected_idx(-1);
+        state.update(5);
+        assert_eq!(state.selected_idx, 0);
+    }
+
+    #[test]
+    fn serialize() {
+        let mut state = ListState::new(9);
+        state.set_selected_idx(2);
+        let state: ListState =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(
+            (state.top_row, state.selected_idx, state.display_row),
+            (0, 2, 9)
+        );
+    }
+}
@@ -49,7 +49,7 @@ impl ListDisplayStatus {
                     if self.selected_idx >= size {
                         self.selected_idx = size - 1;
                     }
-                    self.top_row = if rows >= self.row { rows - self.row } else { 0 };
+                    self.top_row = rows.saturating_sub(self.row);
                 } else if self.selected_idx >= self.column {
                     self.selected_idx -= self.column;
                     // scroll down
@@ -6,10 +6,52 @@ use dioxus::prelude::*;
 
 use crate::{
     c2d::rgba,
+    cancel,
     constant::{SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT},
-    utils::{ease_out_expo, sleep_micros},
+    progress::{self, ProgressEvent},
+    t,
+    utils::{ease_out_expo, format_size, sleep_micros},
 };
 
+/// width of the progress bar
+const PROGRESS_BAR_WIDTH: f64 = 250.0;
+/// frames between updates of the progress, text changing every frame is hard to read
+const PROGRESS_UPDATE_FRAMES: u32 = 15;
+
+fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// `压缩 3/10 45.00%`
+fn progress_summary(event: &ProgressEvent) -> String {
+    let mut res = event.stage.to_string();
+    if event.count > 0 {
+        res += &format!(" {}/{}", event.item, event.count);
+    } else if event.item > 0 {
+        res += &format!(" {}", event.item);
+    }
+    if let Some(percent) = event.percent() {
+        res += &format!(" {:.2}%", percent);
+    }
+    res
+}
+
+/// `1.20 M/3.40 M 256.00 K/s 剩余 00:12`
+fn progress_detail(event: &ProgressEvent) -> String {
+    let mut res = format_size(event.bytes_done);
+    if event.bytes_total > 0 {
+        res += &format!("/{}", format_size(event.bytes_total));
+    }
+    if event.throughput > 0.0 {
+        res += &format!(" {}/s", format_size(event.throughput as u64));
+    }
+    if let Some(eta) = event.eta {
+        res += &t!("loading.eta", eta = format_eta(eta));
+    }
+    res
+}
+
 #[derive(Clone)]
 pub struct PageLoadingVisible {
     pub visible: bool,
@@ -34,6 +76,9 @@ impl PageLoadingVisible {
     pub fn show(&mut self) {
         self.at = Instant::now();
         self.visible = true;
+        // a new operation starts
+        cancel::reset();
+        progress::clear();
     }
 
     pub fn show_info(&mut self, title: Option<String>, desc: Option<String>) {
@@ -51,6 +96,7 @@ impl PageLoadingVisible {
         self.title = None;
         self.desc = None;
         self.download_progress.write().take();
+        progress::clear();
     }
 
     pub fn is_show(&self) -> bool {
@@ -169,8 +215,19 @@ pub struct PageLoadingProps {
 }
 
 pub fn PageLoading(mut props: PageLoadingProps) -> Element {
+    let mut progress_event = use_signal(|| None::<ProgressEvent>);
+
     use_future(move || async move {
+        let mut frame = 0;
         loop {
+            frame += 1;
+            if frame % PROGRESS_UPDATE_FRAMES == 0 {
+                let event = progress::current();
+                if *progress_event.peek() != event {
+                    progress_event.set(event);
+                }
+            }
+
             if let Some(PageLoadingVisible {
                 visible,
                 from_top,
@@ -231,7 +288,100 @@ pub fn PageLoading(mut props: PageLoadingProps) -> Element {
                 height: 36.0,
             }
 
-            if let Some((progress, current, total, unit)) = download_progress
+            // cancel the operation with (B)
+            div {
+                position: "absolute",
+                onkeypress: move |e| {
+                    if e.data.code() != Code::KeyB || !props.visible.peek().visible() || cancel::is_cancelled() {
+                        return;
+                    }
+                    cancel::cancel();
+                    if let Ok(mut visible) = props.visible.try_write() {
+                        visible.show_info(Some(t!("loading.cancelling").to_string()), None);
+                    }
+                },
+            }
+
+            if let Some(event) = progress_event.read().clone() {
+                div {
+                    display: "flex",
+                    align_items: "center",
+                    justify_content: "center",
+                    position: "absolute",
+                    left: 0.0,
+                    top: 0.0,
+                    width: SCREEN_BOTTOM_WIDTH as f64,
+                    height: SCREEN_HEIGHT as f64,
+
+                    div {
+                        display: "flex",
+                        flex_direction: "column",
+                        width: 260.0,
+                        height: 120.0,
+                        color: "white",
+                        background_color: "selected_bg",
+                        padding: 5.0,
+
+                        if let Some(title) = title {
+                            div {
+                                "{title}"
+                            }
+                        }
+
+                        if let Some(desc) = desc {
+                            div {
+                                color: "tips",
+                                "{desc}"
+                            }
+                        }
+
+                        div {
+                            flex: 1,
+                            display: "flex",
+                            flex_direction: "column",
+                            justify_content: "center",
+
+                            if let Some((idx, count)) = event.batch {
+                                div {
+                                    {t!("loading.batch", idx = idx, count = count)}
+                                }
+                                div {
+                                    width: PROGRESS_BAR_WIDTH,
+                                    height: 4.0,
+                                    background_color: "selected_bg_dark",
+
+                                    div {
+                                        width: PROGRESS_BAR_WIDTH * idx.saturating_sub(1) as f64 / count.max(1) as f64,
+                                        height: 4.0,
+                                        background_color: "dir",
+                                    }
+                                }
+                            }
+
+                            div {
+                                {progress_summary(&event)}
+                            }
+
+                            div {
+                                width: PROGRESS_BAR_WIDTH,
+                                height: 6.0,
+                                background_color: "selected_bg_dark",
+
+                                div {
+                                    width: PROGRESS_BAR_WIDTH * event.percent().unwrap_or(0.0) / 100.0,
+                                    height: 6.0,
+                                    background_color: "dir",
+                                }
+                            }
+
+                            div {
+                                color: "tips",
+                                {progress_detail(&event)}
+                            }
+                        }
+                    }
+                }
+            } else if let Some((progress, current, total, unit)) = download_progress
                 .try_read().ok()
                 .and_then(|d| d.as_ref().map(|d| (d.0, d.1, d.2, d.3.clone())))
             {
@@ -266,7 +416,7 @@ pub fn PageLoading(mut props: PageLoadingProps) -> Element {
                             align_items: "center",
                             justify_content: "center",
 
-                            "下载中：{progress:.2}% ({current:.2}/{total:.2} {unit})"
+                            {t!("loading.download", progress = format!("{progress:.2}"), current = format!("{current:.2}"), total = format!("{total:.2}"), unit = unit)}
                         }
                     }
                 }
@@ -0,0 +1,383 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+    rc::Rc,
+};
+
+use ctru::applets::swkbd::Kind;
+use dioxus::prelude::*;
+
+use crate::{
+    app::{
+        action_bar::ActionBar,
+        button::Button,
+        list_display_status::ListState,
+        list_wrap_display_status::ScrollAction,
+        tips::{Tips, TipsVisible},
+        top_bar::NavBar,
+        AppExit,
+    },
+    config::{self, Compression, Config, StoreType, Theme},
+    constant::{CONFIG_PATH, SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT, SCREEN_TOP_WIDTH},
+    i18n::{lang, next_lang},
+    platform::pl_show_swkbd,
+    resource::Resource,
+    t,
+};
+
+/// (compression, level) of the compression option
+const COMPRESSION_OPTIONS: [(Compression, Option<i32>); 4] = [
+    (Compression::Stored, None),
+    (Compression::Deflated, Some(1)),
+    (Compression::Deflated, None),
+    (Compression::Deflated, Some(9)),
+];
+const KEEP_LAST_OPTIONS: [Option<usize>; 6] =
+    [None, Some(3), Some(5), Some(10), Some(20), Some(50)];
+const RETRY_TIMES_OPTIONS: [usize; 6] = [0, 1, 2, 3, 4, 5];
+const CONNECT_TIMEOUT_OPTIONS: [u32; 5] = [10, 20, 30, 60, 120];
+const BUFFER_KIB_OPTIONS: [u32; 5] = [16, 64, 128, 256, 512];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SettingsItem {
+    Language,
+    DefaultStore,
+    BackupName,
+    Compression,
+    DedupStore,
+    KeepLast,
+    Theme,
+    RetryTimes,
+    ConnectTimeout,
+    Buffer,
+    LocalSaveDir,
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 11] = [
+        SettingsItem::Language,
+        SettingsItem::DefaultStore,
+        SettingsItem::BackupName,
+        SettingsItem::Compression,
+        SettingsItem::DedupStore,
+        SettingsItem::KeepLast,
+        SettingsItem::Theme,
+        SettingsItem::RetryTimes,
+        SettingsItem::ConnectTimeout,
+        SettingsItem::Buffer,
+        SettingsItem::LocalSaveDir,
+    ];
+
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            SettingsItem::Language => match config.language {
+                Some(lang) => lang.to_string(),
+                None => t!("settings.value.follow_system", lang = lang()),
+            },
+            SettingsItem::DefaultStore => match config.default_store {
+                StoreType::Local => t!("settings.value.local").to_string(),
+                StoreType::Cloud => t!("settings.value.cloud").to_string(),
+            },
+            SettingsItem::BackupName => config.backup.name_format.clone(),
+            SettingsItem::Compression => {
+                match (config.backup.compression, config.backup.compression_level) {
+                    (Compression::Stored, _) => t!("settings.value.stored").to_string(),
+                    (Compression::Deflated, None) => t!("settings.value.deflated").to_string(),
+                    (Compression::Deflated, Some(level)) => {
+                        t!("settings.value.deflated_level", level = level)
+                    }
+                }
+            }
+            SettingsItem::DedupStore => match config.backup.dedup_store {
+                true => t!("settings.value.on").to_string(),
+                false => t!("settings.value.off").to_string(),
+            },
+            SettingsItem::KeepLast => match config.retention.global.keep_last {
+                Some(count) => t!("settings.value.keep_last", count = count),
+                None => t!("settings.value.keep_all").to_string(),
+            },
+            SettingsItem::Theme => match config.theme {
+                Theme::Dark => t!("settings.value.dark").to_string(),
+                Theme::Light => t!("settings.value.light").to_string(),
+            },
+            SettingsItem::RetryTimes => config.network.retry_times.to_string(),
+            SettingsItem::ConnectTimeout => format!("{} s", config.network.connect_timeout_secs),
+            SettingsItem::Buffer => format!("{} KiB", config.network.buffer_kib),
+            SettingsItem::LocalSaveDir => config.paths.local_save_dir.clone(),
+        }
+    }
+
+    /// switch to the next or the previous option, items without options are not changed
+    pub fn switch(&self, config: &mut Config, forward: bool) {
+        match self {
+            SettingsItem::Language => {
+                config.language = if forward {
+                    next_lang(config.language)
+                } else {
+                    // the cycle is short, go forward until the previous one
+                    let mut lang = config.language;
+                    while next_lang(lang) != config.language {
+                        lang = next_lang(lang);
+                    }
+                    lang
+                };
+            }
+            SettingsItem::DefaultStore => {
+                config.default_store = match config.default_store {
+                    StoreType::Local => StoreType::Cloud,
+                    StoreType::Cloud => StoreType::Local,
+                };
+            }
+            SettingsItem::Compression => {
+                let current = (config.backup.compression, config.backup.compression_level);
+                (config.backup.compression, config.backup.compression_level) =
+                    cycle(&COMPRESSION_OPTIONS, current, forward);
+            }
+            SettingsItem::DedupStore => {
+                config.backup.dedup_store = !config.backup.dedup_store;
+            }
+            SettingsItem::KeepLast => {
+                config.retention.global.keep_last = cycle(
+                    &KEEP_LAST_OPTIONS,
+                    config.retention.global.keep_last,
+                    forward,
+                );
+            }
+            SettingsItem::Theme => {
+                config.theme = match config.theme {
+                    Theme::Dark => Theme::Light,
+                    Theme::Light => Theme::Dark,
+                };
+            }
+            SettingsItem::RetryTimes => {
+                config.network.retry_times =
+                    cycle(&RETRY_TIMES_OPTIONS, config.network.retry_times, forward);
+            }
+            SettingsItem::ConnectTimeout => {
+                config.network.connect_timeout_secs = cycle(
+                    &CONNECT_TIMEOUT_OPTIONS,
+                    config.network.connect_timeout_secs,
+                    forward,
+                );
+            }
+            SettingsItem::Buffer => {
+                config.network.buffer_kib =
+                    cycle(&BUFFER_KIB_OPTIONS, config.network.buffer_kib, forward);
+            }
+            SettingsItem::BackupName | SettingsItem::LocalSaveDir => {}
+        }
+    }
+}
+
+impl Deref for SettingsItem {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SettingsItem::Language => t!("settings.item.language"),
+            SettingsItem::DefaultStore => t!("settings.item.default_store"),
+            SettingsItem::BackupName => t!("settings.item.backup_name"),
+            SettingsItem::Compression => t!("settings.item.compression"),
+            SettingsItem::DedupStore => t!("settings.item.dedup_store"),
+            SettingsItem::KeepLast => t!("settings.item.keep_last"),
+            SettingsItem::Theme => t!("settings.item.theme"),
+            SettingsItem::RetryTimes => t!("settings.item.retry_times"),
+            SettingsItem::ConnectTimeout => t!("settings.item.connect_timeout"),
+            SettingsItem::Buffer => t!("settings.item.buffer"),
+            SettingsItem::LocalSaveDir => t!("settings.item.local_save_dir"),
+        }
+    }
+}
+
+impl Display for SettingsItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.deref())
+    }
+}
+
+/// the option next to `current`, the first one if `current` is not an option
+fn cycle<T: PartialEq + Copy>(options: &[T], current: T, forward: bool) -> T {
+    match options.iter().position(|&o| o == current) {
+        Some(idx) if forward => options[(idx + 1) % options.len()],
+        Some(idx) => options[(idx + options.len() - 1) % options.len()],
+        None => options[0],
+    }
+}
+
+/// # settings of the app
+///
+/// every change is saved to the config file at once
+pub fn Settings() -> Element {
+    let app_exit = consume_context::<Rc<AppExit>>();
+    let resource = use_signal(|| consume_context::<Rc<Resource>>());
+    let mut tips_visible = use_context::<SyncSignal<TipsVisible>>();
+    let mut list_state = use_signal(|| ListState::new(8));
+    let mut current = use_signal(config::get);
+
+    let mut toast = move |text: String| {
+        if let Ok(mut visible) = tips_visible.try_write() {
+            visible.show(Some(text));
+        }
+    };
+
+    let mut save = move |f: &dyn Fn(&mut Config)| match config::update(CONFIG_PATH, f) {
+        Ok(issues) => {
+            if let Some(issue) = issues.first() {
+                toast(issue.clone());
+            }
+            current.set(config::get());
+        }
+        Err(err) => toast(t!("menu.save_config_failed", err = err)),
+    };
+
+    let mut do_switch = move |forward: bool| {
+        let Some(&item) = SettingsItem::ALL.get(list_state.read().selected_idx as usize) else {
+            return;
+        };
+        match item {
+            SettingsItem::BackupName => {
+                let format = current.read().backup.name_format.clone();
+                if let Some(format) = pl_show_swkbd(Kind::Normal, &resource.read(), &format) {
+                    save(&|config: &mut Config| config.backup.name_format = format.clone());
+                }
+            }
+            SettingsItem::LocalSaveDir => {
+                toast(t!("settings.edit_in_file", path = CONFIG_PATH));
+            }
+            item => {
+                save(&|config: &mut Config| item.switch(config, forward));
+                if item == SettingsItem::Theme {
+                    toast(t!("settings.restart_required").to_string());
+                }
+            }
+        }
+    };
+
+    let top_row = list_state.read().top_row;
+    let selected_idx = list_state.read().selected_idx;
+    let display_row = list_state.read().display_row;
+
+    rsx! {
+        div {
+            "screen": "top",
+            "bg_reset": "main_bg",
+            "deep_3d": 2.0,
+            display: "flex",
+            flex_direction: "column",
+            align_items: "center",
+            justify_content: "center",
+            width: SCREEN_TOP_WIDTH,
+            height: SCREEN_HEIGHT,
+
+            if let Some(item) = SettingsItem::ALL.get(selected_idx as usize) {
+                div {
+                    "scale": 0.6,
+                    margin_bottom: 10.0,
+                    "{item}"
+                }
+                div {
+                    "scale": 0.5,
+                    "{item.value(&current.read())}"
+                }
+            }
+            div {
+                "scale": 0.4,
+                color: "tips",
+                margin_top: 20.0,
+                "{CONFIG_PATH}"
+            }
+        }
+
+        div {
+            "screen": "bottom",
+            "bg_reset": "main_bg",
+            position: "absolute",
+            left: 0,
+            top: 0,
+            display: "flex",
+            flex_direction: "column",
+            width: SCREEN_BOTTOM_WIDTH,
+            height: SCREEN_HEIGHT,
+            onkeypress: move |e| {
+                match e.data.code() {
+                    Code::ArrowUp => {
+                        list_state.write().do_scroll(SettingsItem::ALL.len() as i32, ScrollAction::Up);
+                    }
+                    Code::ArrowDown => {
+                        list_state.write().do_scroll(SettingsItem::ALL.len() as i32, ScrollAction::Down);
+                    }
+                    Code::ArrowLeft => do_switch(false),
+                    Code::ArrowRight | Code::KeyA => do_switch(true),
+                    _ => {}
+                }
+            },
+
+            NavBar {
+                is_pending: false,
+            }
+
+            div {
+                flex: 1,
+                display: "flex",
+                flex_direction: "column",
+                padding: 6.0,
+                gap: 2.0,
+
+                for (idx, item) in SettingsItem::ALL.iter().enumerate().skip(top_row as usize).take(display_row as usize) {
+                    div {
+                        height: 20.0,
+                        padding: 1,
+                        background_color: if selected_idx == idx as i32 {
+                            "green"
+                        } else {
+                            "main_bg"
+                        },
+
+                        Button {
+                            display: "flex",
+                            height: 18.0,
+                            align_items: "center",
+                            justify_content: "space-between",
+                            padding_left: 5.0,
+                            padding_right: 5.0,
+                            bg_color: "selected_bg_info",
+                            bg_active_color: "selected_bg",
+                            onclick: move |_| {
+                                list_state.write().set_selected_idx(idx as i32);
+                                do_switch(true);
+                            },
+
+                            div {
+                                "scale": 0.38,
+                                "{item}"
+                            }
+                            div {
+                                "scale": 0.38,
+                                color: "tips",
+                                "{item.value(&current.read())}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            ActionBar {
+                tips: t!("settings.tips"),
+                onkeypress: move |e: KeyboardEvent| {
+                    if app_exit.is_exit() {
+                        return;
+                    }
+                    if e.data().code() == Code::Enter {
+                        app_exit.set_exit();
+                    }
+                }
+            }
+
+            if let Some(true) = tips_visible.try_read().ok().map(|v| v.is_show()) {
+                Tips {
+                    visible: tips_visible,
+                }
+            }
+        }
+    }
+}
@@ -2,13 +2,13 @@
 
 use std::{
     cell::RefCell,
-    error::Error,
     fmt::{self, Display, Formatter},
     ops::Deref,
     rc::Rc,
 };
 
 use dioxus::prelude::*;
+use log::error;
 
 use crate::{
     api::Api,
@@ -22,7 +22,7 @@ use crate::{
         titles::{
             menu::{
                 backup_game_save_to_cloud, backup_game_save_to_local, get_game_local_backup_path,
-                Menu,
+                preflight_confirm, prune_cloud_backups, prune_local_backups, Menu,
             },
             title_list::TitleList,
             title_selected::{SaveTypes, TitleSaveTypes, TitleSelected},
@@ -30,15 +30,20 @@ use crate::{
         top_bar::NavBar,
         AppExit, AuthState,
     },
-    constant::{
-        ABOUT_TEXT, HOME_PAGE_URL, INVALID_EAT_PANCAKE, SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT,
-        SCREEN_TOP_WIDTH,
-    },
-    platform::{pl_is_homebrew, SMDH},
+    cancel,
+    config::{self, StoreType},
+    constant::{HOME_PAGE_URL, SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT, SCREEN_TOP_WIDTH},
+    error::AppResult,
+    platform::{pl_is_homebrew, refresh_titles_save_types, SMDH},
+    preflight::PreflightOp,
+    progress,
     resource::TitleInfo,
-    utils::get_current_format_time,
+    t,
 };
 
+pub mod backup_menu;
+pub mod diff_panel;
+pub mod file_picker;
 pub mod menu;
 pub mod title_list;
 pub mod title_selected;
@@ -56,6 +61,7 @@ pub enum Actions {
     BackupAllGameAllSaves,
     BackupGameAllSavesToCloud,
     BackupAllGameAllSavesToCloud,
+    RefreshSaveTypes,
     About,
 }
 
@@ -64,12 +70,13 @@ impl Deref for Actions {
 
     fn deref(&self) -> &Self::Target {
         match self {
-            Actions::OpenTitle => "打开游戏",
-            Actions::BackupGameAllSaves => "备份【该】游戏所有存档",
-            Actions::BackupAllGameAllSaves => "备份【所有】游戏所有存档",
-            Actions::BackupGameAllSavesToCloud => "备份【该】游戏所有存档到【云端】",
-            Actions::BackupAllGameAllSavesToCloud => "备份【所有】游戏所有存档到【云端】",
-            Actions::About => "关于",
+            Actions::OpenTitle => t!("titles.action.open_title"),
+            Actions::BackupGameAllSaves => t!("titles.action.backup_title"),
+            Actions::BackupAllGameAllSaves => t!("titles.action.backup_all"),
+            Actions::BackupGameAllSavesToCloud => t!("titles.action.backup_title_to_cloud"),
+            Actions::BackupAllGameAllSavesToCloud => t!("titles.action.backup_all_to_cloud"),
+            Actions::RefreshSaveTypes => t!("titles.action.refresh_save_types"),
+            Actions::About => t!("titles.action.about"),
         }
     }
 }
@@ -81,13 +88,18 @@ impl Display for Actions {
 }
 
 pub fn Titles() -> Element {
-    use_context_provider(|| Signal::new(SaveStoreType::Local));
+    use_context_provider(|| {
+        Signal::new(match config::get().default_store {
+            StoreType::Local => SaveStoreType::Local,
+            StoreType::Cloud => SaveStoreType::Cloud,
+        })
+    });
     let app_exit = consume_context::<Rc<AppExit>>();
     let mut auth_state = use_context::<SyncSignal<AuthState>>();
     let app_exit_inner = app_exit.0.clone();
     let app_exit_inner = use_signal(|| app_exit_inner);
     let titles = use_context::<Signal<Vec<TitleInfo>>>();
-    let title_selected = use_context::<Signal<Option<TitleSelected>>>();
+    let mut title_selected = use_context::<Signal<Option<TitleSelected>>>();
     let mut tips_visible = use_context::<SyncSignal<TipsVisible>>();
     let mut loading = use_context::<SyncSignal<PageLoadingVisible>>();
     let mut confirm_visible = use_context::<Signal<ConfirmVisible>>();
@@ -125,11 +137,17 @@ pub fn Titles() -> Element {
                           save_type: SaveTypes,
                           title: TitleInfo,
                           title_name: String|
-          -> Result<(), Box<dyn Error>> {
+          -> AppResult<()> {
         if store_type == SaveStoreType::Local {
-            get_game_local_backup_path(title, save_type, title_name, backup_name).and_then(
-                |backup_path| backup_game_save_to_local(backup_path, title, save_type, notify),
-            )
+            get_game_local_backup_path(title, save_type, title_name, backup_name)
+                .and_then(|backup_path| {
+                    backup_game_save_to_local(backup_path, title, save_type, notify)
+                })
+                .map(|_| {
+                    if let Err(err) = prune_local_backups(title, save_type) {
+                        error!("prune local backups failed: {}", err);
+                    }
+                })
         } else {
             backup_game_save_to_cloud(
                 None,
@@ -141,6 +159,11 @@ pub fn Titles() -> Element {
                 notify,
                 false,
             )
+            .map(|_| {
+                if let Err(err) = prune_cloud_backups(title, save_type, toast) {
+                    error!("prune cloud backups failed: {}", err);
+                }
+            })
         }
     };
 
@@ -201,6 +224,7 @@ pub fn Titles() -> Element {
                         Actions::BackupAllGameAllSaves,
                         Actions::BackupGameAllSavesToCloud,
                         Actions::BackupAllGameAllSavesToCloud,
+                        Actions::RefreshSaveTypes,
                         Actions::About
                     ] {
                         if !*is_homebrew.read() || action != Actions::OpenTitle {
@@ -219,10 +243,10 @@ pub fn Titles() -> Element {
                                     if (action == Actions::BackupGameAllSavesToCloud || action == Actions::BackupAllGameAllSavesToCloud) && (!Api::get_read().is_login() || !Api::is_eat_pancake_valid()) {
                                         if !Api::get_read().is_login() {
                                             auth_state.write().0 = false;
-                                            toast("未登录，请重新登录！".to_string());
+                                            toast(t!("common.not_logged_in").to_string());
                                         } else {
                                             confirm_visible.write().show_qrcode(
-                                                INVALID_EAT_PANCAKE.to_string(),
+                                                t!("common.invalid_eat_pancake").to_string(),
                                                 HOME_PAGE_URL.to_string(),
                                                 Rc::new(RefCell::new(Box::new(move || {}))),
                                             );
@@ -253,119 +277,164 @@ pub fn Titles() -> Element {
                                             if let Some((title, save_types)) = title_selected.read().as_ref().map(|s| (s.title, s.saves.clone())) {
                                                 let size = save_types.len();
                                                 if size == 0 {
-                                                    toast("没有存档".to_string());
+                                                    toast(t!("titles.no_saves").to_string());
                                                     return;
                                                 }
-                                                confirm_visible.write().show(
-                                                    format!("{} ?", action),
-                                                    Rc::new(RefCell::new(Box::new(move || {
+                                                preflight_confirm(
+                                                    if store_type == SaveStoreType::Local { PreflightOp::Backup } else { PreflightOp::CloudBackup },
+                                                    None,
+                                                    Some(format!("{} ?", action)),
+                                                    confirm_visible,
+                                                    loading,
+                                                    move || {
                                                         let save_types = save_types.clone();
                                                         loading.write().show();
                                                         tokio::task::spawn_blocking(move || {
                                                             let mut failed = 0;
                                                             for (idx, save_type) in save_types.into_iter().enumerate() {
+                                                                if cancel::is_cancelled() {
+                                                                    break;
+                                                                }
+                                                                progress::set_batch(Some((idx + 1, size)));
                                                                 if let Some(title_name) = SMDH::new(title.id, title.fs_media_type as u8).and_then(|s| s.short_desc()) {
-                                                                        notify(Some(format!("正在备份 {}/{}: {}", idx + 1, size, save_type)), Some(title_name.to_string()));
+                                                                        notify(Some(t!("titles.backup_running", idx = idx + 1, count = size, save_type = save_type)), Some(title_name.to_string()));
                                                                         if let Err(err) = do_backup(
                                                                             store_type,
-                                                                            format!("{}.zip", get_current_format_time()),
+                                                                            format!("{}.zip", config::backup_name()),
                                                                             save_type,
                                                                             title,
                                                                             title_name.to_string()
                                                                         ) {
                                                                             failed += 1;
-                                                                            toast(format!("备份失败: {} ({})", err, &title_name));
+                                                                            toast(t!("titles.backup_failed", err = err, name = &title_name));
                                                                         }
                                                                 }
                                                             }
-                                                            toast(format!("备份完成: {} 成功, {} 失败", size - failed, failed));
+                                                            if cancel::is_cancelled() {
+                                                                toast(t!("titles.backup_cancelled").to_string());
+                                                            } else {
+                                                                toast(t!("titles.backup_done", done = size - failed, failed = failed));
+                                                            }
                                                             loading.write().hide();
                                                         });
-                                                    })))
+                                                    }
                                                 );
                                             }
                                         }
                                         Actions::BackupAllGameAllSavesToCloud |
                                         Actions::BackupAllGameAllSaves => {
-                                            confirm_visible.write().show(
-                                                format!("{} ?", action),
-                                                Rc::new(RefCell::new(Box::new(move || {
+                                            preflight_confirm(
+                                                if store_type == SaveStoreType::Local { PreflightOp::Backup } else { PreflightOp::CloudBackup },
+                                                None,
+                                                Some(format!("{} ?", action)),
+                                                confirm_visible,
+                                                loading,
+                                                move || {
                                                     let list = list.clone();
                                                     loading.write().show();
                                                     tokio::task::spawn_blocking(move || {
                                                         let mut failed = 0;
                                                         for (idx, title) in list.into_iter().enumerate() {
+                                                            if cancel::is_cancelled() {
+                                                                break;
+                                                            }
+                                                            progress::set_batch(Some((idx + 1, size)));
                                                             if let Some(save_type) = SaveTypes::get_title_save_type(title.id) {
                                                                 if save_type == 0 {
                                                                     continue;
                                                                 }
                                                                 let mut c = 0;
                                                                 if let Some(title_name) = SMDH::new(title.id, title.fs_media_type as u8).and_then(|s| s.short_desc()) {
-                                                                    notify(Some(format!("正在备份 {}/{}: {}", idx + 1, size, SaveTypes::User)), Some(title_name.to_string()));
+                                                                    notify(Some(t!("titles.backup_running", idx = idx + 1, count = size, save_type = SaveTypes::User)), Some(title_name.to_string()));
                                                                     if save_type & 0b0001 != 0 {
                                                                         if let Err(err) = do_backup(
                                                                             store_type,
-                                                                            format!("{}.zip", get_current_format_time()),
+                                                                            format!("{}.zip", config::backup_name()),
                                                                             SaveTypes::User,
                                                                             title,
                                                                             title_name.to_string()
                                                                         ) {
                                                                             c = 1;
-                                                                            toast(format!("备份失败: {} ({})", err, &title_name));
+                                                                            toast(t!("titles.backup_failed", err = err, name = &title_name));
                                                                         }
                                                                     }
-                                                                    notify(Some(format!("正在备份 {}/{}: {}", idx + 1, size, SaveTypes::Ext)), Some(title_name.to_string()));
+                                                                    notify(Some(t!("titles.backup_running", idx = idx + 1, count = size, save_type = SaveTypes::Ext)), Some(title_name.to_string()));
                                                                     if save_type & 0b0010 != 0 {
                                                                         if let Err(err) = do_backup(
                                                                             store_type,
-                                                                            format!("{}.zip", get_current_format_time()),
+                                                                            format!("{}.zip", config::backup_name()),
                                                                             SaveTypes::Ext,
                                                                             title,
                                                                             title_name.to_string()
                                                                         ) {
                                                                             c = 1;
-                                                                            toast(format!("备份失败: {} ({})", err, &title_name));
+                                                                            toast(t!("titles.backup_failed", err = err, name = &title_name));
                                                                         }
                                                                     }
-                                                                    notify(Some(format!("正在备份 {}/{}: {}", idx + 1, size, SaveTypes::Sys)), Some(title_name.to_string()));
+                                                                    notify(Some(t!("titles.backup_running", idx = idx + 1, count = size, save_type = SaveTypes::Sys)), Some(title_name.to_string()));
                                                                     if save_type & 0b0100 != 0 {
                                                                         if let Err(err) = do_backup(
                                                                             store_type,
-                                                                            format!("{}.zip", get_current_format_time()),
+                                                                            format!("{}.zip", config::backup_name()),
                                                                             SaveTypes::Sys,
                                                                             title,
                                                                             title_name.to_string()
                                                                         ) {
                                                                             c = 1;
-                                                                            toast(format!("备份失败: {} ({})", err, &title_name));
+                                                                            toast(t!("titles.backup_failed", err = err, name = &title_name));
                                                                         }
                                                                     }
-                                                                    notify(Some(format!("正在备份 {}/{}: {}", idx + 1, size, SaveTypes::Boss)), Some(title_name.to_string()));
+                                                                    notify(Some(t!("titles.backup_running", idx = idx + 1, count = size, save_type = SaveTypes::Boss)), Some(title_name.to_string()));
                                                                     if save_type & 0b1000 != 0 {
                                                                         if let Err(err) = do_backup(
                                                                             store_type,
-                                                                            format!("{}.zip", get_current_format_time()),
+                                                                            format!("{}.zip", config::backup_name()),
                                                                             SaveTypes::Boss,
                                                                             title,
                                                                             title_name.to_string()
                                                                         ) {
                                                                             c = 1;
-                                                                            toast(format!("备份失败: {} ({})", err, &title_name));
+                                                                            toast(t!("titles.backup_failed", err = err, name = &title_name));
                                                                         }
                                                                     }
                                                                 }
                                                                 failed += c;
                                                             }
                                                         }
-                                                        toast(format!("备份完成: {} 成功, {} 失败", size - failed, failed));
+                                                        if cancel::is_cancelled() {
+                                                            toast(t!("titles.backup_cancelled").to_string());
+                                                        } else {
+                                                            toast(t!("titles.backup_done", done = size - failed, failed = failed));
+                                                        }
+                                                        loading.write().hide();
+                                                    });
+                                                }
+                                            );
+                                        }
+                                        Actions::RefreshSaveTypes => {
+                                            confirm_visible.write().show(
+                                                format!("{} ?", action),
+                                                Rc::new(RefCell::new(Box::new(move || {
+                                                    let ids = list.iter().map(|t| (t.id, t.fs_media_type)).collect::<Vec<_>>();
+                                                    spawn(async move {
+                                                        loading.write().show();
+                                                        notify(Some(format!("{}", action)), None);
+                                                        let _ = tokio::task::spawn_blocking(move || {
+                                                            refresh_titles_save_types(ids);
+                                                        })
+                                                        .await;
+                                                        if let Some(title) = title_selected.peek().as_ref().map(|s| s.title) {
+                                                            title_selected.write().replace(TitleSelected::new(title));
+                                                        }
                                                         loading.write().hide();
+                                                        toast(t!("titles.save_types_refreshed").to_string());
                                                     });
                                                 })))
                                             );
                                         }
                                         Actions::About => {
                                             confirm_visible.write().show_qrcode(
-                                                ABOUT_TEXT.to_string(),
+                                                t!("titles.about").to_string(),
                                                 HOME_PAGE_URL.to_string(),
                                                 Rc::new(RefCell::new(Box::new(move || {})))
                                             );
@@ -383,7 +452,7 @@ pub fn Titles() -> Element {
 
             ActionBar {
                 version: true,
-                tips: "(START) 退出   (A) 备份/恢复",
+                tips: t!("titles.tips"),
                 onkeypress: move |e: KeyboardEvent| {
                     if is_pending() || app_exit.is_exit() {
                         return;
@@ -0,0 +1,198 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+    rc::Rc,
+};
+
+use dioxus::prelude::*;
+
+use crate::app::{
+    action_bar::ActionBar, button::Button, confirm::ConfirmVisible, dialog::DialogVisible,
+    list_display_status::ListState, list_wrap_display_status::ScrollAction,
+};
+use crate::t;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BackupActions {
+    ExportZip,
+    UploadEncrypted,
+    ExportCheckpoint,
+    ExportEmulator,
+    ImportExternal,
+    RestoreFiles,
+    CompareSave,
+    ComparePrevious,
+    Protect,
+    Unprotect,
+    Prune,
+    SetPassphrase,
+    ClearPassphrase,
+    ShowExtdataId,
+    SecureValue,
+}
+
+impl Deref for BackupActions {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            BackupActions::ExportZip => t!("backup.action.export_zip"),
+            BackupActions::UploadEncrypted => t!("backup.action.upload_encrypted"),
+            BackupActions::ExportCheckpoint => t!("backup.action.export_checkpoint"),
+            BackupActions::ExportEmulator => t!("backup.action.export_emulator"),
+            BackupActions::ImportExternal => t!("backup.action.import_external"),
+            BackupActions::RestoreFiles => t!("backup.action.restore_files"),
+            BackupActions::CompareSave => t!("backup.action.compare_save"),
+            BackupActions::ComparePrevious => t!("backup.action.compare_previous"),
+            BackupActions::Protect => t!("backup.action.protect"),
+            BackupActions::Unprotect => t!("backup.action.unprotect"),
+            BackupActions::Prune => t!("backup.action.prune"),
+            BackupActions::SetPassphrase => t!("backup.action.set_passphrase"),
+            BackupActions::ClearPassphrase => t!("backup.action.clear_passphrase"),
+            BackupActions::ShowExtdataId => t!("backup.action.show_extdata_id"),
+            BackupActions::SecureValue => t!("backup.action.secure_value"),
+        }
+    }
+}
+
+impl Display for BackupActions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.deref())
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct BackupMenuProps {
+    visible: Signal<DialogVisible>,
+    actions: Signal<Option<(Vec<BackupActions>, String, String)>>,
+    onaction: EventHandler<(BackupActions, String)>,
+}
+
+/// # actions of the selected backup
+pub fn BackupMenu(mut props: BackupMenuProps) -> Element {
+    let mut confirm_visible = use_context::<Signal<ConfirmVisible>>();
+    let mut list_state = use_signal(|| ListState::new(9));
+
+    let is_pending = use_memo(move || {
+        !props.visible.read().visible() || confirm_visible.read().dialog.read().is_show()
+    });
+
+    let mut do_action = move || {
+        props.actions.read().as_ref().map(|actions| {
+            let actions = actions.clone();
+            let idx = list_state.read().selected_idx;
+            if let Some(action) = actions.0.get(idx as usize).map(|&a| a.clone()) {
+                // only shows info or switches option
+                if action == BackupActions::ShowExtdataId || action == BackupActions::SecureValue {
+                    props.visible.write().hide();
+                    props.onaction.call((action, actions.1.clone()));
+                    return;
+                }
+                let tips = match action {
+                    BackupActions::Prune
+                    | BackupActions::SetPassphrase
+                    | BackupActions::ClearPassphrase => format!("{}?", action),
+                    _ => format!("{} {}?", action, actions.1),
+                };
+                confirm_visible.write().show(
+                    tips,
+                    Rc::new(RefCell::new(Box::new(move || {
+                        props.visible.write().hide();
+                        props.onaction.call((action, actions.1.clone()));
+                    }))),
+                );
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            "scale": 0.38,
+            flex: 1,
+            display: "flex",
+            flex_direction: "column",
+            position: "relative",
+            onkeypress: move |e| {
+                if is_pending() {
+                    return;
+                }
+                match e.data.code() {
+                    Code::ArrowUp => {
+                        props.actions.read().as_ref().map(|(actions, _, _)| {
+                            list_state.write().do_scroll(actions.len() as i32, ScrollAction::Up);
+                        });
+                    }
+                    Code::ArrowDown => {
+                        props.actions.read().as_ref().map(|(actions, _, _)| {
+                            list_state.write().do_scroll(actions.len() as i32, ScrollAction::Down);
+                        });
+                    }
+                    Code::KeyA => {
+                        do_action();
+                    }
+                    Code::KeyB => {
+                        props.visible.write().hide();
+                    }
+                    _ => {}
+                }
+            },
+
+            div {
+                flex: 1,
+                display: "flex",
+                flex_direction: "column",
+                padding: 5.0,
+                padding_top: 0.0,
+                margin_top: 4.0,
+
+                if let Some((actions, _, info)) = props.actions.read().as_ref().map(|l| l.clone()) {
+                    div {
+                        display: "flex",
+                        height: 20.0,
+                        align_items: "center",
+                        justify_content: "center",
+                        padding_top: 2.0,
+                        padding_left: 5.0,
+                        padding_right: 5.0,
+                        margin_bottom: 5.0,
+                        background_color: "selected_bg",
+
+                        "{info}"
+                    }
+
+                    for (idx, action) in actions.iter().enumerate() {
+                        div {
+                            height: 20.0,
+                            padding: 1,
+                            background_color: if list_state.read().selected_idx == idx as i32 {
+                                "green"
+                            } else {
+                                "main_bg"
+                            },
+
+                            Button {
+                                display: "flex",
+                                height: 18.0,
+                                align_items: "center",
+                                padding_left: 5.0,
+                                padding_right: 5.0,
+                                bg_color: "main_bg",
+                                bg_active_color: "selected_bg",
+                                onclick: move |_| {
+                                    list_state.write().set_selected_idx(idx as i32);
+                                    do_action();
+                                },
+                                "{&action}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            ActionBar {
+                tips: t!("common.tips_close_select")
+            }
+        }
+    }
+}
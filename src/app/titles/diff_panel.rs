@@ -0,0 +1,114 @@
+use dioxus::prelude::*;
+
+use crate::{
+    constant::SCREEN_TOP_WIDTH,
+    diff::{DiffKind, SaveDiff},
+    t,
+    utils::format_size,
+};
+
+/// rows of the top screen
+const MAX_DIFF_ROWS: usize = 12;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DiffPanelProps {
+    title: String,
+    diff: SaveDiff,
+}
+
+/// # diff of two save sources on the top screen
+pub fn DiffPanel(props: DiffPanelProps) -> Element {
+    let summary = props.diff.summary();
+    let more = props.diff.entries.len().saturating_sub(MAX_DIFF_ROWS);
+    let rows = props
+        .diff
+        .entries
+        .iter()
+        .take(MAX_DIFF_ROWS)
+        .map(|entry| match entry.kind {
+            DiffKind::Added => format!(
+                "+ {}  {}",
+                entry.path,
+                entry
+                    .to
+                    .as_ref()
+                    .map(|f| format_size(f.size))
+                    .unwrap_or_default()
+            ),
+            DiffKind::Removed => format!(
+                "- {}  {}",
+                entry.path,
+                entry
+                    .from
+                    .as_ref()
+                    .map(|f| format_size(f.size))
+                    .unwrap_or_default()
+            ),
+            DiffKind::Modified => format!(
+                "~ {}  {} -> {}  {}",
+                entry.path,
+                entry
+                    .from
+                    .as_ref()
+                    .map(|f| format_size(f.size))
+                    .unwrap_or_default(),
+                entry
+                    .to
+                    .as_ref()
+                    .map(|f| format_size(f.size))
+                    .unwrap_or_default(),
+                entry
+                    .to
+                    .as_ref()
+                    .map(|f| &f.md5[..8.min(f.md5.len())])
+                    .unwrap_or_default()
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div {
+            "screen": "top",
+            "deep_3d": 1.0,
+            "scale": 0.45,
+            color: "white",
+            display: "flex",
+            flex_direction: "column",
+            position: "absolute",
+            width: SCREEN_TOP_WIDTH as f64,
+            padding: 10.0,
+            top: 0,
+            left: 0,
+            right: 0,
+            bottom: 0,
+            background_color: "main_bg",
+
+            div {
+                height: 18.0,
+                padding_left: 5.0,
+                background_color: "selected_bg",
+
+                "{props.title}  [{summary}]"
+            }
+
+            for row in rows {
+                div {
+                    height: 15.0,
+                    padding_left: 5.0,
+
+                    "{row}"
+                }
+            }
+
+            if more > 0 {
+                div {
+                    height: 15.0,
+                    padding_left: 5.0,
+                    color: "tips",
+
+                    {t!("diff.more", count = more)}
+                }
+            }
+        }
+    }
+}
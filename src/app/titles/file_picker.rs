@@ -0,0 +1,201 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use dioxus::prelude::*;
+
+use crate::{
+    app::{
+        action_bar::ActionBar, button::Button, confirm::ConfirmVisible, dialog::DialogVisible,
+        list_display_status::ListState, list_wrap_display_status::ScrollAction,
+    },
+    manifest::ManifestFile,
+    t,
+    utils::format_size,
+};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct FilePickerProps {
+    visible: Signal<DialogVisible>,
+    /// backup name and files of the backup
+    files: Signal<Option<(String, Vec<ManifestFile>)>>,
+    onrestore: EventHandler<Vec<String>>,
+    /// closed without restore
+    onclose: EventHandler<()>,
+    /// shown with the confirm, e.g. how the secure value is handled
+    note: Option<String>,
+}
+
+/// # pick files of a backup to restore
+pub fn FilePicker(mut props: FilePickerProps) -> Element {
+    let mut confirm_visible = use_context::<Signal<ConfirmVisible>>();
+    let mut list_state = use_signal(|| ListState::new(9));
+    let mut selected = use_signal(HashSet::<String>::new);
+
+    let is_pending = use_memo(move || {
+        !props.visible.read().visible() || confirm_visible.read().dialog.read().is_show()
+    });
+
+    let mut toggle = move |path: String| {
+        let mut selected = selected.write();
+        if !selected.remove(&path) {
+            selected.insert(path);
+        }
+    };
+
+    let mut do_restore = move || {
+        let files = props
+            .files
+            .read()
+            .as_ref()
+            .map(|(_, files)| {
+                files
+                    .iter()
+                    .filter(|f| selected.read().contains(&f.path))
+                    .map(|f| f.path.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if files.is_empty() {
+            return;
+        }
+        let title = t!("file_picker.confirm", count = files.len());
+        confirm_visible.write().show(
+            match props.note.as_ref() {
+                Some(note) => format!("{} ({})", title, note),
+                None => title,
+            },
+            Rc::new(RefCell::new(Box::new(move || {
+                props.visible.write().hide();
+                props.onrestore.call(files.clone());
+            }))),
+        );
+    };
+
+    let rows = props
+        .files
+        .read()
+        .as_ref()
+        .map(|(_, files)| {
+            let top = list_state.read().top_row as usize;
+            files
+                .iter()
+                .enumerate()
+                .skip(top)
+                .take(list_state.read().display_row as usize)
+                .map(|(idx, f)| {
+                    (
+                        idx,
+                        f.path.clone(),
+                        format!(
+                            "[{}] {}  {}",
+                            if selected.read().contains(&f.path) {
+                                "x"
+                            } else {
+                                " "
+                            },
+                            f.path.trim_start_matches('/'),
+                            format_size(f.size)
+                        ),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    rsx! {
+        div {
+            "scale": 0.38,
+            flex: 1,
+            display: "flex",
+            flex_direction: "column",
+            position: "relative",
+            onkeypress: move |e| {
+                if is_pending() {
+                    return;
+                }
+                match e.data.code() {
+                    Code::ArrowUp => {
+                        props.files.read().as_ref().map(|(_, files)| {
+                            list_state.write().do_scroll(files.len() as i32, ScrollAction::Up);
+                        });
+                    }
+                    Code::ArrowDown => {
+                        props.files.read().as_ref().map(|(_, files)| {
+                            list_state.write().do_scroll(files.len() as i32, ScrollAction::Down);
+                        });
+                    }
+                    Code::KeyA => {
+                        let idx = list_state.read().selected_idx as usize;
+                        let path = props.files.read().as_ref().and_then(|(_, files)| files.get(idx).map(|f| f.path.clone()));
+                        if let Some(path) = path {
+                            toggle(path);
+                        }
+                    }
+                    Code::KeyY => {
+                        do_restore();
+                    }
+                    Code::KeyB => {
+                        props.visible.write().hide();
+                        props.onclose.call(());
+                    }
+                    _ => {}
+                }
+            },
+
+            div {
+                flex: 1,
+                display: "flex",
+                flex_direction: "column",
+                padding: 5.0,
+                padding_top: 0.0,
+                margin_top: 4.0,
+
+                if let Some(name) = props.files.read().as_ref().map(|(name, _)| name.clone()) {
+                    div {
+                        display: "flex",
+                        height: 20.0,
+                        align_items: "center",
+                        justify_content: "center",
+                        padding_top: 2.0,
+                        padding_left: 5.0,
+                        padding_right: 5.0,
+                        margin_bottom: 5.0,
+                        background_color: "selected_bg",
+
+                        "{name}  ({selected.read().len()})"
+                    }
+                }
+
+                for (idx, path, row) in rows {
+                    div {
+                        height: 20.0,
+                        padding: 1,
+                        background_color: if list_state.read().selected_idx == idx as i32 {
+                            "green"
+                        } else {
+                            "main_bg"
+                        },
+
+                        Button {
+                            display: "flex",
+                            height: 18.0,
+                            align_items: "center",
+                            padding_left: 5.0,
+                            padding_right: 5.0,
+                            bg_color: "main_bg",
+                            bg_active_color: "selected_bg",
+                            onclick: move |_| {
+                                list_state.write().set_selected_idx(idx as i32);
+                                toggle(path.clone());
+                            },
+                            "{row}"
+                        }
+                    }
+                }
+            }
+
+            ActionBar {
+                tips: t!("file_picker.tips")
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
     cell::RefCell,
-    error::Error,
+    fmt::{self, Display, Formatter},
     fs::{self, create_dir_all},
+    ops::Deref,
     path::Path,
     rc::Rc,
+    sync::Mutex,
     thread::sleep,
     time::{Duration, Instant},
 };
@@ -12,6 +14,7 @@ use ctru::{applets::swkbd::Kind, services::fs::ArchiveID};
 use dioxus::prelude::*;
 use log::error;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use zip::ZipArchive;
 
 use crate::{
     api::{Api, SaveItem},
@@ -20,26 +23,56 @@ use crate::{
         auth::Auth,
         button::Button,
         confirm::ConfirmVisible,
-        dialog::DialogVisible,
+        dialog::{use_dialog, Dialog, DialogVisible},
         line::Line,
         list_display_status::ListState,
         list_wrap_display_status::{ListDisplayStatus, ScrollAction},
         loading::PageLoadingVisible,
         tips::TipsVisible,
-        titles::{title_selected::TitleSelected, SaveStoreType},
+        titles::{
+            backup_menu::{BackupActions, BackupMenu},
+            diff_panel::DiffPanel,
+            file_picker::FilePicker,
+            title_selected::TitleSelected,
+            SaveStoreType,
+        },
         AuthState,
     },
+    checkpoint::{
+        checkpoint_backup_dir, find_external_backup, is_external_backup, list_external_backups,
+        ExternalBackup, ExternalTool,
+    },
+    config,
     constant::{
-        GAME_SAVE_CLOUD_DIR, HOME_LOCAL_PATH_SAVE, HOME_PAGE_URL, INVALID_EAT_PANCAKE,
-        SCREEN_TOP_WIDTH,
+        BACKUP_MANIFEST_NAME, CONFIG_PATH, EMULATOR_PATH, ENCRYPTED_EXT, GAME_SAVE_CLOUD_DIR,
+        HOME_LOCAL_PATH_CACHE, HOME_PAGE_URL, SCREEN_BOTTOM_WIDTH, SCREEN_HEIGHT, SCREEN_TOP_WIDTH,
+        SNAPSHOT_EXT,
     },
+    crypto::{check_passphrase, is_encrypted_file, session_passphrase, set_session_passphrase},
+    diff::{diff_files, zip_files, SaveDiff},
+    emulator::{
+        export_emulator_zip, import_emulator_dir, import_emulator_zip, list_emulator_backups,
+    },
+    error::{with_network_retry, AppError, AppResult},
+    extdata::{extdata_id, extdata_redirects, RedirectSource},
     fsu,
-    platform::{pl_commit_arch_data, pl_delete_arch_sv, pl_show_swkbd, SMDH},
+    manifest::{BackupManifest, ManifestFile},
+    platform::{
+        get_wifi_strength, pl_battery_info, pl_create_ext_save_data, pl_delete_arch_sv,
+        pl_get_title_sv, pl_set_title_sv, pl_show_swkbd, pl_show_swkbd_password, pl_storage_info,
+        update_title_save_types, SMDH,
+    },
+    preflight::{run_checks, PreflightIssue, PreflightOp, PreflightState},
+    progress::{self, ProgressStage},
     resource::{Resource, TitleInfo},
+    retention::{parse_backup_time, select_prune, RetentionConfig},
+    t,
     utils::{
-        backup_game_save, check_save_arch_is_empty, delete_dir_if_empty, get_current_format_time,
-        get_local_dir_start_with, get_local_game_saves, join_path, normalize_path,
-        restore_game_save,
+        backup_files, backup_game_save, backup_game_save_to_store, check_save_arch_is_empty,
+        decrypt_backup, delete_dir_if_empty, encrypt_backup, export_snapshot, ext_data_quota,
+        get_current_format_time, get_local_dir_start_with, get_local_game_saves, join_path,
+        normalize_path, restore_game_save, round_up_to_block, save_arch_blocks_size,
+        save_arch_files, save_arch_size, store_gc, zip_dir, zip_extract,
     },
 };
 
@@ -65,13 +98,19 @@ fn fetch_game_title_detail(title: TitleInfo) -> Option<(String, String, String)>
     })
 }
 
+fn title_short_name(title: TitleInfo) -> String {
+    SMDH::new(title.id, title.fs_media_type as u8)
+        .and_then(|s| s.short_desc())
+        .unwrap_or_default()
+}
+
 pub fn get_game_local_backup_path(
     title: TitleInfo,
     save_type: SaveTypes,
     title_name: String,
     backup_name: String,
-) -> Result<String, Box<dyn Error>> {
-    let game_backup_save_type_dir = join_path(HOME_LOCAL_PATH_SAVE, &save_type);
+) -> AppResult<String> {
+    let game_backup_save_type_dir = join_path(&config::local_save_dir(), &save_type);
     let path = match get_local_dir_start_with(&game_backup_save_type_dir, &title.id_hex_str()) {
         Some(path) => join_path(&path, &backup_name),
         None => {
@@ -122,19 +161,30 @@ fn fetch_game_save_local(
     mut list_local: SyncSignal<(ListState, Vec<String>, bool)>,
 ) {
     // update local list
-    let target_path = join_path(HOME_LOCAL_PATH_SAVE, &save_type);
-    if let Some(path) = get_local_dir_start_with(&target_path, &title.id_hex_str()) {
-        let res = get_local_game_saves(&path);
-        list_local.with_mut(|list| {
-            list.0.update(res.len() as i32 + 1);
-            list.1 = res;
-            list.2 = true;
-        });
-    } else {
-        list_local.with_mut(|list| {
-            list.2 = true;
-        });
-    }
+    let target_path = join_path(&config::local_save_dir(), &save_type);
+    let mut res = get_local_dir_start_with(&target_path, &title.id_hex_str())
+        .map(|path| get_local_game_saves(&path))
+        .unwrap_or_default();
+    // backups of other save managers are listed after local backups
+    res.extend(
+        list_external_backups(title.low_id() >> 8, &title_short_name(title), &save_type)
+            .iter()
+            .chain(
+                list_emulator_backups(
+                    &save_type,
+                    title.high_id(),
+                    title.low_id(),
+                    extdata_id(title.low_id()),
+                )
+                .iter(),
+            )
+            .map(|backup| backup.list_name()),
+    );
+    list_local.with_mut(|list| {
+        list.0.update(res.len() as i32 + 1);
+        list.1 = res;
+        list.2 = true;
+    });
 }
 
 pub fn fetch_game_save_cloud(
@@ -155,12 +205,26 @@ pub fn fetch_game_save_cloud(
     });
 }
 
+/// manifest of the title's save archive
+pub fn new_backup_manifest(title: TitleInfo, save_type: SaveTypes) -> BackupManifest {
+    let mut manifest = BackupManifest::new(
+        title.id_hex_str(),
+        title.product_code(),
+        title.media_name(),
+        &save_type,
+    );
+    if save_type == SaveTypes::User {
+        manifest.secure_value = pl_get_title_sv(title.low_id() >> 8);
+    }
+    manifest
+}
+
 pub fn backup_game_save_to_local(
     backup_path: String,
     title: TitleInfo,
     save_type: SaveTypes,
     notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
+) -> AppResult<()> {
     if let (Ok(arch_from), Ok(arch_to)) = (
         fsu::arch(
             save_type.arch_id(),
@@ -176,9 +240,30 @@ pub fn backup_game_save_to_local(
         ),
     ) {
         if !check_save_arch_is_empty("/", &arch_from) {
-            backup_game_save(("/", &arch_from), (&backup_path, &arch_to), notify)?;
+            if backup_path.ends_with(SNAPSHOT_EXT) {
+                let is_overwrite = Path::new(&backup_path).exists();
+                backup_game_save_to_store(
+                    ("/", &arch_from),
+                    &backup_path,
+                    new_backup_manifest(title, save_type),
+                    notify,
+                )?;
+                // chunks of the overwritten snapshot may be unused now
+                if is_overwrite {
+                    if let Err(err) = store_gc() {
+                        error!("store gc failed: {}", err);
+                    }
+                }
+            } else {
+                backup_game_save(
+                    ("/", &arch_from),
+                    (&backup_path, &arch_to),
+                    new_backup_manifest(title, save_type),
+                    notify,
+                )?;
+            }
         } else {
-            return Err("存档数据为空！".into());
+            return Err(AppError::Validation(t!("menu.save_empty").to_string()));
         }
     }
 
@@ -194,7 +279,7 @@ pub fn backup_game_save_to_cloud(
     toast: impl FnMut(String) + Copy,
     notify: impl FnMut(Option<String>, Option<String>) + Copy,
     is_overwrite: bool,
-) -> Result<(), Box<dyn Error>> {
+) -> AppResult<()> {
     // backup to local
     let local_backup_path =
         get_game_local_backup_path(title, save_type, title_name.clone(), backup_name.clone())
@@ -208,11 +293,12 @@ pub fn backup_game_save_to_cloud(
         get_game_cloud_backup_path(game_save_cloud_dir, title, save_type, title_name, toast);
 
     // upload to cloud
-    let res = Api::upload_to_cloud(
+    let res = upload_backup(
         &cloud_dir,
         &backup_name,
         &local_backup_path,
         is_overwrite,
+        None,
         notify,
     );
 
@@ -232,44 +318,942 @@ pub fn backup_game_save_to_cloud(
     res
 }
 
-fn restore_backup(
+/// # prune local backups of the title by the retention rules
+///
+/// returns count of deleted backups
+pub fn prune_local_backups(title: TitleInfo, save_type: SaveTypes) -> AppResult<usize> {
+    let retention = config::get().retention;
+    let title_id = title.id_hex_str();
+    let target_path = join_path(&config::local_save_dir(), &save_type);
+    if let (Some(path), Some(now)) = (
+        get_local_dir_start_with(&target_path, &title_id),
+        parse_backup_time(&get_current_format_time()),
+    ) {
+        let names = get_local_game_saves(&path);
+        let prune = select_prune(&names, retention.rule(&title_id), now, |name| {
+            retention.is_protected(&RetentionConfig::protect_key(
+                false, &title_id, &save_type, name,
+            ))
+        });
+        for name in prune.iter() {
+            fs::remove_file(join_path(&path, name))?;
+        }
+        if prune.iter().any(|name| name.ends_with(SNAPSHOT_EXT)) {
+            store_gc()?;
+        }
+        delete_dir_if_empty(&path).ok();
+        return Ok(prune.len());
+    }
+
+    Ok(0)
+}
+
+/// # prune cloud backups of the title by the retention rules
+///
+/// returns count of deleted backups
+pub fn prune_cloud_backups(
     title: TitleInfo,
     save_type: SaveTypes,
-    backup_path: String,
+    toast: impl FnMut(String) + Copy,
+) -> AppResult<usize> {
+    let retention = config::get().retention;
+    let title_id = title.id_hex_str();
+    if let ((Some(dir), Some(list)), Some(now)) = (
+        Api::fetch_save_cloud_list(&title_id, &save_type, false, toast),
+        parse_backup_time(&get_current_format_time()),
+    ) {
+        let names = list
+            .iter()
+            .map(|item| item.name.to_string())
+            .collect::<Vec<_>>();
+        let prune = select_prune(&names, retention.rule(&title_id), now, |name| {
+            retention.is_protected(&RetentionConfig::protect_key(
+                true, &title_id, &save_type, name,
+            ))
+        });
+        for name in prune.iter() {
+            if let Err(err) = Api::start_file_manager(
+                &utf8_percent_encode(&join_path(&dir, name), NON_ALPHANUMERIC).to_string(),
+                None,
+                None,
+                crate::api::ApiOperates::Delete,
+            ) {
+                return Err(format!("{}: {}", name, err).into());
+            }
+        }
+        return Ok(prune.len());
+    }
+
+    Ok(0)
+}
+
+/// # diff the current save of the title with a local backup
+///
+/// the changes are what a restore of the backup would make
+fn diff_backup_with_save(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_path: &str,
+) -> AppResult<SaveDiff> {
+    let arch = fsu::arch(
+        save_type.arch_id(),
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    )
+    .map_err(|e| AppError::archive_open(save_type.arch_id(), e))?;
+    Ok(diff_files(
+        &save_arch_files(("/", &arch))?,
+        &backup_files(backup_path)?,
+    ))
+}
+
+/// diff local backup with the previous backup, or with the current save if `previous` is `None`
+fn diff_local_backup(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_name: &str,
+    previous: Option<String>,
+) -> AppResult<SaveDiff> {
+    let target_path = join_path(&config::local_save_dir(), &save_type);
+    let path = get_local_dir_start_with(&target_path, &title.id_hex_str())
+        .ok_or(t!("menu.local_backup_missing"))?;
+    let backup_path = join_path(&path, backup_name);
+    match previous {
+        Some(previous) => Ok(diff_files(
+            &backup_files(&join_path(&path, &previous))?,
+            &backup_files(&backup_path)?,
+        )),
+        None => diff_backup_with_save(title, save_type, &backup_path),
+    }
+}
+
+/// # upload backup to cloud
+///
+/// the backup is encrypted before upload if the passphrase of the session is set
+fn upload_backup(
+    cloud_dir: &str,
+    backup_name: &str,
+    backup_path: &str,
+    is_overwrite: bool,
+    passphrase: Option<&str>,
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    let passphrase = match passphrase {
+        Some(passphrase) if !is_encrypted_file(backup_path) => passphrase,
+        _ => {
+            progress::start(ProgressStage::Upload, 1, 0);
+            return with_network_retry(|| {
+                Api::upload_to_cloud(cloud_dir, backup_name, backup_path, is_overwrite, notify)
+            });
+        }
+    };
+    notify(
+        Some(t!("menu.encrypting").to_string()),
+        Some(backup_name.to_string()),
+    );
+    let encrypted_path = format!("{}.enc.tmp", backup_path);
+    encrypt_backup(backup_path, &encrypted_path, passphrase)?;
+    let encrypted_name = encrypted_backup_name(backup_name);
+    progress::start(ProgressStage::Upload, 1, 0);
+    let res = with_network_retry(|| {
+        Api::upload_to_cloud(
+            cloud_dir,
+            &encrypted_name,
+            &encrypted_path,
+            is_overwrite,
+            notify,
+        )
+    });
+    fs::remove_file(&encrypted_path).ok();
+    res
+}
+
+/// # state of the console and the save for preflight checks
+///
+/// `backup_path` is the local backup zip to restore
+fn preflight_state(
+    title_save: Option<(TitleInfo, SaveTypes)>,
+    backup_path: Option<&str>,
+) -> PreflightState {
+    let battery = pl_battery_info();
+    let mut state = PreflightState {
+        battery_level: battery.map(|(level, _)| level),
+        is_charging: battery.is_some_and(|(_, charging)| charging),
+        sd_free: pl_storage_info().0,
+        wifi_strength: get_wifi_strength(),
+        ..Default::default()
+    };
+    if let Some((title, save_type)) = title_save {
+        let mut block_size = 1;
+        if let Ok(arch) = fsu::arch(
+            save_type.arch_id(),
+            title.fs_media_type,
+            title.high_id(),
+            title.low_id(),
+        ) {
+            // the backup, or the auto backup made by restore
+            state.sd_required = save_arch_size("/", &arch);
+            if let Ok(info) =
+                fsu::arch_info(&arch, title.fs_media_type, title.high_id(), title.low_id())
+            {
+                block_size = info.block_size;
+                // extdata has no fixed size
+                if save_type == SaveTypes::User || save_type == SaveTypes::Sys {
+                    state.save_capacity =
+                        Some(info.free + save_arch_blocks_size("/", &arch, block_size));
+                }
+                state.save_max_files = Some(info.max_files).filter(|&max| max > 0);
+            }
+        }
+        if let Some(files) = backup_path
+            .and_then(|path| fs::File::open(path).ok())
+            .and_then(|file| zip_files(file).ok())
+        {
+            state.save_required = files
+                .iter()
+                .map(|f| round_up_to_block(f.size, block_size))
+                .sum();
+            state.save_required_files = files.len();
+        }
+    }
+    state
+}
+
+pub async fn preflight_issues(
+    op: PreflightOp,
+    title_save: Option<(TitleInfo, SaveTypes)>,
+    backup_path: Option<String>,
+) -> Vec<PreflightIssue> {
+    tokio::task::spawn_blocking(move || {
+        run_checks(
+            &config::get().preflight,
+            op,
+            &preflight_state(title_save, backup_path.as_deref()),
+        )
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// title of the confirm with warnings of preflight, `None` if it is blocked
+pub fn preflight_title(title: &str, issues: &[PreflightIssue]) -> Option<String> {
+    if issues.iter().any(|issue| issue.is_blocking) {
+        return None;
+    }
+    if issues.is_empty() {
+        return Some(title.to_string());
+    }
+    let warnings = issues
+        .iter()
+        .map(|issue| issue.message.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("{} ({})", title, warnings))
+}
+
+/// show the blocking issue of preflight
+pub fn show_preflight_error(
+    mut confirm_visible: Signal<ConfirmVisible>,
+    issues: &[PreflightIssue],
+) {
+    if let Some(issue) = issues.iter().find(|issue| issue.is_blocking) {
+        confirm_visible.write().show(
+            t!("menu.preflight_failed", reason = issue.message),
+            Rc::new(RefCell::new(Box::new(move || {}))),
+        );
+    }
+}
+
+/// # run preflight checks before a long operation
+///
+/// blocking issues are shown in the confirm dialog, warnings are shown with
+/// `title` and can be ignored. `on_confirm` runs without confirm if `title` is
+/// `None` and there is no warning
+pub fn preflight_confirm(
+    op: PreflightOp,
+    title_save: Option<(TitleInfo, SaveTypes)>,
+    title: Option<String>,
+    mut confirm_visible: Signal<ConfirmVisible>,
+    loading: SyncSignal<PageLoadingVisible>,
+    on_confirm: impl FnMut() + 'static,
+) {
+    preflight_confirm_with_cancel(
+        op,
+        title_save,
+        None,
+        title,
+        confirm_visible,
+        loading,
+        on_confirm,
+        || {},
+    );
+}
+
+/// # `preflight_confirm` that runs `on_cancel` if it is blocked or canceled
+///
+/// `backup_path` is the local backup zip to restore, the save archive is
+/// checked against it
+pub fn preflight_confirm_with_cancel(
+    op: PreflightOp,
+    title_save: Option<(TitleInfo, SaveTypes)>,
+    backup_path: Option<String>,
+    title: Option<String>,
+    mut confirm_visible: Signal<ConfirmVisible>,
+    mut loading: SyncSignal<PageLoadingVisible>,
+    mut on_confirm: impl FnMut() + 'static,
+    mut on_cancel: impl FnMut() + 'static,
+) {
+    spawn(async move {
+        loading.write().show();
+        let issues = preflight_issues(op, title_save, backup_path).await;
+        loading.write().hide();
+        if title.is_none() && issues.is_empty() {
+            on_confirm();
+            return;
+        }
+        match preflight_title(title.as_deref().unwrap_or(t!("menu.continue")), &issues) {
+            Some(title) => confirm_visible.write().show_with_cancel(
+                title,
+                Rc::new(RefCell::new(Box::new(on_confirm))),
+                Rc::new(RefCell::new(Box::new(on_cancel))),
+            ),
+            None => {
+                on_cancel();
+                show_preflight_error(confirm_visible, &issues);
+            }
+        }
+    });
+}
+
+/// # ask passphrase of an encrypted backup
+///
+/// returns false if the input is canceled or the passphrase is wrong
+fn prepare_passphrase(
+    resource: &Resource,
+    backup_path: &str,
     mut toast: impl FnMut(String) + Copy,
+) -> bool {
+    if !is_encrypted_file(backup_path)
+        || session_passphrase().is_some_and(|p| check_passphrase(backup_path, &p))
+    {
+        return true;
+    }
+    match pl_show_swkbd_password(resource) {
+        Some(passphrase) if check_passphrase(backup_path, &passphrase) => {
+            set_session_passphrase(Some(passphrase));
+            true
+        }
+        Some(_) => {
+            toast(t!("menu.wrong_passphrase").to_string());
+            false
+        }
+        None => false,
+    }
+}
+
+/// `<name>.enc` of an encrypted upload, so it is not taken as a plain zip
+fn encrypted_backup_name(backup_name: &str) -> String {
+    format!("{}{}", backup_name.trim_end_matches(".zip"), ENCRYPTED_EXT)
+}
+
+/// # upload local backup
+///
+/// snapshot of the dedup store is uploaded as a zip, the backup is encrypted
+/// only if `passphrase` is given
+fn upload_local_backup(
+    cloud_dir: &str,
+    backup_name: &str,
+    backup_path: &str,
+    passphrase: Option<&str>,
     notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
-    if let (Ok(arch_from), Ok(arch_to)) = (
-        fsu::arch(
-            ArchiveID::Sdmc,
-            title.fs_media_type,
+) -> AppResult<()> {
+    if !backup_name.ends_with(SNAPSHOT_EXT) {
+        return upload_backup(
+            cloud_dir,
+            backup_name,
+            backup_path,
+            false,
+            passphrase,
+            notify,
+        );
+    }
+    let zip_name = format!("{}.zip", backup_name.trim_end_matches(SNAPSHOT_EXT));
+    let zip_path = temp_zip_path(backup_path);
+    export_snapshot(backup_path, &zip_path, notify)?;
+    let res = upload_backup(cloud_dir, &zip_name, &zip_path, false, passphrase, notify);
+    fs::remove_file(&zip_path).ok();
+    res
+}
+
+/// temp zip of a snapshot or an encrypted backup, next to it so the auto
+/// backup of a restore stays in the backup dir
+fn temp_zip_path(backup_path: &str) -> String {
+    Path::new(backup_path)
+        .with_extension("zip.tmp")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// find backup of other save managers by the name in the local list
+fn find_other_backup(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    title_name: &str,
+    list_name: &str,
+) -> Option<ExternalBackup> {
+    find_external_backup(title.low_id() >> 8, title_name, &save_type, list_name).or_else(|| {
+        list_emulator_backups(
+            &save_type,
             title.high_id(),
             title.low_id(),
-        ),
-        fsu::arch(
-            save_type.arch_id(),
+            extdata_id(title.low_id()),
+        )
+        .into_iter()
+        .find(|backup| backup.list_name() == list_name)
+    })
+}
+
+/// zip backup of other save managers, `list_name` is the name in the local list
+fn external_backup_to_zip(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    list_name: &str,
+    to: &str,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    let backup = find_other_backup(title, save_type, &title_short_name(title), list_name)
+        .ok_or(t!("menu.backup_missing"))?;
+    if backup.tool == ExternalTool::Emulator {
+        let manifest = new_backup_manifest(title, save_type);
+        let (high_id, low_id) = (title.high_id(), title.low_id());
+        let ext_id = extdata_id(low_id);
+        let res = fs::File::create(to)
+            .map_err(|e| AppError::fs(e, to))
+            .and_then(|file| {
+                Ok(if Path::new(&backup.path).is_dir() {
+                    import_emulator_dir(
+                        &backup.path,
+                        file,
+                        &save_type,
+                        high_id,
+                        low_id,
+                        ext_id,
+                        manifest,
+                    )?
+                } else {
+                    import_emulator_zip(
+                        fs::File::open(&backup.path)?,
+                        file,
+                        &save_type,
+                        high_id,
+                        low_id,
+                        ext_id,
+                        manifest,
+                    )?
+                })
+            });
+        if res.is_err() {
+            fs::remove_file(to).ok();
+        }
+        return res.map(|_| ());
+    }
+    let arch = fsu::arch(
+        ArchiveID::Sdmc,
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    )
+    .map_err(|e| AppError::archive_open(ArchiveID::Sdmc, e))?;
+    zip_dir(
+        (&backup.path, &arch),
+        (to, &arch),
+        &[],
+        Some(new_backup_manifest(title, save_type)),
+        notify,
+    )
+}
+
+/// # import backup of other save managers as a local zip backup
+///
+/// returns name of the local backup
+fn import_external_backup(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    title_name: String,
+    list_name: &str,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<String> {
+    let name = find_other_backup(title, save_type, &title_name, list_name)
+        .map(|backup| {
+            format!(
+                "{}.zip",
+                normalize_path(backup.name.trim_end_matches(".zip"))
+            )
+        })
+        .ok_or(t!("menu.backup_missing"))?;
+    let backup_path = get_game_local_backup_path(title, save_type, title_name, name.clone())?;
+    if Path::new(&backup_path).exists() {
+        return Err(AppError::Validation(t!("menu.backup_exists").to_string()));
+    }
+    external_backup_to_zip(title, save_type, list_name, &backup_path, notify)?;
+    Ok(name)
+}
+
+/// # plain zip of the local backup
+///
+/// snapshot and encrypted backup are exported to a temp zip, returns the zip
+/// and whether it is a temp file
+fn plain_backup_zip(
+    backup_path: String,
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<(String, bool)> {
+    if backup_path.ends_with(SNAPSHOT_EXT) {
+        let zip_path = temp_zip_path(&backup_path);
+        notify(Some(t!("menu.exporting_snapshot").to_string()), None);
+        export_snapshot(&backup_path, &zip_path, notify)?;
+        return Ok((zip_path, true));
+    }
+    if is_encrypted_file(&backup_path) {
+        let passphrase = session_passphrase().ok_or(t!("menu.passphrase_required"))?;
+        let zip_path = temp_zip_path(&backup_path);
+        notify(Some(t!("menu.decrypting").to_string()), None);
+        decrypt_backup(&backup_path, &zip_path, &passphrase)?;
+        return Ok((zip_path, true));
+    }
+    Ok((backup_path, false))
+}
+
+/// # export local backup as a checkpoint backup folder
+///
+/// returns the backup folder
+fn export_to_checkpoint(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    title_name: &str,
+    backup_name: &str,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<String> {
+    let name = backup_name
+        .trim_end_matches(SNAPSHOT_EXT)
+        .trim_end_matches(ENCRYPTED_EXT)
+        .trim_end_matches(".zip");
+    let target = checkpoint_backup_dir(title.low_id() >> 8, title_name, &save_type, name)
+        .ok_or(t!("menu.checkpoint_unsupported"))?;
+    if Path::new(&target).exists() {
+        return Err(AppError::Validation(t!("menu.backup_exists").to_string()));
+    }
+    let (zip_path, is_temp) = plain_backup_zip(
+        fetch_backup_file(title, save_type, backup_name, None, notify)?,
+        notify,
+    )?;
+    let arch = fsu::arch(
+        ArchiveID::Sdmc,
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    )
+    .map_err(|e| AppError::archive_open(ArchiveID::Sdmc, e))?;
+    create_dir_all(&target)?;
+    // manifest is not part of the save data
+    let res = zip_extract(
+        (&zip_path, &arch),
+        (&target, &arch),
+        &[BACKUP_MANIFEST_NAME],
+        notify,
+    );
+    if is_temp {
+        fs::remove_file(&zip_path).ok();
+    }
+    if let Err(err) = res {
+        fs::remove_dir_all(&target).ok();
+        return Err(err);
+    }
+    Ok(target)
+}
+
+/// # export local backup as a zip in the sdmc layout of emulators
+///
+/// the zip is written to `EMULATOR_PATH`, returns the zip
+fn export_to_emulator(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_name: &str,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<String> {
+    let name = backup_name
+        .trim_end_matches(SNAPSHOT_EXT)
+        .trim_end_matches(ENCRYPTED_EXT)
+        .trim_end_matches(".zip");
+    let target = join_path(
+        EMULATOR_PATH,
+        &format!("{} {} {}.zip", title.id_hex_str(), save_type, name),
+    );
+    if Path::new(&target).exists() {
+        return Err(AppError::Validation(t!("menu.backup_exists").to_string()));
+    }
+    let (zip_path, is_temp) = plain_backup_zip(
+        fetch_backup_file(title, save_type, backup_name, None, notify)?,
+        notify,
+    )?;
+    create_dir_all(EMULATOR_PATH)?;
+    let res = fs::File::open(&zip_path)
+        .and_then(|input| Ok((input, fs::File::create(&target)?)))
+        .map_err(AppError::from)
+        .and_then(|(input, output)| {
+            Ok(export_emulator_zip(
+                input,
+                output,
+                &save_type,
+                title.high_id(),
+                title.low_id(),
+                extdata_id(title.low_id()),
+            )?)
+        });
+    if is_temp {
+        fs::remove_file(&zip_path).ok();
+    }
+    if let Err(err) = res {
+        fs::remove_file(&target).ok();
+        return Err(err);
+    }
+    Ok(target)
+}
+
+/// # get the backup as a local file
+///
+/// local backup if `fs_id` is `None`, otherwise the cloud backup is downloaded to cache.
+/// backup of other save managers is zipped to cache
+fn fetch_backup_file(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_name: &str,
+    fs_id: Option<u64>,
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<String> {
+    match fs_id {
+        None if is_external_backup(backup_name) => {
+            notify(
+                Some(t!("menu.reading_backup").to_string()),
+                Some(backup_name.to_string()),
+            );
+            let cache_path = join_path(
+                HOME_LOCAL_PATH_CACHE,
+                &format!("{}.zip", normalize_path(backup_name)),
+            );
+            create_dir_all(HOME_LOCAL_PATH_CACHE)?;
+            if let Err(err) =
+                external_backup_to_zip(title, save_type, backup_name, &cache_path, notify)
+            {
+                fs::remove_file(&cache_path).ok();
+                return Err(err);
+            }
+            Ok(cache_path)
+        }
+        None => {
+            let target_path = join_path(&config::local_save_dir(), &save_type);
+            get_local_dir_start_with(&target_path, &title.id_hex_str())
+                .map(|path| join_path(&path, backup_name))
+                .ok_or(t!("menu.local_backup_missing").into())
+        }
+        Some(fs_id) => {
+            notify(
+                Some(t!("common.downloading").to_string()),
+                Some(backup_name.to_string()),
+            );
+            let cache_path = join_path(HOME_LOCAL_PATH_CACHE, backup_name);
+            create_dir_all(HOME_LOCAL_PATH_CACHE)?;
+            progress::start(ProgressStage::Download, 1, 0);
+            if let Err(err) = with_network_retry(|| Api::start_download(fs_id, &cache_path, None)) {
+                fs::remove_file(&cache_path).ok();
+                return Err(err);
+            }
+            Ok(cache_path)
+        }
+    }
+}
+
+/// remove the backup got by `fetch_backup_file` if it is in cache
+fn discard_backup_file(backup_path: &str, is_cached: bool) {
+    if is_cached {
+        fs::remove_file(backup_path).ok();
+    }
+}
+
+/// # restore backup got by `fetch_backup_file`
+///
+/// a backup in cache, downloaded from cloud or zipped from other save managers,
+/// is moved out of cache first, so the auto backup is made in the backup dir,
+/// and it is removed after restore
+fn restore_backup_file(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    title_name: String,
+    backup_name: String,
+    backup_path: String,
+    is_cached: bool,
+    files: Option<Vec<String>>,
+    toast: impl FnMut(String) + Copy,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    if !is_cached {
+        return restore_backup(title, save_type, backup_path, files, toast, notify);
+    }
+    let res = get_game_local_backup_path(title, save_type, title_name, backup_name).and_then(
+        |local_backup_path| {
+            fs::rename(&backup_path, &local_backup_path)?;
+            let res = restore_backup(
+                title,
+                save_type,
+                local_backup_path.clone(),
+                files,
+                toast,
+                notify,
+            );
+            // remove download backup after restore
+            fs::remove_file(&local_backup_path).ok();
+            // remove local backup dir if empty
+            if let Some(parent) = Path::new(&local_backup_path).parent() {
+                delete_dir_if_empty(parent).ok();
+            }
+            res
+        },
+    );
+    // not moved out of cache if failed before restore
+    discard_backup_file(&backup_path, true);
+    res
+}
+
+/// # how the secure value of user saves is handled on restore
+///
+/// the secure value is checked by games against the save to prevent rollback
+#[derive(Clone, Copy, PartialEq)]
+pub enum SecureValueAction {
+    /// delete it, the game accepts any save
+    Delete,
+    /// restore the value in the backup manifest
+    Restore,
+    /// leave the current value untouched
+    Keep,
+}
+
+impl Deref for SecureValueAction {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SecureValueAction::Delete => t!("menu.secure_value.delete"),
+            SecureValueAction::Restore => t!("menu.secure_value.restore"),
+            SecureValueAction::Keep => t!("menu.secure_value.keep"),
+        }
+    }
+}
+
+impl Display for SecureValueAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.deref())
+    }
+}
+
+impl SecureValueAction {
+    pub fn next(&self) -> Self {
+        match self {
+            SecureValueAction::Delete => SecureValueAction::Restore,
+            SecureValueAction::Restore => SecureValueAction::Keep,
+            SecureValueAction::Keep => SecureValueAction::Delete,
+        }
+    }
+}
+
+/// secure value action of the session
+static SECURE_VALUE_ACTION: Mutex<SecureValueAction> = Mutex::new(SecureValueAction::Delete);
+
+pub fn secure_value_action() -> SecureValueAction {
+    SECURE_VALUE_ACTION
+        .lock()
+        .map(|a| *a)
+        .unwrap_or(SecureValueAction::Delete)
+}
+
+pub fn set_secure_value_action(action: SecureValueAction) {
+    if let Ok(mut a) = SECURE_VALUE_ACTION.lock() {
+        *a = action;
+    }
+}
+
+fn restore_backup(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_path: String,
+    files: Option<Vec<String>>,
+    mut toast: impl FnMut(String) + Copy,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    // snapshot of the dedup store is restored from a temp zip
+    if backup_path.ends_with(SNAPSHOT_EXT) {
+        let zip_path = temp_zip_path(&backup_path);
+        notify(Some(t!("menu.exporting_snapshot").to_string()), None);
+        export_snapshot(&backup_path, &zip_path, notify)?;
+        let res = restore_backup(title, save_type, zip_path.clone(), files, toast, notify);
+        fs::remove_file(&zip_path).ok();
+        return res;
+    }
+    // encrypted backup is decrypted to a temp zip
+    if is_encrypted_file(&backup_path) {
+        let passphrase = session_passphrase().ok_or(t!("menu.passphrase_required"))?;
+        let zip_path = temp_zip_path(&backup_path);
+        notify(Some(t!("menu.decrypting").to_string()), None);
+        decrypt_backup(&backup_path, &zip_path, &passphrase)?;
+        let res = restore_backup(title, save_type, zip_path.clone(), files, toast, notify);
+        fs::remove_file(&zip_path).ok();
+        return res;
+    }
+    let arch_from = fsu::arch(
+        ArchiveID::Sdmc,
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    )
+    .map_err(|e| AppError::archive_open(ArchiveID::Sdmc, e))?;
+    let arch_to = match fsu::arch(
+        save_type.arch_id(),
+        title.fs_media_type,
+        title.high_id(),
+        title.low_id(),
+    ) {
+        Ok(arch) => arch,
+        Err(err) => {
+            // only an archive that does not exist yet is created, other errors are kept
+            let err = AppError::archive_open(save_type.arch_id(), err);
+            if !err.is_not_found() {
+                return Err(err);
+            }
+            create_missing_arch(title, save_type, &backup_path, notify)?;
+            fsu::arch(
+                save_type.arch_id(),
+                title.fs_media_type,
+                title.high_id(),
+                title.low_id(),
+            )
+            .map_err(|e| AppError::archive_open(save_type.arch_id(), e))?
+        }
+    };
+    // the whole save is replaced, the backup must fit in the archive before it is cleared
+    if files.is_none() {
+        if let Ok(info) = fsu::arch_info(
+            &arch_to,
             title.fs_media_type,
             title.high_id(),
             title.low_id(),
-        ),
-    ) {
-        // restore
-        restore_game_save((&backup_path, &arch_from), ("/", &arch_to), notify)?;
-        // commit data
-        if !pl_commit_arch_data(&arch_to) {
-            toast("提交数据失败！".to_string());
-            error!("提交数据失败！");
+        ) {
+            let backup_files = zip_files(fs::File::open(&backup_path)?)?;
+            // extdata has no fixed size, a file always takes whole blocks
+            if save_type == SaveTypes::User || save_type == SaveTypes::Sys {
+                let available = info.free + save_arch_blocks_size("/", &arch_to, info.block_size);
+                let required = backup_files.iter().map(|f| info.blocks_size(f.size)).sum();
+                if required > available {
+                    return Err(AppError::Quota {
+                        required,
+                        available,
+                    });
+                }
+            }
+            if info.max_files > 0 && backup_files.len() > info.max_files as usize {
+                return Err(t!(
+                    "common.save_files_full",
+                    required = backup_files.len(),
+                    capacity = info.max_files
+                )
+                .into());
+            }
+        }
+    }
+    // the secure value is read before the save is touched, delete it if the backup has none
+    let mut action = secure_value_action();
+    let mut secure_value = None;
+    if action == SecureValueAction::Restore && save_type == SaveTypes::User {
+        secure_value = fs::File::open(&backup_path)
+            .ok()
+            .and_then(|file| ZipArchive::new(file).ok())
+            .and_then(|mut zip| BackupManifest::read_from(&mut zip))
+            .and_then(|manifest| manifest.secure_value);
+        if secure_value.is_none() {
+            action = SecureValueAction::Delete;
         }
+    }
+    // restore
+    let res = restore_game_save(
+        (&backup_path, &arch_from),
+        ("/", &arch_to),
+        new_backup_manifest(title, save_type),
+        files,
+        notify,
+    );
+    // the capacity shown in the save types bar is read again
+    SaveTypes::clear_arch_info(title.id);
+    res?;
+    match action {
         // delete arch security value
-        if !pl_delete_arch_sv(&arch_to, title.low_id() >> 8) {
-            toast("删除安全值失败！".to_string());
-            error!("删除安全值失败！");
+        SecureValueAction::Delete => {
+            if !pl_delete_arch_sv(&arch_to, title.low_id() >> 8) {
+                toast(t!("menu.secure_value.delete_failed").to_string());
+                error!("删除安全值失败！");
+            } else if secure_value_action() == SecureValueAction::Restore {
+                toast(t!("menu.secure_value.missing").to_string());
+            }
+        }
+        SecureValueAction::Restore => {
+            if let Some(value) = secure_value {
+                if !pl_set_title_sv(title.low_id() >> 8, value) {
+                    toast(t!("menu.secure_value.restore_failed").to_string());
+                    error!("还原安全值失败！");
+                }
+            }
         }
+        SecureValueAction::Keep => {}
+    }
+    // auto backups made by restore follow the retention rules too
+    if let Err(err) = prune_local_backups(title, save_type) {
+        error!("prune local backups failed: {}", err);
     }
 
     Ok(())
 }
 
+/// # create the save archive that does not exist yet
+///
+/// e.g. extdata of a game that is not started since installed. only extdata
+/// can be created, the size is based on the files of the backup
+fn create_missing_arch(
+    title: TitleInfo,
+    save_type: SaveTypes,
+    backup_path: &str,
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    if save_type != SaveTypes::Ext {
+        return Err(AppError::Validation(t!(
+            "menu.save_create_unsupported",
+            save_type = save_type
+        )));
+    }
+    notify(Some(t!("menu.creating_save").to_string()), None);
+    let files = zip_files(fs::File::open(backup_path)?)?;
+    let (dirs, files, size) = ext_data_quota(&files);
+    if !pl_create_ext_save_data(
+        extdata_id(title.low_id()),
+        title.id,
+        title.fs_media_type as u8,
+        dirs,
+        files,
+        size,
+    ) {
+        return Err(t!("menu.create_save_failed").into());
+    }
+    // the title has extdata now, keep the save types of the title list up to date
+    update_title_save_types(title.id, title.fs_media_type);
+    Ok(())
+}
+
 fn Item(props: ItemProps) -> Element {
     rsx! {
         div {
@@ -311,6 +1295,29 @@ pub fn Menu(mut props: MenuProps) -> Element {
     let mut tips_visible = use_context::<SyncSignal<TipsVisible>>();
     let mut title_detail = use_signal_sync::<Option<(String, String, String)>>(|| None);
     let mut confirm_visible = use_context::<Signal<ConfirmVisible>>();
+    let mut backup_actions = use_signal::<Option<(Vec<BackupActions>, String, String)>>(|| None);
+    let mut retention = use_signal_sync(|| config::get().retention);
+    // diff shown on the top screen while the confirm with the same title is shown
+    let mut diff_result = use_signal::<Option<(String, SaveDiff)>>(|| None);
+    let mut backup_menu_visible = use_dialog(
+        false,
+        SCREEN_HEIGHT as f64,
+        0.0,
+        SCREEN_BOTTOM_WIDTH as f64,
+        SCREEN_HEIGHT as f64,
+        None,
+    );
+    // files of the backup to pick, and the local path of the backup and whether it is in cache
+    let mut restore_files = use_signal::<Option<(String, Vec<ManifestFile>)>>(|| None);
+    let mut restore_source = use_signal::<Option<(String, bool)>>(|| None);
+    let mut file_picker_visible = use_dialog(
+        false,
+        SCREEN_HEIGHT as f64,
+        0.0,
+        SCREEN_BOTTOM_WIDTH as f64,
+        SCREEN_HEIGHT as f64,
+        None,
+    );
 
     let mut toast = move |text: String| {
         if let Ok(mut visible) = tips_visible.try_write() {
@@ -375,10 +1382,477 @@ pub fn Menu(mut props: MenuProps) -> Element {
         loading.read().visible()
             || !props.visible.read().visible()
             || confirm_visible.read().dialog.read().is_show()
+            || backup_menu_visible.read().is_show()
+            || file_picker_visible.read().is_show()
     });
 
+    let do_backup_action = {
+        let resource = resource.clone();
+        move |(action, backup_name): (BackupActions, String)| {
+            if let Some((Some(save_type), selected)) = title_selected
+                .read()
+                .as_ref()
+                .map(|s| (s.save_type, s.clone()))
+            {
+                match action {
+                    BackupActions::ExportZip => {
+                        loading.write().show();
+                        tokio::task::spawn_blocking(move || {
+                            notify(
+                                Some(t!("menu.exporting").to_string()),
+                                Some(backup_name.clone()),
+                            );
+                            let target_path = join_path(&config::local_save_dir(), &save_type);
+                            if let Some(path) =
+                                get_local_dir_start_with(&target_path, &selected.title.id_hex_str())
+                            {
+                                let zip_path = join_path(
+                                    &path,
+                                    &format!("{}.zip", backup_name.trim_end_matches(SNAPSHOT_EXT)),
+                                );
+                                if Path::new(&zip_path).exists() {
+                                    toast(t!("menu.export_exists").to_string());
+                                } else {
+                                    match export_snapshot(
+                                        &join_path(&path, &backup_name),
+                                        &zip_path,
+                                        notify,
+                                    ) {
+                                        Ok(_) => {
+                                            fetch_game_save_local(
+                                                selected.title,
+                                                save_type,
+                                                list_local,
+                                            );
+                                            toast(t!("menu.export_done").to_string());
+                                        }
+                                        Err(err) => {
+                                            toast(t!("menu.export_failed", err = err));
+                                        }
+                                    }
+                                }
+                            }
+                            loading.write().hide();
+                        });
+                    }
+                    BackupActions::UploadEncrypted => {
+                        if !Api::get_read().is_login() {
+                            toast(t!("common.not_logged_in").to_string());
+                            auth_state.write().0 = false;
+                            return;
+                        }
+                        let Some(title_name) = title_detail.read().as_ref().map(|t| t.1.clone())
+                        else {
+                            return;
+                        };
+                        let Some(passphrase) =
+                            session_passphrase().or_else(|| pl_show_swkbd_password(&resource))
+                        else {
+                            return;
+                        };
+                        set_session_passphrase(Some(passphrase.clone()));
+                        let cloud_dir = list_cloud.read().2.clone();
+                        loading.write().show();
+                        tokio::task::spawn_blocking(move || {
+                            notify(
+                                Some(t!("common.uploading").to_string()),
+                                Some(backup_name.clone()),
+                            );
+                            let target_path = join_path(&config::local_save_dir(), &save_type);
+                            if let Some(path) =
+                                get_local_dir_start_with(&target_path, &selected.title.id_hex_str())
+                            {
+                                let cloud_dir = get_game_cloud_backup_path(
+                                    cloud_dir,
+                                    selected.title,
+                                    save_type,
+                                    title_name,
+                                    toast,
+                                );
+                                match upload_local_backup(
+                                    &cloud_dir,
+                                    &backup_name,
+                                    &join_path(&path, &backup_name),
+                                    Some(&passphrase),
+                                    notify,
+                                ) {
+                                    Ok(_) => {
+                                        fetch_game_save_cloud(
+                                            selected.title,
+                                            save_type,
+                                            list_cloud,
+                                            toast,
+                                        );
+                                        toast(t!("menu.upload_done").to_string());
+                                    }
+                                    Err(err) => {
+                                        if err.needs_reauth() {
+                                            AuthState::expire(auth_state);
+                                        }
+                                        toast(t!("menu.upload_failed", err = err));
+                                    }
+                                }
+                            }
+                            loading.write().hide();
+                        });
+                    }
+                    BackupActions::ImportExternal => {
+                        let title_name = title_short_name(selected.title);
+                        loading.write().show();
+                        tokio::task::spawn_blocking(move || {
+                            notify(
+                                Some(t!("menu.importing").to_string()),
+                                Some(backup_name.clone()),
+                            );
+                            match import_external_backup(
+                                selected.title,
+                                save_type,
+                                title_name,
+                                &backup_name,
+                                notify,
+                            ) {
+                                Ok(name) => {
+                                    fetch_game_save_local(selected.title, save_type, list_local);
+                                    toast(t!("menu.imported", name = name));
+                                }
+                                Err(err) => toast(t!("menu.import_failed", err = err)),
+                            }
+                            loading.write().hide();
+                        });
+                    }
+                    BackupActions::ExportEmulator => {
+                        let resource = resource.clone();
+                        spawn(async move {
+                            let target_path = join_path(&config::local_save_dir(), &save_type);
+                            if let Some(path) =
+                                get_local_dir_start_with(&target_path, &selected.title.id_hex_str())
+                            {
+                                if !prepare_passphrase(
+                                    &resource,
+                                    &join_path(&path, &backup_name),
+                                    toast,
+                                ) {
+                                    return;
+                                }
+                            }
+                            loading.write().show();
+                            let res = tokio::task::spawn_blocking(move || {
+                                notify(
+                                    Some(t!("menu.exporting").to_string()),
+                                    Some(backup_name.clone()),
+                                );
+                                export_to_emulator(selected.title, save_type, &backup_name, notify)
+                                    .map_err(|e| e.to_string())
+                            })
+                            .await;
+                            loading.write().hide();
+                            match res {
+                                Ok(Ok(path)) => toast(t!("menu.exported_to", path = path)),
+                                Ok(Err(err)) => toast(t!("menu.export_failed", err = err)),
+                                Err(err) => toast(t!("menu.export_failed", err = err)),
+                            }
+                        });
+                    }
+                    BackupActions::ExportCheckpoint => {
+                        let title_name = title_short_name(selected.title);
+                        let resource = resource.clone();
+                        spawn(async move {
+                            let target_path = join_path(&config::local_save_dir(), &save_type);
+                            if let Some(path) =
+                                get_local_dir_start_with(&target_path, &selected.title.id_hex_str())
+                            {
+                                if !prepare_passphrase(
+                                    &resource,
+                                    &join_path(&path, &backup_name),
+                                    toast,
+                                ) {
+                                    return;
+                                }
+                            }
+                            loading.write().show();
+                            let res = tokio::task::spawn_blocking(move || {
+                                notify(
+                                    Some(t!("menu.exporting").to_string()),
+                                    Some(backup_name.clone()),
+                                );
+                                export_to_checkpoint(
+                                    selected.title,
+                                    save_type,
+                                    &title_name,
+                                    &backup_name,
+                                    notify,
+                                )
+                                .map_err(|e| e.to_string())
+                            })
+                            .await;
+                            loading.write().hide();
+                            match res {
+                                Ok(Ok(path)) => toast(t!("menu.exported_to", path = path)),
+                                Ok(Err(err)) => toast(t!("menu.export_failed", err = err)),
+                                Err(err) => toast(t!("menu.export_failed", err = err)),
+                            }
+                        });
+                    }
+                    BackupActions::RestoreFiles => {
+                        let is_local = *store_type.read() == SaveStoreType::Local;
+                        let fs_id = list_cloud
+                            .read()
+                            .1
+                            .iter()
+                            .find(|item| item.name == backup_name)
+                            .map(|item| item.fs_id);
+                        loading.write().show();
+                        notify(
+                            Some(t!("menu.reading_backup").to_string()),
+                            Some(backup_name.clone()),
+                        );
+                        let resource = resource.clone();
+                        spawn(async move {
+                            let name = backup_name.clone();
+                            let res = tokio::task::spawn_blocking(move || {
+                                let fs_id = if is_local {
+                                    None
+                                } else {
+                                    Some(fs_id.ok_or(t!("menu.cloud_backup_missing"))?)
+                                };
+                                fetch_backup_file(selected.title, save_type, &name, fs_id, notify)
+                                    .map_err(|e| e.to_string())
+                            })
+                            .await;
+                            loading.write().hide();
+                            let path = match res {
+                                Ok(Ok(path)) => path,
+                                Ok(Err(err)) => {
+                                    toast(t!("menu.read_backup_failed", err = err));
+                                    return;
+                                }
+                                Err(err) => {
+                                    toast(t!("menu.read_backup_failed", err = err));
+                                    return;
+                                }
+                            };
+                            let is_cached = !is_local || is_external_backup(&backup_name);
+                            if !prepare_passphrase(&resource, &path, toast) {
+                                discard_backup_file(&path, is_cached);
+                                return;
+                            }
+
+                            loading.write().show();
+                            let backup_path = path.clone();
+                            let res = tokio::task::spawn_blocking(move || {
+                                backup_files(&backup_path).map_err(|e| e.to_string())
+                            })
+                            .await;
+                            loading.write().hide();
+                            match res {
+                                Ok(Ok(files)) => {
+                                    restore_files.set(Some((backup_name, files)));
+                                    restore_source.set(Some((path, is_cached)));
+                                    file_picker_visible.write().show();
+                                }
+                                Ok(Err(err)) => {
+                                    discard_backup_file(&path, is_cached);
+                                    toast(t!("menu.read_backup_failed", err = err));
+                                }
+                                Err(err) => {
+                                    discard_backup_file(&path, is_cached);
+                                    toast(t!("menu.read_backup_failed", err = err));
+                                }
+                            }
+                        });
+                    }
+                    BackupActions::CompareSave | BackupActions::ComparePrevious => {
+                        let previous = {
+                            let local = list_local.read();
+                            local
+                                .1
+                                .iter()
+                                .position(|name| *name == backup_name)
+                                .and_then(|idx| local.1.get(idx + 1).cloned())
+                                .filter(|name| !is_external_backup(name))
+                        };
+                        let title = match (action, previous.as_ref()) {
+                            (BackupActions::ComparePrevious, Some(previous)) => {
+                                t!("menu.compare_title", from = previous, to = backup_name)
+                            }
+                            _ => t!("menu.compare_current_title", to = backup_name),
+                        };
+                        loading.write().show();
+                        notify(
+                            Some(t!("menu.comparing").to_string()),
+                            Some(backup_name.clone()),
+                        );
+                        spawn(async move {
+                            let previous = match action {
+                                BackupActions::ComparePrevious => previous,
+                                _ => None,
+                            };
+                            let res = tokio::task::spawn_blocking(move || {
+                                diff_local_backup(selected.title, save_type, &backup_name, previous)
+                                    .map_err(|e| e.to_string())
+                            })
+                            .await;
+                            loading.write().hide();
+                            match res {
+                                Ok(Ok(diff)) => {
+                                    diff_result.set(Some((title.clone(), diff)));
+                                    confirm_visible
+                                        .write()
+                                        .show(title, Rc::new(RefCell::new(Box::new(move || {}))));
+                                }
+                                Ok(Err(err)) => toast(t!("menu.compare_failed", err = err)),
+                                Err(err) => toast(t!("menu.compare_failed", err = err)),
+                            }
+                        });
+                    }
+                    BackupActions::Protect | BackupActions::Unprotect => {
+                        let key = RetentionConfig::protect_key(
+                            *store_type.read() == SaveStoreType::Cloud,
+                            &selected.title.id_hex_str(),
+                            &save_type,
+                            &backup_name,
+                        );
+                        let is_protected = retention.write().toggle_protected(key);
+                        let protected = retention.read().protected.clone();
+                        if let Err(err) = config::update(CONFIG_PATH, move |config| {
+                            config.retention.protected = protected
+                        }) {
+                            toast(t!("menu.save_config_failed", err = err));
+                        } else if is_protected {
+                            toast(t!("menu.protected").to_string());
+                        } else {
+                            toast(t!("menu.unprotected").to_string());
+                        }
+                    }
+                    BackupActions::SetPassphrase => {
+                        if let Some(passphrase) = pl_show_swkbd_password(&resource) {
+                            set_session_passphrase(Some(passphrase));
+                            toast(t!("menu.passphrase_set").to_string());
+                        }
+                    }
+                    BackupActions::ClearPassphrase => {
+                        set_session_passphrase(None);
+                        toast(t!("menu.passphrase_cleared").to_string());
+                    }
+                    BackupActions::SecureValue => {
+                        let action = secure_value_action().next();
+                        set_secure_value_action(action);
+                        toast(t!("menu.secure_value_action", action = action));
+                    }
+                    BackupActions::ShowExtdataId => {
+                        let (extdata_id, source) =
+                            extdata_redirects().resolve(selected.title.low_id());
+                        let source = match source {
+                            RedirectSource::Default => t!("menu.redirect.default").to_string(),
+                            RedirectSource::Bundled(name) => {
+                                t!("menu.redirect.bundled", name = name)
+                            }
+                            RedirectSource::User(name) => t!("menu.redirect.user", name = name),
+                        };
+                        toast(format!("Extdata ID: 0x{:08X} ({})", extdata_id, source));
+                    }
+                    BackupActions::Prune => {
+                        let is_cloud = *store_type.read() == SaveStoreType::Cloud;
+                        loading.write().show();
+                        tokio::task::spawn_blocking(move || {
+                            notify(Some(t!("menu.pruning").to_string()), None);
+                            let res = if is_cloud {
+                                prune_cloud_backups(selected.title, save_type, toast)
+                            } else {
+                                prune_local_backups(selected.title, save_type)
+                            };
+                            if is_cloud {
+                                fetch_game_save_cloud(selected.title, save_type, list_cloud, toast);
+                            } else {
+                                fetch_game_save_local(selected.title, save_type, list_local);
+                            }
+                            match res {
+                                Ok(0) => toast(t!("menu.prune_none").to_string()),
+                                Ok(count) => toast(t!("menu.pruned", count = count)),
+                                Err(err) => toast(t!("menu.prune_failed", err = err)),
+                            }
+                            loading.write().hide();
+                        });
+                    }
+                }
+            }
+        }
+    };
+
+    let do_restore_files = move |files: Vec<String>| {
+        if let (
+            Some((Some(save_type), selected)),
+            Some(title_name),
+            Some(backup_name),
+            Some((backup_path, is_cached)),
+        ) = (
+            title_selected
+                .read()
+                .as_ref()
+                .map(|s| (s.save_type, s.clone())),
+            title_detail.read().as_ref().map(|t| t.1.clone()),
+            restore_files.read().as_ref().map(|(name, _)| name.clone()),
+            restore_source.write().take(),
+        ) {
+            let cancel_path = backup_path.clone();
+            preflight_confirm_with_cancel(
+                PreflightOp::Restore,
+                Some((selected.title, save_type)),
+                Some(backup_path.clone()),
+                None,
+                confirm_visible,
+                loading,
+                move || {
+                    let (title_name, backup_name) = (title_name.clone(), backup_name.clone());
+                    let (backup_path, files) = (backup_path.clone(), files.clone());
+                    loading.write().show();
+                    tokio::task::spawn_blocking(move || {
+                        notify(
+                            Some(t!("menu.restoring").to_string()),
+                            Some(backup_name.clone()),
+                        );
+                        let res = restore_backup_file(
+                            selected.title,
+                            save_type,
+                            title_name,
+                            backup_name,
+                            backup_path,
+                            is_cached,
+                            Some(files),
+                            toast,
+                            notify,
+                        );
+                        // update local backup list
+                        fetch_game_save_local(selected.title, save_type, list_local);
+                        match res {
+                            Ok(_) => toast(t!("menu.restore_done").to_string()),
+                            Err(err) => toast(t!("menu.restore_failed", err = err)),
+                        }
+                        loading.write().hide();
+                    });
+                },
+                move || discard_backup_file(&cancel_path, is_cached),
+            );
+        }
+    };
+
+    let close_file_picker = move |_: ()| {
+        if let Some((backup_path, is_cached)) = restore_source.write().take() {
+            discard_backup_file(&backup_path, is_cached);
+        }
+    };
+
     rsx! {
-        if let Some((id, name, product_code)) = title_detail.read().as_ref() {
+        if let Some((title, diff)) = diff_result
+            .read()
+            .as_ref()
+            .filter(|(title, _)| confirm_visible.read().title().as_ref() == Some(title))
+        {
+            DiffPanel {
+                title: title.clone(),
+                diff: diff.clone(),
+            }
+        } else if let Some((id, name, product_code)) = title_detail.read().as_ref() {
             div {
                 "screen": "top",
                 "deep_3d": 1.0,
@@ -469,7 +1943,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                             if *store_type.read() == SaveStoreType::Local {
                                 loading.write().show();
                                 tokio::task::spawn_blocking(move || {
-                                    notify(Some("正在备份到本地".to_string()), None);
+                                    notify(Some(t!("menu.backing_up_local").to_string()), None);
                                     // backup
                                     if let Err(err) = get_game_local_backup_path(
                                         title,
@@ -484,18 +1958,21 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                             notify,
                                         )
                                     }) {
-                                        toast(format!("本地备份失败: {}", err));
+                                        toast(t!("menu.local_backup_failed", err = err));
                                     } else {
+                                        if let Err(err) = prune_local_backups(title, save_type) {
+                                            error!("prune local backups failed: {}", err);
+                                        }
                                         // update local list
                                         fetch_game_save_local(title, save_type, list_local);
-                                        toast("本地备份完成！".to_string());
+                                        toast(t!("menu.local_backup_done").to_string());
                                     }
                                     loading.write().hide();
                                 });
                             } else {
                                 loading.write().show();
                                 tokio::task::spawn_blocking(move || {
-                                    notify(Some("正在备份到云端".to_string()), None);
+                                    notify(Some(t!("menu.backing_up_cloud").to_string()), None);
                                     // backup
                                     let cloud_dir = { list_cloud.read().2.clone() };
                                     if let Err(err) = backup_game_save_to_cloud(
@@ -508,10 +1985,16 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                         notify,
                                         is_overwrite,
                                     ) {
-                                        toast(format!("云端备份失败: {}", err));
+                                        if err.needs_reauth() {
+                                            AuthState::expire(auth_state);
+                                        }
+                                        toast(t!("menu.cloud_backup_failed", err = err));
                                     } else {
+                                        if let Err(err) = prune_cloud_backups(title, save_type, toast) {
+                                            error!("prune cloud backups failed: {}", err);
+                                        }
                                         fetch_game_save_cloud(title, save_type, list_cloud, toast);
-                                        toast("云端备份完成！".to_string());
+                                        toast(t!("menu.cloud_backup_done").to_string());
                                     }
                                     loading.write().hide();
                                 });
@@ -524,11 +2007,11 @@ pub fn Menu(mut props: MenuProps) -> Element {
                         {
                             if *store_type.read() == SaveStoreType::Cloud && (!Api::get_read().is_login() || !Api::is_eat_pancake_valid()) {
                                 if !Api::get_read().is_login() {
-                                    toast("未登录，请重新登录！".to_string());
+                                    toast(t!("common.not_logged_in").to_string());
                                     auth_state.write().0 = false;
                                 } else {
                                     confirm_visible.write().show_qrcode(
-                                        INVALID_EAT_PANCAKE.to_string(),
+                                        t!("common.invalid_eat_pancake").to_string(),
                                         HOME_PAGE_URL.to_string(),
                                         Rc::new(RefCell::new(Box::new(move || {}))),
                                     );
@@ -536,6 +2019,11 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                 return;
                             }
 
+                            let op = if *store_type.read() == SaveStoreType::Local {
+                                PreflightOp::Backup
+                            } else {
+                                PreflightOp::CloudBackup
+                            };
                             let is_new_backup = (*store_type.read() == SaveStoreType::Local && list_local.read().0.selected_idx == 0) ||
                                 (*store_type.read() == SaveStoreType::Cloud && list_cloud.read().0.selected_idx == 0);
 
@@ -547,21 +2035,30 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                         list_cloud.read().1.get(list_cloud.read().0.selected_idx as usize - 1).map(|s| s.name.to_string())
                                     }
                                 } {
-                                    confirm_visible.write().show("覆盖当前备份？".to_string(), Rc::new(RefCell::new(Box::new(move || {
+                                    preflight_confirm(op, Some((selected.title, save_type)), Some(t!("menu.overwrite_backup").to_string()), confirm_visible, loading, move || {
                                         do_backup(true, backup_name.clone(), save_type, selected.title, title_name.clone());
-                                    }))));
+                                    });
                                 }
                             } else {
-                                if let Some(backup_name) = pl_show_swkbd(Kind::Normal, &resource, &get_current_format_time()).map(|name| {
-                                    if name.ends_with(".zip") { name } else { format!("{}.zip", name) }
+                                if let Some(backup_name) = pl_show_swkbd(Kind::Normal, &resource, &config::backup_name()).map(|name| {
+                                    if name.ends_with(".zip") || name.ends_with(SNAPSHOT_EXT) {
+                                        name
+                                    } else if *store_type.read() == SaveStoreType::Local && config::get().backup.dedup_store {
+                                        // the dedup store is opt-in, a zip can still be asked for by name
+                                        format!("{}{}", name, SNAPSHOT_EXT)
+                                    } else {
+                                        format!("{}.zip", name)
+                                    }
                                 }) {
-                                    do_backup(false, backup_name, save_type, selected.title, title_name);
+                                    preflight_confirm(op, Some((selected.title, save_type)), None, confirm_visible, loading, move || {
+                                        do_backup(false, backup_name.clone(), save_type, selected.title, title_name.clone());
+                                    });
                                 } else {
-                                    toast("备份取消！".to_string());
+                                    toast(t!("menu.backup_cancelled").to_string());
                                 }
                             }
                         } else {
-                            toast("没有存档！".to_string());
+                            toast(t!("menu.no_save").to_string());
                         }
                     }
                     // 删除
@@ -585,33 +2082,42 @@ pub fn Menu(mut props: MenuProps) -> Element {
                         {
                             if *store_type.read() == SaveStoreType::Cloud && (!Api::get_read().is_login() || !Api::is_eat_pancake_valid()) {
                                 if !Api::get_read().is_login() {
-                                    toast("未登录，请重新登录！".to_string());
+                                    toast(t!("common.not_logged_in").to_string());
                                     auth_state.write().0 = false;
                                 } else {
                                     confirm_visible.write().show_qrcode(
-                                        INVALID_EAT_PANCAKE.to_string(),
+                                        t!("common.invalid_eat_pancake").to_string(),
                                         HOME_PAGE_URL.to_string(),
                                         Rc::new(RefCell::new(Box::new(move || {}))),
                                     );
                                 }
                                 return;
                             }
-                            confirm_visible.write().show(format!("确定删除 {} ?", backup_name), Rc::new(RefCell::new(Box::new(move || {
+                            if *store_type.read() == SaveStoreType::Local && is_external_backup(&backup_name) {
+                                toast(t!("menu.external_delete").to_string());
+                                return;
+                            }
+                            confirm_visible.write().show(t!("menu.confirm_delete", name = backup_name), Rc::new(RefCell::new(Box::new(move || {
                                 if *store_type.read() == SaveStoreType::Local {
                                     let backup_name = backup_name.clone();
                                     loading.write().show();
                                     tokio::task::spawn_blocking(move || {
-                                        let target_path = join_path(HOME_LOCAL_PATH_SAVE, &save_type);
+                                        let target_path = join_path(&config::local_save_dir(), &save_type);
                                         if let Some(path) = get_local_dir_start_with(&target_path, &selected.title.id_hex_str()) {
                                             // delete local backup
                                             match fs::remove_file(join_path(&path, &backup_name)) {
                                                 Ok(_) => {
+                                                    if backup_name.ends_with(SNAPSHOT_EXT) {
+                                                        if let Err(err) = store_gc() {
+                                                            error!("store gc failed: {}", err);
+                                                        }
+                                                    }
                                                     fetch_game_save_local(selected.title, save_type, list_local);
                                                     delete_dir_if_empty(&path).ok();
-                                                    toast("删除成功！".to_string());
+                                                    toast(t!("common.delete_done").to_string());
                                                 }
                                                 Err(err) => {
-                                                    toast(format!("删除失败: {}", err));
+                                                    toast(t!("common.delete_failed", err = err));
                                                 }
                                             }
                                         }
@@ -621,7 +2127,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                     let backup_name = backup_name.clone();
                                     let cloud_dir = cloud_dir.clone();
                                     loading.write().show();
-                                    notify(Some("正在删除云端备份".to_string()), Some(backup_name.clone()));
+                                    notify(Some(t!("menu.deleting_cloud").to_string()), Some(backup_name.clone()));
                                     tokio::task::spawn_blocking(move || {
                                         if let Some(cloud_dir) = cloud_dir.clone() {
                                             let target_path = join_path(&cloud_dir, &backup_name);
@@ -644,10 +2150,10 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                                             ).ok();
                                                         });
                                                     }
-                                                    toast("删除成功！".to_string());
+                                                    toast(t!("common.delete_done").to_string());
                                                 }
                                                 Err(err) => {
-                                                    toast(format!("删除失败: {}", err));
+                                                    toast(t!("common.delete_failed", err = err));
                                                 }
                                             }
                                         }
@@ -679,21 +2185,25 @@ pub fn Menu(mut props: MenuProps) -> Element {
                         {
                             if !Api::get_read().is_login() || !Api::is_eat_pancake_valid() {
                                 if !Api::get_read().is_login() {
-                                    toast("未登录，请重新登录！".to_string());
+                                    toast(t!("common.not_logged_in").to_string());
                                     auth_state.write().0 = false;
                                 } else {
                                     confirm_visible.write().show_qrcode(
-                                        INVALID_EAT_PANCAKE.to_string(),
+                                        t!("common.invalid_eat_pancake").to_string(),
                                         HOME_PAGE_URL.to_string(),
                                         Rc::new(RefCell::new(Box::new(move || {}))),
                                     );
                                 }
                                 return;
                             }
+                            if *store_type.read() == SaveStoreType::Local && is_external_backup(&backup_name) {
+                                toast(t!("menu.import_first").to_string());
+                                return;
+                            }
                             let title_tips = if *store_type.read() == SaveStoreType::Local {
-                                format!("上传备份 {}?", &backup_name)
+                                t!("menu.confirm_upload", name = &backup_name)
                             } else {
-                                format!("下载备份 {}?", &backup_name)
+                                t!("menu.confirm_download", name = &backup_name)
                             };
                             confirm_visible.write().show(title_tips, Rc::new(RefCell::new(Box::new(move || {
                                 if *store_type.read() == SaveStoreType::Local {
@@ -702,23 +2212,26 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                     let cloud_dir = cloud_dir.clone();
                                     loading.write().show();
                                     tokio::task::spawn_blocking(move || {
-                                        notify(Some("正在上传".to_string()), Some(backup_name.clone()));
-                                        let target_path = join_path(HOME_LOCAL_PATH_SAVE, &save_type);
+                                        notify(Some(t!("common.uploading").to_string()), Some(backup_name.clone()));
+                                        let target_path = join_path(&config::local_save_dir(), &save_type);
                                         if let Some(path) = get_local_dir_start_with(&target_path, &selected.title.id_hex_str()) {
                                             let cloud_dir = get_game_cloud_backup_path(cloud_dir.clone(), selected.title, save_type, title_name.clone(), toast);
-                                            match Api::upload_to_cloud(
+                                            match upload_local_backup(
                                                 &cloud_dir,
                                                 &backup_name,
                                                 &join_path(&path, &backup_name),
-                                                false,
+                                                None,
                                                 notify,
                                             ) {
                                                 Ok(_) => {
                                                     fetch_game_save_cloud(selected.title, save_type, list_cloud, toast);
-                                                    toast("备份上传完成！".to_string());
+                                                    toast(t!("menu.upload_done").to_string());
                                                 }
                                                 Err(err) => {
-                                                    toast(format!("备份上传失败: {}", err));
+                                                    if err.needs_reauth() {
+                                                        AuthState::expire(auth_state);
+                                                    }
+                                                    toast(t!("menu.upload_failed", err = err));
                                                 }
                                             }
                                             loading.write().hide();
@@ -729,19 +2242,23 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                     let title_name = title_name.clone();
                                     loading.write().show();
                                     tokio::task::spawn_blocking(move || {
-                                        notify(Some("正在下载".to_string()), Some(backup_name.clone()));
+                                        notify(Some(t!("common.downloading").to_string()), Some(backup_name.clone()));
                                         if let Err(err) = get_game_local_backup_path(
                                             selected.title,
                                             save_type,
                                             title_name.clone(),
                                             backup_name.clone(),
                                         ).and_then(|backup_path| {
-                                            Api::start_download(fs_id, &backup_path, None)
+                                            progress::start(ProgressStage::Download, 1, 0);
+                                            with_network_retry(|| Api::start_download(fs_id, &backup_path, None))
                                         }) {
-                                            toast(format!("备份下载失败: {}", err));
+                                            if err.needs_reauth() {
+                                                AuthState::expire(auth_state);
+                                            }
+                                            toast(t!("menu.download_failed", err = err));
                                         } else {
                                             fetch_game_save_local(selected.title, save_type, list_local);
-                                            toast("备份下载完成！".to_string());
+                                            toast(t!("menu.download_done").to_string());
                                         }
                                         loading.write().hide();
                                     });
@@ -772,75 +2289,179 @@ pub fn Menu(mut props: MenuProps) -> Element {
                         {
                             if *store_type.read() == SaveStoreType::Cloud && (!Api::get_read().is_login() || !Api::is_eat_pancake_valid()) {
                                 if !Api::get_read().is_login() {
-                                    toast("未登录，请重新登录！".to_string());
+                                    toast(t!("common.not_logged_in").to_string());
                                     auth_state.write().0 = false;
                                 } else {
                                     confirm_visible.write().show_qrcode(
-                                        INVALID_EAT_PANCAKE.to_string(),
+                                        t!("common.invalid_eat_pancake").to_string(),
                                         HOME_PAGE_URL.to_string(),
                                         Rc::new(RefCell::new(Box::new(move || {}))),
                                     );
                                 }
                                 return;
                             }
-                            confirm_visible.write().show(format!("恢复存档 {}?", &backup_name), Rc::new(RefCell::new(Box::new(move || {
-                                if *store_type.read() == SaveStoreType::Local {
-                                    let backup_name = backup_name.clone();
+                            let is_local = *store_type.read() == SaveStoreType::Local;
+                            let restore_title = if save_type == SaveTypes::User {
+                                t!("menu.confirm_restore_secure_value", name = &backup_name, action = secure_value_action())
+                            } else {
+                                t!("menu.confirm_restore", name = &backup_name)
+                            };
+                            let resource = resource.clone();
+                            let title = selected.title;
+                            // the backup is fetched after the preflight of cloud restore, e.g. wifi
+                            let mut fetch_and_confirm = move || {
+                                let (backup_name, title_name, restore_title) = (backup_name.clone(), title_name.clone(), restore_title.clone());
+                                let (resource, selected) = (resource.clone(), selected.clone());
+                                loading.write().show();
+                                notify(Some(t!("menu.reading_backup").to_string()), Some(backup_name.clone()));
+                                spawn(async move {
+                                    let name = backup_name.clone();
+                                    let res = tokio::task::spawn_blocking(move || {
+                                        fetch_backup_file(selected.title, save_type, &name, (!is_local).then_some(fs_id), notify)
+                                            .map_err(|e| e.to_string())
+                                    }).await;
+                                    loading.write().hide();
+                                    let is_cached = !is_local || is_external_backup(&backup_name);
+
+                                    let backup_path = match res {
+                                        Ok(Ok(backup_path)) => backup_path,
+                                        Ok(Err(err)) => {
+                                            toast(t!("menu.restore_failed", err = err));
+                                            return;
+                                        }
+                                        Err(err) => {
+                                            toast(t!("menu.restore_failed", err = err));
+                                            return;
+                                        }
+                                    };
+                                    if !prepare_passphrase(&resource, &backup_path, toast) {
+                                        discard_backup_file(&backup_path, is_cached);
+                                        return;
+                                    }
+
+                                    let issues = preflight_issues(PreflightOp::Restore, Some((selected.title, save_type)), Some(backup_path.clone())).await;
+                                    let restore_title = match preflight_title(&restore_title, &issues) {
+                                        Some(title) => title,
+                                        None => {
+                                            discard_backup_file(&backup_path, is_cached);
+                                            show_preflight_error(confirm_visible, &issues);
+                                            return;
+                                        }
+                                    };
+
                                     loading.write().show();
-                                    tokio::task::spawn_blocking(move || {
-                                        notify(Some("正在恢复存档".to_string()), Some(backup_name.clone()));
-                                        let target_path = join_path(HOME_LOCAL_PATH_SAVE, &save_type);
-                                        if let Some(path) = get_local_dir_start_with(&target_path, &selected.title.id_hex_str()) {
-                                            let backup_path = join_path(&path, &backup_name);
-                                            match restore_backup(selected.title, save_type, backup_path, toast, notify) {
-                                                Ok(_) => {
-                                                    // update local backup list
-                                                    fetch_game_save_local(selected.title, save_type, list_local);
-                                                    toast("存档恢复完成！".to_string());
-                                                }
-                                                Err(err) => {
-                                                    toast(format!("存档恢复失败: {}", err));
-                                                }
+                                    notify(Some(t!("menu.comparing").to_string()), Some(backup_name.clone()));
+                                    let path = backup_path.clone();
+                                    let diff = tokio::task::spawn_blocking(move || {
+                                        diff_backup_with_save(selected.title, save_type, &path)
+                                            .map_err(|e| e.to_string())
+                                    }).await;
+                                    loading.write().hide();
+                                    match diff {
+                                        Ok(Ok(diff)) => diff_result.set(Some((restore_title.clone(), diff))),
+                                        Ok(Err(err)) => toast(t!("menu.compare_unavailable", err = err)),
+                                        Err(err) => toast(t!("menu.compare_unavailable", err = err)),
+                                    }
+
+                                    let cancel_path = backup_path.clone();
+                                    confirm_visible.write().show_with_cancel(restore_title, Rc::new(RefCell::new(Box::new(move || {
+                                        let backup_path = backup_path.clone();
+                                        let backup_name = backup_name.clone();
+                                        let title_name = title_name.clone();
+                                        loading.write().show();
+                                        tokio::task::spawn_blocking(move || {
+                                            notify(Some(t!("menu.restoring").to_string()), Some(backup_name.clone()));
+                                            let res = restore_backup_file(selected.title, save_type, title_name, backup_name, backup_path, is_cached, None, toast, notify);
+                                            // update local backup list
+                                            fetch_game_save_local(selected.title, save_type, list_local);
+                                            match res {
+                                                Ok(_) => toast(t!("menu.restore_done").to_string()),
+                                                Err(err) => toast(t!("menu.restore_failed", err = err)),
                                             }
                                             loading.write().hide();
+                                        });
+                                    }))), Rc::new(RefCell::new(Box::new(move || {
+                                        discard_backup_file(&cancel_path, is_cached);
+                                    }))));
+                                });
+                            };
+                            if is_local {
+                                fetch_and_confirm();
+                            } else {
+                                preflight_confirm(PreflightOp::CloudRestore, Some((title, save_type)), None, confirm_visible, loading, fetch_and_confirm);
+                            }
+                        }
+
+                    }
+                    // 更多操作
+                    Code::Enter => {
+                        let is_cloud = *store_type.read() == SaveStoreType::Cloud;
+                        if is_cloud && !auth_state.read().0 {
+                            return;
+                        }
+                        if let Some((Some(save_type), title_id)) = title_selected.read()
+                            .as_ref()
+                            .map(|s| (s.save_type, s.title.id_hex_str()))
+                        {
+                            let backup_name = if is_cloud {
+                                let cloud = list_cloud.read();
+                                (cloud.0.selected_idx > 0).then(|| cloud.1.get(cloud.0.selected_idx as usize - 1).map(|item| item.name.to_string())).flatten()
+                            } else {
+                                let local = list_local.read();
+                                (local.0.selected_idx > 0).then(|| local.1.get(local.0.selected_idx as usize - 1).map(|s| s.to_string())).flatten()
+                            };
+                            let mut actions = vec![];
+                            let info = match backup_name.as_ref() {
+                                Some(backup_name) if !is_cloud && is_external_backup(backup_name) => {
+                                    actions.push(BackupActions::RestoreFiles);
+                                    actions.push(BackupActions::ImportExternal);
+                                    t!("menu.info.external")
+                                }
+                                Some(backup_name) => {
+                                    if !is_cloud && backup_name.ends_with(SNAPSHOT_EXT) {
+                                        actions.push(BackupActions::ExportZip);
+                                    }
+                                    actions.push(BackupActions::RestoreFiles);
+                                    if !is_cloud {
+                                        if !backup_name.ends_with(ENCRYPTED_EXT) {
+                                            actions.push(BackupActions::UploadEncrypted);
                                         }
-                                    });
-                                } else {
-                                    let backup_name = backup_name.clone();
-                                    let title_name = title_name.clone();
-                                    loading.write().show();
-                                    tokio::task::spawn_blocking(move || {
-                                        notify(Some("正在下载".to_string()), Some(backup_name.clone()));
-                                        if let Err(err) = get_game_local_backup_path(
-                                            selected.title,
-                                            save_type,
-                                            title_name.clone(),
-                                            backup_name.clone(),
-                                        ).and_then(|backup_path| {
-                                            Api::start_download(fs_id, &backup_path, None).and_then(|_| {
-                                                // restore download backup
-                                                let res = restore_backup(selected.title, save_type, backup_path.clone(), toast, notify);
-                                                // remove download backup after restore
-                                                fs::remove_file(&backup_path).ok();
-                                                // update local backup list
-                                                fetch_game_save_local(selected.title, save_type, list_local);
-                                                // remove local backup dir if empty
-                                                if let Some(parent) = Path::new(&backup_path).parent() {
-                                                    delete_dir_if_empty(parent).ok();
-                                                }
-                                                res
-                                            })
-                                        }) {
-                                            toast(format!("存档恢复失败: {}", err));
-                                        } else {
-                                            toast("存档恢复完成！".to_string());
+                                        actions.push(BackupActions::CompareSave);
+                                        // list is sorted newest first
+                                        if list_local.read().1.get(list_local.read().0.selected_idx as usize).is_some_and(|name| !is_external_backup(name)) {
+                                            actions.push(BackupActions::ComparePrevious);
                                         }
-                                        loading.write().hide();
-                                    });
+                                        if ExternalTool::Checkpoint.root(&save_type).is_some() {
+                                            actions.push(BackupActions::ExportCheckpoint);
+                                            actions.push(BackupActions::ExportEmulator);
+                                        }
+                                    }
+                                    let key = RetentionConfig::protect_key(is_cloud, &title_id, &save_type, backup_name);
+                                    if retention.read().is_protected(&key) {
+                                        actions.push(BackupActions::Unprotect);
+                                        t!("menu.info.protected")
+                                    } else {
+                                        actions.push(BackupActions::Protect);
+                                        t!("menu.info.backup")
+                                    }
                                 }
-                            }))));
+                                None => t!("menu.info.all"),
+                            };
+                            actions.push(BackupActions::Prune);
+                            if save_type == SaveTypes::Ext {
+                                actions.push(BackupActions::ShowExtdataId);
+                            }
+                            if save_type == SaveTypes::User {
+                                actions.push(BackupActions::SecureValue);
+                            }
+                            if session_passphrase().is_some() {
+                                actions.push(BackupActions::ClearPassphrase);
+                            } else {
+                                actions.push(BackupActions::SetPassphrase);
+                            }
+                            backup_actions.set(Some((actions, backup_name.unwrap_or_default(), info.to_string())));
+                            backup_menu_visible.write().show();
                         }
-
                     }
                     Code::KeyB => {
                         props.visible.write().hide();
@@ -892,7 +2513,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                         }
                     },
 
-                    "本地备份"
+                    {t!("menu.local_backups")}
                 }
 
                 div {
@@ -913,7 +2534,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                             store_type.set(SaveStoreType::Cloud);
                         }
                     },
-                    "云端备份"
+                    {t!("menu.cloud_backups")}
                 }
             }
 
@@ -937,7 +2558,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                     }
                                     list_local.write().0.set_selected_idx(0);
                                 },
-                                "新建本地备份"
+                                {t!("menu.new_local_backup")}
                             }
                         } else if let Some(save) = list_local.read().1.get((list_local.read().0.top_row + idx - 1) as usize){
                             Item {
@@ -946,6 +2567,9 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                     let idx = list_local.read().0.top_row + idx;
                                     list_local.write().0.set_selected_idx(idx);
                                 },
+                                if title_selected.read().as_ref().and_then(|s| s.save_type.map(|st| (st, s.title.id_hex_str()))).is_some_and(|(st, id)| retention.read().is_protected(&RetentionConfig::protect_key(false, &id, &st, save))) {
+                                    {t!("menu.protected_mark")}
+                                }
                                 "{save}"
                             }
                         }
@@ -962,7 +2586,7 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                         }
                                         list_cloud.write().0.set_selected_idx(0);
                                     },
-                                    "新建云端备份"
+                                    {t!("menu.new_cloud_backup")}
                                 }
                             } else if let Some(save) = list_cloud.read().1.get((list_cloud.read().0.top_row + idx - 1) as usize){
                                 Item {
@@ -971,6 +2595,9 @@ pub fn Menu(mut props: MenuProps) -> Element {
                                         let idx = list_cloud.read().0.top_row + idx;
                                         list_cloud.write().0.set_selected_idx(idx);
                                     },
+                                    if title_selected.read().as_ref().and_then(|s| s.save_type.map(|st| (st, s.title.id_hex_str()))).is_some_and(|(st, id)| retention.read().is_protected(&RetentionConfig::protect_key(true, &id, &st, &save.name))) {
+                                        {t!("menu.protected_mark")}
+                                    }
                                     "{save.name}"
                                 }
                             }
@@ -990,9 +2617,34 @@ pub fn Menu(mut props: MenuProps) -> Element {
 
             ActionBar {
                 tips:if *store_type.read() == SaveStoreType::Local
-                  { "(SELECT) 上传   (Y) 恢复   (X) 删除   (B) 关闭   (A) 选择" }
+                  { t!("menu.tips_local") }
                   else
-                  { "(SELECT) 下载   (Y) 恢复   (X) 删除   (B) 关闭   (A) 选择" }
+                  { t!("menu.tips_cloud") }
+            }
+        }
+
+        if backup_menu_visible.read().is_show() {
+            Dialog {
+                visible: backup_menu_visible,
+                BackupMenu {
+                    visible: backup_menu_visible,
+                    actions: backup_actions,
+                    onaction: do_backup_action,
+                }
+            }
+        }
+
+        if file_picker_visible.read().is_show() {
+            Dialog {
+                visible: file_picker_visible,
+                FilePicker {
+                    visible: file_picker_visible,
+                    files: restore_files,
+                    onrestore: do_restore_files,
+                    onclose: close_file_picker,
+                    note: title_selected.read().as_ref()
+                        .and_then(|s| (s.save_type == Some(SaveTypes::User)).then(|| secure_value_action().to_string())),
+                }
             }
         }
     }
@@ -6,6 +6,7 @@ use crate::{
         titles::title_selected::TitleSelected,
     },
     resource::TitleInfo,
+    t,
 };
 
 #[component]
@@ -36,7 +37,7 @@ pub fn TitleList(is_pending: bool) -> Element {
                     margin_bottom: 20.0,
                 }
 
-                "正在加载 {percent}%"
+                {t!("titles.loading", percent = percent)}
             }
         };
     }
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Display, Formatter},
     ops::Deref,
     sync::{Mutex, OnceLock},
@@ -10,16 +10,33 @@ use dioxus::prelude::*;
 
 use crate::{
     app::dialog::DialogVisible,
+    fsu::{self, ArchiveInfo},
     platform::{
         is_exists_boss_game_save, is_exists_ext_game_save, is_exists_sys_game_save,
-        is_exists_user_game_save,
+        is_exists_user_game_save, update_title_save_types,
     },
     resource::TitleInfo,
+    t,
+    utils::storage_size_to_info,
 };
 
+/// titles whose cached save types were looked for extdata again in this session
+static TITLE_EXT_CHECKED: OnceLock<Mutex<HashSet<u64>>> = OnceLock::new();
 static TITLE_SAVE_TYPES_DATA: OnceLock<Mutex<HashMap<u64, u8>>> = OnceLock::new();
+/// capacity of the save archives, `None` if it can not be read
+static TITLE_ARCH_INFOS: OnceLock<Mutex<HashMap<(u64, SaveTypes), Option<ArchiveInfo>>>> =
+    OnceLock::new();
 
-#[derive(Clone, Copy, PartialEq)]
+/// `true` only on the first call for the title in this session
+fn is_first_ext_check(title_id: u64) -> bool {
+    TITLE_EXT_CHECKED
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .map(|mut lock| lock.insert(title_id))
+        .unwrap_or(false)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SaveTypes {
     User,
     Ext,
@@ -46,6 +63,49 @@ impl SaveTypes {
         }
     }
 
+    pub fn clear_titles_save_types() {
+        if let Ok(mut lock) = Self::get_titles_save_types().lock() {
+            lock.clear();
+        }
+    }
+
+    fn get_arch_infos() -> &'static Mutex<HashMap<(u64, SaveTypes), Option<ArchiveInfo>>> {
+        TITLE_ARCH_INFOS.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// capacity of the save archive of the title, the archive is only opened
+    /// the first time, later calls use the cache until `clear_arch_info`
+    pub fn arch_info(&self, title: TitleInfo) -> Option<ArchiveInfo> {
+        if let Some(info) = Self::get_arch_infos()
+            .lock()
+            .ok()
+            .and_then(|lock| lock.get(&(title.id, *self)).copied())
+        {
+            return info;
+        }
+        let info = fsu::arch(
+            self.arch_id(),
+            title.fs_media_type,
+            title.high_id(),
+            title.low_id(),
+        )
+        .ok()
+        .and_then(|arch| {
+            fsu::arch_info(&arch, title.fs_media_type, title.high_id(), title.low_id()).ok()
+        });
+        if let Ok(mut lock) = Self::get_arch_infos().lock() {
+            lock.insert((title.id, *self), info);
+        }
+        info
+    }
+
+    /// the save archives of the title are changed
+    pub fn clear_arch_info(title_id: u64) {
+        if let Ok(mut lock) = Self::get_arch_infos().lock() {
+            lock.retain(|(id, _), _| *id != title_id);
+        }
+    }
+
     pub fn arch_id(&self) -> ArchiveID {
         match self {
             SaveTypes::User => ArchiveID::UserSavedata,
@@ -95,7 +155,18 @@ impl TitleSelected {
             save_type: None,
             saves: vec![],
         };
-        if let Some(save_type) = SaveTypes::get_title_save_type(title.id) {
+        if let Some(save_type) = SaveTypes::get_title_save_type(title.id).map(|save_type| {
+            // games create their extdata on the first launch, which may be after the cache was
+            // written, look for it again once in a session
+            if save_type & 0b0010 == 0
+                && is_first_ext_check(title.id)
+                && is_exists_ext_game_save(title.high_id(), title.low_id(), title.fs_media_type)
+            {
+                update_title_save_types(title.id, title.fs_media_type)
+            } else {
+                save_type
+            }
+        }) {
             if save_type & 0b0001 != 0 {
                 res.saves.push(SaveTypes::User);
             }
@@ -148,6 +219,12 @@ impl TitleSelected {
     }
 }
 
+/// short size for the save types bar, e.g. 512K
+fn short_size(size: u64) -> String {
+    let (p, unit) = storage_size_to_info(size as f64);
+    format!("{:.0}{}", size as f64 / p, unit)
+}
+
 pub fn TitleSaveTypes(props: TitleSelectedProps) -> Element {
     let mut title_selected = use_context::<Signal<Option<TitleSelected>>>();
 
@@ -186,7 +263,7 @@ pub fn TitleSaveTypes(props: TitleSelectedProps) -> Element {
 
                 if info.saves.is_empty() {
                     div {
-                        "没有存档数据"
+                        {t!("titles.no_save_data")}
                     }
                 } else if info.saves.len() > 1 {
                     div {
@@ -243,6 +320,20 @@ pub fn TitleSaveTypes(props: TitleSelectedProps) -> Element {
                             props.onclick.call(());
                         },
                         "{save}"
+                        // only the selected one is read, extdata has no fixed size
+                        if let Some(arch) = info
+                            .save_type
+                            .filter(|&selected| selected == save)
+                            .and_then(|save| save.arch_info(info.title))
+                            .filter(|arch| arch.total > 0)
+                        {
+                            div {
+                                "scale": 0.28,
+                                color: "tips",
+                                margin_left: 4.0,
+                                "{short_size(arch.used())}/{short_size(arch.total)}"
+                            }
+                        }
                     }
                 }
             }
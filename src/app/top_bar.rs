@@ -5,6 +5,7 @@ use dioxus::prelude::*;
 use crate::{
     app::{line::Line, Panel},
     constant::SELECTED_BG_COLOR,
+    t,
 };
 
 #[component]
@@ -13,7 +14,8 @@ pub fn NavItem(is_pending: bool) -> Element {
 
     let left = match *selected_panel.read() {
         Panel::Device => 9,
-        _ => 46,
+        Panel::Cloud => 46,
+        Panel::Settings => 83,
     };
 
     rsx! {
@@ -33,12 +35,16 @@ pub fn NavItem(is_pending: bool) -> Element {
                   }
                   let current_panel = *selected_panel.read();
                   match e.data.code() {
-                      Code::KeyL if current_panel != Panel::Device => {
-                          *selected_panel.write() = Panel::Device;
-                      }
-                      Code::KeyR if current_panel != Panel::Cloud => {
-                          *selected_panel.write() = Panel::Cloud;
-                      }
+                      Code::KeyL => match current_panel {
+                          Panel::Cloud => *selected_panel.write() = Panel::Device,
+                          Panel::Settings => *selected_panel.write() = Panel::Cloud,
+                          Panel::Device => {}
+                      },
+                      Code::KeyR => match current_panel {
+                          Panel::Device => *selected_panel.write() = Panel::Cloud,
+                          Panel::Cloud => *selected_panel.write() = Panel::Settings,
+                          Panel::Settings => {}
+                      },
                       _ => {}
                   }
               },
@@ -49,7 +55,7 @@ pub fn NavItem(is_pending: bool) -> Element {
             position: "absolute",
             display: "flex",
             padding_top: 26,
-            width: 86,
+            width: 123,
             height: 34,
 
             div {
@@ -82,7 +88,7 @@ pub fn NavItem(is_pending: bool) -> Element {
               display: "flex",
               justify_content: "space-around",
               align_items: "center",
-              width: 86,
+              width: 123,
               height: 34,
               padding_left: 6,
               padding_right: 6,
@@ -112,6 +118,23 @@ pub fn NavItem(is_pending: bool) -> Element {
                       *selected_panel.write() = Panel::Cloud;
                   }
               }
+
+              div {
+                  "scale": 0.36,
+                  display: "flex",
+                  justify_content: "center",
+                  align_items: "center",
+                  width: 24,
+                  height: 24,
+                  onclick: move |_| {
+                      if is_pending || *selected_panel.read() == Panel::Settings {
+                          return;
+                      }
+                      *selected_panel.write() = Panel::Settings;
+                  },
+
+                  {t!("settings.nav")}
+              }
           }
         }
     }
@@ -151,7 +174,7 @@ pub fn NavBar(props: NavProps) -> Element {
                 margin_left: 6,
             }
 
-            // device and cloud image
+            // device, cloud and settings
             NavItem {
                 is_pending: props.is_pending
             }
@@ -0,0 +1,38 @@
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{error::AppError, http::http_set_cancelled};
+
+/// cancel flag of the running operation, there is only one at a time
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// # clear the flag before an operation starts
+///
+/// also called before a rollback, so the rollback is not cancelled
+pub fn reset() {
+    CANCELLED.store(false, Ordering::SeqCst);
+    unsafe { http_set_cancelled(false) };
+}
+
+/// cancel the running operation, transfers of curl are aborted too
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+    unsafe { http_set_cancelled(true) };
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// # `Err` if the operation is cancelled, checked between chunks and entries
+///
+/// the payload is `AppError::Cancelled`, the kind is not `Interrupted` that std and
+/// zip retry
+pub fn check() -> io::Result<()> {
+    if is_cancelled() {
+        return Err(io::Error::other(AppError::Cancelled));
+    }
+    Ok(())
+}
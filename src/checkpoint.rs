@@ -0,0 +1,291 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    ops::Deref,
+    path::Path,
+};
+
+use crate::constant::{
+    CHECKPOINT_EXTDATA_PATH, CHECKPOINT_SAVES_PATH, INVALID_CHARS, JKSM_EXTDATA_PATH,
+    JKSM_SAVES_PATH,
+};
+
+/// # save managers that keep backups as raw folders
+///
+/// - Checkpoint: `<root>/<0x unique id> <title name>/<backup name>/`
+/// - JKSM: `<root>/<title name>/<backup name>/`
+/// - Emulator: zips or folders in the sdmc layout of emulators, see `emulator`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExternalTool {
+    Checkpoint,
+    Jksm,
+    Emulator,
+}
+
+impl Deref for ExternalTool {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ExternalTool::Checkpoint => "Checkpoint",
+            ExternalTool::Jksm => "JKSM",
+            ExternalTool::Emulator => "Emulator",
+        }
+    }
+}
+
+impl Display for ExternalTool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.deref())
+    }
+}
+
+impl ExternalTool {
+    /// backup root of the title dirs of the save type, only user save and
+    /// extdata are supported, emulator backups have no title dirs
+    pub fn root(&self, save_type: &str) -> Option<&'static str> {
+        match (self, save_type) {
+            (ExternalTool::Checkpoint, "user") => Some(CHECKPOINT_SAVES_PATH),
+            (ExternalTool::Checkpoint, "ext") => Some(CHECKPOINT_EXTDATA_PATH),
+            (ExternalTool::Jksm, "user") => Some(JKSM_SAVES_PATH),
+            (ExternalTool::Jksm, "ext") => Some(JKSM_EXTDATA_PATH),
+            _ => None,
+        }
+    }
+
+    fn is_title_dir(&self, dir_name: &str, unique_id: u32, title_name: &str) -> bool {
+        match self {
+            ExternalTool::Checkpoint => dir_name
+                .to_uppercase()
+                .starts_with(&checkpoint_id_prefix(unique_id).to_uppercase()),
+            ExternalTool::Jksm => {
+                !title_name.trim().is_empty() && name_key(dir_name) == name_key(title_name)
+            }
+            ExternalTool::Emulator => false,
+        }
+    }
+}
+
+/// `0x%05X`, checkpoint names title dirs by the unique id, aka `low id >> 8`
+fn checkpoint_id_prefix(unique_id: u32) -> String {
+    format!("0x{:05X} ", unique_id)
+}
+
+/// title names of the tools differ in invalid chars and spaces
+fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| !INVALID_CHARS.contains(c) && !c.is_whitespace())
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalBackup {
+    pub tool: ExternalTool,
+    pub name: String,
+    /// the backup folder, or the zip of emulator backups
+    pub path: String,
+}
+
+impl ExternalBackup {
+    /// name shown in the list of local backups, e.g. `[Checkpoint] 20240101-120000`
+    pub fn list_name(&self) -> String {
+        format!("[{}] {}", self.tool, self.name)
+    }
+}
+
+pub fn is_external_backup(list_name: &str) -> bool {
+    [
+        ExternalTool::Checkpoint,
+        ExternalTool::Jksm,
+        ExternalTool::Emulator,
+    ]
+    .iter()
+    .any(|tool| list_name.starts_with(&format!("[{}] ", tool)))
+}
+
+fn find_title_dir(
+    tool: ExternalTool,
+    unique_id: u32,
+    title_name: &str,
+    save_type: &str,
+) -> Option<String> {
+    let root = tool.root(save_type)?;
+    fs::read_dir(root).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let name = path.file_name()?.to_str()?;
+        (path.is_dir() && tool.is_title_dir(name, unique_id, title_name))
+            .then(|| path.display().to_string())
+    })
+}
+
+/// # backups of the title made by other tools
+///
+/// sorted by name, newest first for the default names of the tools
+pub fn list_external_backups(
+    unique_id: u32,
+    title_name: &str,
+    save_type: &str,
+) -> Vec<ExternalBackup> {
+    let mut list = vec![];
+    for tool in [ExternalTool::Checkpoint, ExternalTool::Jksm] {
+        let title_dir = match find_title_dir(tool, unique_id, title_name, save_type) {
+            Some(dir) => dir,
+            None => continue,
+        };
+        let mut backups = fs::read_dir(&title_dir)
+            .map(|dirs| {
+                dirs.flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .filter_map(|path| {
+                        Some(ExternalBackup {
+                            tool,
+                            name: path.file_name()?.to_str()?.to_string(),
+                            path: path.display().to_string(),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        backups.sort_by(|a, b| b.name.cmp(&a.name));
+        list.extend(backups);
+    }
+    list
+}
+
+/// find the backup by the name in the list of local backups
+pub fn find_external_backup(
+    unique_id: u32,
+    title_name: &str,
+    save_type: &str,
+    list_name: &str,
+) -> Option<ExternalBackup> {
+    list_external_backups(unique_id, title_name, save_type)
+        .into_iter()
+        .find(|backup| backup.list_name() == list_name)
+}
+
+/// # folder to export a backup as checkpoint backup
+///
+/// the title dir of checkpoint is used if exists, `None` if the save type is
+/// not supported by checkpoint
+pub fn checkpoint_backup_dir(
+    unique_id: u32,
+    title_name: &str,
+    save_type: &str,
+    backup_name: &str,
+) -> Option<String> {
+    let root = ExternalTool::Checkpoint.root(save_type)?;
+    let title_dir = find_title_dir(ExternalTool::Checkpoint, unique_id, title_name, save_type)
+        .unwrap_or_else(|| {
+            let name = title_name
+                .chars()
+                .filter(|c| !INVALID_CHARS.contains(c))
+                .collect::<String>();
+            Path::new(root)
+                .join(format!("{}{}", checkpoint_id_prefix(unique_id), name.trim()).trim())
+                .display()
+                .to_string()
+        });
+    Some(
+        Path::new(&title_dir)
+            .join(backup_name)
+            .display()
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_prefix() {
+        for (unique_id, prefix) in [
+            (0x55D, "0x0055D "),
+            (0x55D00 >> 8, "0x0055D "),
+            (0xA2FFF, "0xA2FFF "),
+            (0, "0x00000 "),
+        ] {
+            assert_eq!(checkpoint_id_prefix(unique_id), prefix);
+        }
+    }
+
+    #[test]
+    fn name_keys() {
+        for (name, key) in [
+            ("Pokémon Y", "PokémonY"),
+            (" Pokémon  Y ", "PokémonY"),
+            ("Zelda: A Link Between Worlds", "ZeldaALinkBetweenWorlds"),
+            ("a/b\\c?", "abc"),
+            ("", ""),
+        ] {
+            assert_eq!(name_key(name), key, "{}", name);
+        }
+    }
+
+    #[test]
+    fn title_dirs() {
+        for (tool, dir_name, title_name, is_title_dir) in [
+            (ExternalTool::Checkpoint, "0x0055D Pokémon Y", "", true),
+            (ExternalTool::Checkpoint, "0x0055d pokémon y", "", true),
+            (
+                ExternalTool::Checkpoint,
+                "0x0055E Pokémon X",
+                "Pokémon Y",
+                false,
+            ),
+            (ExternalTool::Checkpoint, "Pokémon Y", "Pokémon Y", false),
+            (ExternalTool::Jksm, "Pokemon Y", "Pokémon Y", false),
+            (
+                ExternalTool::Jksm,
+                "Zelda A Link Between Worlds",
+                "Zelda: A Link Between Worlds",
+                true,
+            ),
+            (ExternalTool::Jksm, "", " ", false),
+            (
+                ExternalTool::Emulator,
+                "0x0055D Pokémon Y",
+                "Pokémon Y",
+                false,
+            ),
+        ] {
+            assert_eq!(
+                tool.is_title_dir(dir_name, 0x55D, title_name),
+                is_title_dir,
+                "{} {}",
+                tool,
+                dir_name
+            );
+        }
+    }
+
+    #[test]
+    fn backup_dirs() {
+        // there is no checkpoint dir on the host, new title dirs are named by the id and title
+        for (title_name, save_type, dir) in [
+            (
+                "Pokémon Y",
+                "user",
+                Some("/3ds/Checkpoint/saves/0x0055D Pokémon Y/2024"),
+            ),
+            (
+                "Zelda: A Link ",
+                "ext",
+                Some("/3ds/Checkpoint/extdata/0x0055D Zelda A Link/2024"),
+            ),
+            ("", "user", Some("/3ds/Checkpoint/saves/0x0055D/2024")),
+            ("Pokémon Y", "sys", None),
+            ("Pokémon Y", "boss", None),
+        ] {
+            assert_eq!(
+                checkpoint_backup_dir(0x55D, title_name, save_type, "2024").as_deref(),
+                dir,
+                "{} {}",
+                title_name,
+                save_type
+            );
+        }
+    }
+}
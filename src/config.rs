@@ -0,0 +1,567 @@
+use std::{
+    error::Error,
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::{write::FileOptions, CompressionMethod};
+
+use crate::{
+    constant::{
+        HOME_LOCAL_PATH_SAVE, INVALID_CHARS, LANGUAGE_CONFIG_PATH, PREFLIGHT_CONFIG_PATH,
+        RETENTION_CONFIG_PATH,
+    },
+    http::http_set_options,
+    i18n::{interpolate, set_lang, Lang},
+    platform::pl_system_lang,
+    preflight::PreflightConfig,
+    retention::RetentionConfig,
+    t,
+    utils::get_current_format_time,
+};
+
+/// version of the config file, bumped when a field is moved or changes meaning
+pub const CONFIG_VERSION: u32 = 2;
+/// placeholder of the time in the backup name format
+pub const BACKUP_NAME_TIME: &str = "{time}";
+
+/// config of the session, `None` until `init`
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+/// theme is read on every draw, kept out of the lock
+static LIGHT_THEME: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreType {
+    #[default]
+    Local,
+    Cloud,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Stored,
+    #[default]
+    Deflated,
+}
+
+/// # backup options
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// name of new backups, starts with `{time}` so that retention can parse it
+    pub name_format: String,
+    pub compression: Compression,
+    /// 0 to 9 of deflated, the default level of zip if `None`
+    pub compression_level: Option<i32>,
+    /// new local backups go to the deduplicated store as `.snap` instead of a zip
+    pub dedup_store: bool,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            name_format: BACKUP_NAME_TIME.to_string(),
+            compression: Compression::default(),
+            compression_level: None,
+            dedup_store: false,
+        }
+    }
+}
+
+impl BackupConfig {
+    pub fn file_options(&self) -> FileOptions {
+        match self.compression {
+            Compression::Stored => {
+                FileOptions::default().compression_method(CompressionMethod::Stored)
+            }
+            Compression::Deflated => FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(self.compression_level),
+        }
+    }
+}
+
+/// # network options
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// times to try a request again when it failed with a retryable error
+    pub retry_times: usize,
+    pub connect_timeout_secs: u32,
+    /// receive buffer of curl
+    pub buffer_kib: u32,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            retry_times: 2,
+            connect_timeout_secs: 30,
+            buffer_kib: 128,
+        }
+    }
+}
+
+/// # where backups are saved
+///
+/// the dir of cloud backups is looked up by the api, it can not be changed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathsConfig {
+    pub local_save_dir: String,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            local_save_dir: HOME_LOCAL_PATH_SAVE.to_string(),
+        }
+    }
+}
+
+/// # config of the app, saved as json
+///
+/// missing fields are filled with defaults, invalid values are reset by `validate`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    /// follow the system language if not set
+    pub language: Option<Lang>,
+    pub default_store: StoreType,
+    pub backup: BackupConfig,
+    /// global rule, rules of titles and protected backups
+    pub retention: RetentionConfig,
+    pub theme: Theme,
+    pub network: NetworkConfig,
+    pub paths: PathsConfig,
+    pub preflight: PreflightConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            language: None,
+            default_store: StoreType::default(),
+            backup: BackupConfig::default(),
+            retention: RetentionConfig::default(),
+            theme: Theme::default(),
+            network: NetworkConfig::default(),
+            paths: PathsConfig::default(),
+            preflight: PreflightConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// # load the config, migrated to the current version and validated
+    ///
+    /// default config if the file does not exist or can not be parsed
+    pub fn load(path: impl AsRef<Path>) -> (Self, Vec<String>) {
+        let mut value = fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+            .filter(|value| value.is_object())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        migrate(&mut value, read_json);
+        let mut config: Config = serde_json::from_value(value).unwrap_or_else(|err| {
+            error!("parse config failed: {}", err);
+            Config::default()
+        });
+        let issues = config.validate();
+        (config, issues)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// # reset invalid values to defaults
+    ///
+    /// returns messages of the reset values
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut issues = vec![];
+        let default = Config::default();
+
+        let name_rest = self.backup.name_format.strip_prefix(BACKUP_NAME_TIME);
+        if name_rest.is_none_or(|rest| {
+            rest.contains(|c| INVALID_CHARS.contains(&c) || c == '{' || c == '}')
+        }) {
+            issues.push(t!(
+                "config.invalid_name_format",
+                value = self.backup.name_format
+            ));
+            self.backup.name_format = default.backup.name_format;
+        }
+        match (self.backup.compression, self.backup.compression_level) {
+            (Compression::Deflated, Some(level)) if !(0..=9).contains(&level) => {
+                issues.push(t!("config.invalid_compression_level", value = level));
+                self.backup.compression_level = None;
+            }
+            (Compression::Stored, Some(_)) => self.backup.compression_level = None,
+            _ => {}
+        }
+        for rule in [&mut self.retention.global]
+            .into_iter()
+            .chain(self.retention.titles.values_mut())
+        {
+            if rule.keep_last == Some(0) {
+                rule.keep_last = None;
+            }
+        }
+        if self.network.retry_times > 5 {
+            issues.push(t!(
+                "config.invalid_retry_times",
+                value = self.network.retry_times
+            ));
+            self.network.retry_times = default.network.retry_times;
+        }
+        if !(5..=120).contains(&self.network.connect_timeout_secs) {
+            issues.push(t!(
+                "config.invalid_connect_timeout",
+                value = self.network.connect_timeout_secs
+            ));
+            self.network.connect_timeout_secs = default.network.connect_timeout_secs;
+        }
+        if !(16..=512).contains(&self.network.buffer_kib) {
+            issues.push(t!("config.invalid_buffer", value = self.network.buffer_kib));
+            self.network.buffer_kib = default.network.buffer_kib;
+        }
+        if !is_valid_dir(&self.paths.local_save_dir) {
+            issues.push(t!(
+                "config.invalid_local_dir",
+                value = self.paths.local_save_dir
+            ));
+            self.paths.local_save_dir = default.paths.local_save_dir;
+        }
+        self.version = CONFIG_VERSION;
+        issues
+    }
+}
+
+/// an absolute dir other than the root, `..` is not allowed
+fn is_valid_dir(dir: &str) -> bool {
+    dir.len() > 1
+        && dir.starts_with('/')
+        && !dir.ends_with('/')
+        && dir
+            .split('/')
+            .skip(1)
+            .all(|name| !name.is_empty() && name != "." && name != "..")
+}
+
+fn read_json(path: &str) -> Option<Value> {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+}
+
+/// # migrate the json of older versions to the current version
+///
+/// `legacy` reads the json of the legacy config files
+///
+/// - 0: language and the global retention rule were saved in their own files
+/// - 1: rules of titles, protected backups and the preflight config were saved
+///   in their own files
+fn migrate(value: &mut Value, legacy: impl Fn(&str) -> Option<Value>) {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version < 1 {
+        let legacy_key =
+            |path: &str, key: &str| legacy(path).and_then(|value| value.get(key).cloned());
+        if let Some(lang) = legacy_key(LANGUAGE_CONFIG_PATH, "lang") {
+            value["language"] = lang;
+        }
+        if let Some(rule) = legacy_key(RETENTION_CONFIG_PATH, "global") {
+            value["retention"] = rule;
+        }
+        value["version"] = 1.into();
+    }
+    if version < 2 {
+        if let Some(retention) = legacy(RETENTION_CONFIG_PATH) {
+            if !value.get("retention").is_some_and(Value::is_object) {
+                value["retention"] = Value::Object(Default::default());
+            }
+            for key in ["titles", "protected"] {
+                if let Some(item) = retention.get(key) {
+                    value["retention"][key] = item.clone();
+                }
+            }
+        }
+        if let Some(preflight) = legacy(PREFLIGHT_CONFIG_PATH) {
+            value["preflight"] = preflight;
+        }
+        value["version"] = 2.into();
+    }
+}
+
+fn apply(config: &Config) {
+    set_lang(config.language.unwrap_or_else(pl_system_lang));
+    LIGHT_THEME.store(config.theme == Theme::Light, Ordering::Relaxed);
+    http_set_options(
+        config.network.connect_timeout_secs,
+        config.network.buffer_kib * 1024,
+    );
+}
+
+/// # load the config of the session
+///
+/// the migrated config is saved back, returns messages of the reset values
+pub fn init(path: impl AsRef<Path>) -> Vec<String> {
+    let (config, issues) = Config::load(&path);
+    if let Err(err) = config.save(&path) {
+        error!("save config failed: {}", err);
+    }
+    apply(&config);
+    *CONFIG.write().unwrap() = Some(config);
+    issues
+}
+
+/// config of the session, default config before `init`
+pub fn get() -> Config {
+    CONFIG.read().unwrap().clone().unwrap_or_default()
+}
+
+/// # change the config of the session, validated and saved
+///
+/// returns messages of the reset values
+pub fn update(
+    path: impl AsRef<Path>,
+    f: impl FnOnce(&mut Config),
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut config = get();
+    f(&mut config);
+    let issues = config.validate();
+    config.save(path)?;
+    apply(&config);
+    *CONFIG.write().unwrap() = Some(config);
+    Ok(issues)
+}
+
+pub fn theme() -> Theme {
+    if LIGHT_THEME.load(Ordering::Relaxed) {
+        Theme::Light
+    } else {
+        Theme::Dark
+    }
+}
+
+pub fn local_save_dir() -> String {
+    CONFIG
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|config| config.paths.local_save_dir.clone())
+        .unwrap_or_else(|| HOME_LOCAL_PATH_SAVE.to_string())
+}
+
+/// name of a new backup without extension, by `name_format` of the config
+pub fn backup_name() -> String {
+    interpolate(
+        &get().backup.name_format,
+        &[("time", get_current_format_time().to_string())],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{preflight::PreflightCheck, retention::RetentionRule};
+
+    fn migrated(value: Value, legacy: &[(&str, Value)]) -> Config {
+        let legacy = legacy.iter().cloned().collect::<HashMap<_, _>>();
+        let mut value = value;
+        migrate(&mut value, |path| legacy.get(path).cloned());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn defaults() {
+        let path = env::temp_dir().join(format!("save-cloud-config-{}", std::process::id()));
+        let (config, issues) = Config::load(&path);
+        assert_eq!(config, Config::default());
+        assert!(issues.is_empty());
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.backup.name_format, BACKUP_NAME_TIME);
+        assert_eq!(config.paths.local_save_dir, HOME_LOCAL_PATH_SAVE);
+
+        // missing fields are filled, a broken file is the default config
+        fs::write(
+            &path,
+            r#"{"version":2,"theme":"light","network":{"retry_times":0}}"#,
+        )
+        .unwrap();
+        let (config, _) = Config::load(&path);
+        assert_eq!(config.theme, Theme::Light);
+        assert_eq!(config.network.retry_times, 0);
+        assert_eq!(
+            config.network.buffer_kib,
+            NetworkConfig::default().buffer_kib
+        );
+        fs::write(&path, "[]").unwrap();
+        assert_eq!(Config::load(&path).0, Config::default());
+        fs::write(&path, r#"{"theme":1}"#).unwrap();
+        assert_eq!(Config::load(&path).0, Config::default());
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = env::temp_dir().join(format!("save-cloud-config-saved-{}", std::process::id()));
+        let mut config = Config {
+            language: Some(Lang::Ja),
+            ..Default::default()
+        };
+        config.retention.titles.insert(
+            "0x4000000055D00".to_string(),
+            RetentionRule {
+                keep_last: Some(3),
+                ..Default::default()
+            },
+        );
+        config
+            .retention
+            .protected
+            .insert("local/0x4000000055D00/user/a".to_string());
+        config.save(&path).unwrap();
+        let res = Config::load(&path);
+        fs::remove_file(&path).ok();
+        assert_eq!(res, (config, vec![]));
+    }
+
+    #[test]
+    fn migrate_legacy_files() {
+        let retention = json!({
+            "global": {"keep_last": 5},
+            "titles": {"0x4000000055D00": {"keep_last": 2}},
+            "protected": ["local/0x4000000055D00/user/a"],
+        });
+        let preflight = json!({"checks": ["battery"], "min_battery_level": 3});
+        let legacy = [
+            (LANGUAGE_CONFIG_PATH, json!({"lang": "en"})),
+            (RETENTION_CONFIG_PATH, retention.clone()),
+            (PREFLIGHT_CONFIG_PATH, preflight),
+        ];
+
+        // version 0, everything was in its own file
+        let config = migrated(json!({}), &legacy);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.language, Some(Lang::En));
+        assert_eq!(config.retention.global.keep_last, Some(5));
+        assert_eq!(config.retention.rule("0x4000000055D00").keep_last, Some(2));
+        assert!(config
+            .retention
+            .is_protected("local/0x4000000055D00/user/a"));
+        assert_eq!(config.preflight.checks, [PreflightCheck::Battery]);
+        assert_eq!(config.preflight.min_battery_level, 3);
+
+        // version 1, the global rule of the config is kept
+        let config = migrated(
+            json!({"version": 1, "language": "ja", "retention": {"keep_last": 7}}),
+            &legacy,
+        );
+        assert_eq!(config.language, Some(Lang::Ja));
+        assert_eq!(config.retention.global.keep_last, Some(7));
+        assert_eq!(config.retention.rule("0x4000000055D00").keep_last, Some(2));
+        assert_eq!(config.retention.rule("0x4000000055E00").keep_last, Some(7));
+
+        // without legacy files
+        let config = migrated(json!({"version": 1}), &[]);
+        assert_eq!(config.retention, RetentionConfig::default());
+        assert_eq!(config.preflight, PreflightConfig::default());
+
+        // the current version does not read legacy files
+        let config = migrated(json!({"version": CONFIG_VERSION}), &legacy);
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn invalid_values_are_reset() {
+        let mut config = Config::default();
+        config.backup.name_format = "{time} a/b".to_string();
+        config.backup.compression_level = Some(10);
+        config.network.retry_times = 6;
+        config.network.connect_timeout_secs = 1;
+        config.network.buffer_kib = 1024;
+        config.paths.local_save_dir = "/a/../b".to_string();
+        config.retention.global.keep_last = Some(0);
+        config.retention.titles.insert(
+            "0x4000000055D00".to_string(),
+            RetentionRule {
+                keep_last: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(config.validate().len(), 6);
+        let mut default = Config::default();
+        default
+            .retention
+            .titles
+            .insert("0x4000000055D00".to_string(), RetentionRule::default());
+        assert_eq!(config, default);
+
+        // a level of stored is dropped without an issue
+        config.backup.compression = Compression::Stored;
+        config.backup.compression_level = Some(5);
+        assert!(config.validate().is_empty());
+        assert_eq!(config.backup.compression_level, None);
+    }
+
+    #[test]
+    fn valid_name_format() {
+        for (format, is_valid) in [
+            ("{time}", true),
+            ("{time} 3ds", true),
+            ("3ds {time}", false),
+            ("{time} {name}", false),
+            ("{time}?", false),
+            ("", false),
+        ] {
+            let mut config = Config::default();
+            config.backup.name_format = format.to_string();
+            assert_eq!(config.validate().is_empty(), is_valid, "{}", format);
+        }
+    }
+
+    #[test]
+    fn valid_dirs() {
+        for (dir, is_valid) in [
+            ("/3ds/save-cloud/saves", true),
+            ("/a", true),
+            ("/", false),
+            ("", false),
+            ("a/b", false),
+            ("/a/", false),
+            ("/a//b", false),
+            ("/a/./b", false),
+            ("/a/../b", false),
+        ] {
+            assert_eq!(is_valid_dir(dir), is_valid, "{}", dir);
+        }
+    }
+}
@@ -12,6 +12,10 @@ pub const MAX_DEEP_3D: f32 = 5.0;
 // buffer size
 pub const DOWNLOAD_BUF_SIZE: usize = 1024 * 512; // 512kib;
 pub const UPLOAD_SLICE_PER_SIZE: usize = 1024 * 1024 * 4; // 4 MiB
+pub const STORE_CHUNK_SIZE: usize = 1024 * 256; // 256 KiB
+
+// max count of dirs and files of created extdata at least
+pub const EXT_DATA_MIN_ENTRIES: u32 = 128;
 
 // invalid path chars
 pub const INVALID_CHARS: [char; 10] = ['\\', '/', ':', '*', '?', '"', '\'', '<', '>', '|'];
@@ -19,17 +23,34 @@ pub const INVALID_CHARS: [char; 10] = ['\\', '/', ':', '*', '?', '"', '\'', '<',
 // paths
 pub const HOME_LOCAL_PATH_SAVE: &str = "/save-cloud/save";
 pub const HOME_LOCAL_PATH_CACHE: &str = "/save-cloud/cache";
+pub const HOME_LOCAL_PATH_STORE: &str = "/save-cloud/store";
+pub const SNAPSHOT_EXT: &str = ".snap";
+pub const ENCRYPTED_EXT: &str = ".enc";
 pub const CACHE_ICON_NAME: &str = "icons.bin";
+pub const CACHE_SAVE_TYPES_NAME: &str = "save_types.bin";
+/// in a reserved dir of the backup zip, so it never collides with a file of the save
+pub const BACKUP_MANIFEST_NAME: &str = ".save-cloud/manifest.json";
+pub const CONFIG_PATH: &str = "/save-cloud/config.json";
+// legacy config, migrated into `CONFIG_PATH`
+pub const RETENTION_CONFIG_PATH: &str = "/save-cloud/retention.json";
+pub const PREFLIGHT_CONFIG_PATH: &str = "/save-cloud/preflight.json";
+pub const LANGUAGE_CONFIG_PATH: &str = "/save-cloud/language.json";
+pub const EXTDATA_REDIRECT_PATH: &str = "/save-cloud/extdata.json";
 pub const AUTH_BAIDU_CONFIG_PATH: &str = "/save-cloud/auth";
 pub const GAME_SAVE_CLOUD_DIR_PREFIX: &str = "/apps/Backup/";
 pub const GAME_SAVE_CLOUD_DIR_ROOT: &str = "/apps/Backup/3ds/save-cloud";
 pub const GAME_SAVE_CLOUD_DIR: &str = "/apps/Backup/3ds/save-cloud/saves";
 pub const UPLOAD_CACHE_DIR: &str = "/apps/Backup/upload_cache_can_delete";
 
+// backups of other save managers
+pub const CHECKPOINT_SAVES_PATH: &str = "/3ds/Checkpoint/saves";
+pub const CHECKPOINT_EXTDATA_PATH: &str = "/3ds/Checkpoint/extdata";
+pub const JKSM_SAVES_PATH: &str = "/JKSV/Saves";
+pub const JKSM_EXTDATA_PATH: &str = "/JKSV/ExtData";
+pub const EMULATOR_PATH: &str = "/save-cloud/emulator";
+
 // home page
 pub const HOME_PAGE_URL: &str = "https://save-cloud.sketchraw.com?3ds=go";
-pub const INVALID_EAT_PANCAKE: &str = "缺少 eat.pancake";
-pub const ABOUT_TEXT: &str = "Save Cloud 云存档，扫码访问主页！";
 pub const CURL_CERT_CURL: &str = "https://curl.se/ca/cacert.pem";
 pub const CURL_CERT_PATH: &str = "/config/ssl/cacert.pem";
 pub const FBI_SC_TITLE_ID: u64 = 0x400000F899900;
@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use chacha20poly1305::{
+    aead::{
+        generic_array::GenericArray,
+        stream::{DecryptorBE32, EncryptorBE32},
+    },
+    ChaCha20Poly1305, Key, KeyInit,
+};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+
+use crate::t;
+
+/// magic of the encrypted backup container
+pub const MAGIC: &[u8; 8] = b"SCENC\x00\x00\x01";
+const SALT_LEN: usize = 16;
+/// nonce of chacha20poly1305 is 12 bytes, 5 of them are used by the stream
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+/// plain text size of each encrypted chunk
+const CHUNK_SIZE: usize = 1024 * 64;
+/// the header is not authenticated before the key is derived, so other counts are rejected
+const KDF_ITERATIONS: u32 = 100_000;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_PREFIX_LEN;
+
+/// passphrase cached for the session
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn session_passphrase() -> Option<String> {
+    PASSPHRASE.lock().ok().and_then(|p| p.clone())
+}
+
+pub fn set_session_passphrase(passphrase: Option<String>) {
+    if let Ok(mut p) = PASSPHRASE.lock() {
+        *p = passphrase;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Key {
+    let mut key = Key::default();
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// whether the reader starts with the magic of the container, the reader is consumed
+pub fn is_encrypted(reader: &mut impl Read) -> io::Result<bool> {
+    let mut magic = [0; MAGIC.len()];
+    match reader.read_exact(&mut magic) {
+        Ok(_) => Ok(&magic == MAGIC),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn is_encrypted_file(path: impl AsRef<Path>) -> bool {
+    fs::File::open(path)
+        .and_then(|mut file| is_encrypted(&mut file))
+        .unwrap_or(false)
+}
+
+/// # encrypt with a passphrase while writing
+///
+/// header: magic, kdf iterations (u32 le), salt, nonce prefix. the header is the
+/// associated data of every chunk, `finish` must be called to write the last chunk
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    stream: Option<EncryptorBE32<ChaCha20Poly1305>>,
+    header: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    pub fn new(mut inner: W, passphrase: &str) -> io::Result<Self> {
+        let mut salt = [0; SALT_LEN];
+        let mut nonce = [0; NONCE_PREFIX_LEN];
+        for buf in [&mut salt[..], &mut nonce[..]] {
+            getrandom::getrandom(buf).map_err(|e| io::Error::other(e.to_string()))?;
+        }
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&KDF_ITERATIONS.to_le_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+        inner.write_all(&header)?;
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt, KDF_ITERATIONS));
+        Ok(Self {
+            inner,
+            stream: Some(EncryptorBE32::from_aead(
+                cipher,
+                GenericArray::from_slice(&nonce),
+            )),
+            header,
+            buf: Vec::with_capacity(CHUNK_SIZE + TAG_LEN),
+        })
+    }
+
+    /// write the last chunk, returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let stream = self
+            .stream
+            .take()
+            .ok_or_else(|| invalid_data(t!("crypto.finished")))?;
+        stream
+            .encrypt_last_in_place(&self.header, &mut self.buf)
+            .map_err(|_| invalid_data(t!("crypto.encrypt_failed")))?;
+        self.inner.write_all(&self.buf)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| invalid_data(t!("crypto.finished")))?;
+        // a full chunk is kept until more data comes, it may be the last one
+        if self.buf.len() == CHUNK_SIZE && !buf.is_empty() {
+            stream
+                .encrypt_next_in_place(&self.header, &mut self.buf)
+                .map_err(|_| invalid_data(t!("crypto.encrypt_failed")))?;
+            self.inner.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        let size = buf.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..size]);
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// # decrypt while reading
+///
+/// the first chunk is decrypted by `new`, so a wrong passphrase fails early
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    stream: Option<DecryptorBE32<ChaCha20Poly1305>>,
+    header: Vec<u8>,
+    /// cipher text read ahead
+    pending: Vec<u8>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> DecryptReader<R> {
+    pub fn new(mut inner: R, passphrase: &str) -> io::Result<Self> {
+        let mut header = vec![0; HEADER_LEN];
+        inner
+            .read_exact(&mut header)
+            .map_err(|_| invalid_data(t!("crypto.not_encrypted")))?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data(t!("crypto.not_encrypted")));
+        }
+        let mut iterations = [0; 4];
+        iterations.copy_from_slice(&header[MAGIC.len()..MAGIC.len() + 4]);
+        if u32::from_le_bytes(iterations) != KDF_ITERATIONS {
+            return Err(invalid_data(t!("crypto.unsupported_kdf")));
+        }
+        let salt = &header[MAGIC.len() + 4..MAGIC.len() + 4 + SALT_LEN];
+        let nonce = &header[HEADER_LEN - NONCE_PREFIX_LEN..];
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt, KDF_ITERATIONS));
+        let stream = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+        let mut reader = Self {
+            inner,
+            stream: Some(stream),
+            header,
+            pending: Vec::with_capacity(CHUNK_SIZE + TAG_LEN + 1),
+            buf: vec![],
+            pos: 0,
+        };
+        reader.next_chunk()?;
+        Ok(reader)
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+        // one more byte to know if this is the last chunk
+        let size = CHUNK_SIZE + TAG_LEN + 1;
+        while self.pending.len() < size {
+            let len = self.pending.len();
+            self.pending.resize(size, 0);
+            let n = self.inner.read(&mut self.pending[len..])?;
+            self.pending.truncate(len + n);
+            if n == 0 {
+                break;
+            }
+        }
+
+        self.pos = 0;
+        if self.pending.len() < size {
+            self.buf = std::mem::take(&mut self.pending);
+            if let Some(stream) = self.stream.take() {
+                stream
+                    .decrypt_last_in_place(&self.header, &mut self.buf)
+                    .map_err(|_| invalid_data(t!("crypto.wrong_passphrase")))?;
+            }
+        } else {
+            self.buf = self.pending.drain(..CHUNK_SIZE + TAG_LEN).collect();
+            stream
+                .decrypt_next_in_place(&self.header, &mut self.buf)
+                .map_err(|_| invalid_data(t!("crypto.wrong_passphrase")))?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.buf.len() {
+            if self.stream.is_none() {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let size = buf.len().min(self.buf.len() - self.pos);
+        buf[..size].copy_from_slice(&self.buf[self.pos..self.pos + size]);
+        self.pos += size;
+        Ok(size)
+    }
+}
+
+/// check the passphrase with the first chunk of the encrypted file
+pub fn check_passphrase(path: impl AsRef<Path>, passphrase: &str) -> bool {
+    fs::File::open(path)
+        .and_then(|file| DecryptReader::new(file, passphrase))
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn encrypt(data: &[u8], passphrase: &str) -> Vec<u8> {
+        let mut writer = EncryptWriter::new(vec![], passphrase).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn decrypt(data: &[u8], passphrase: &str) -> io::Result<Vec<u8>> {
+        let mut output = vec![];
+        DecryptReader::new(Cursor::new(data), passphrase)?.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = (0..CHUNK_SIZE * 2 + 1)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        let encrypted = encrypt(&data, "secret");
+        assert!(is_encrypted(&mut Cursor::new(&encrypted)).unwrap());
+        // a chunk of exactly the chunk size is the last one
+        for len in [0, 1, CHUNK_SIZE, data.len()] {
+            let encrypted = encrypt(&data[..len], "secret");
+            assert_eq!(decrypt(&encrypted, "secret").unwrap(), &data[..len]);
+        }
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let encrypted = encrypt(b"save data", "secret");
+        let err = decrypt(&encrypted, "Secret").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tampered_data() {
+        let mut encrypted = encrypt(b"save data", "secret");
+        *encrypted.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&encrypted, "secret").is_err());
+
+        let data = vec![0; CHUNK_SIZE + 1];
+        let encrypted = encrypt(&data, "secret");
+        // the last chunk is cut off
+        let truncated = &encrypted[..HEADER_LEN + CHUNK_SIZE + TAG_LEN];
+        assert!(decrypt(truncated, "secret").is_err());
+    }
+
+    #[test]
+    fn iterations_in_header_are_checked() {
+        let mut encrypted = encrypt(b"save data", "secret");
+        encrypted[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decrypt(&encrypted, "secret").is_err());
+    }
+
+    #[test]
+    fn plain_data_is_not_encrypted() {
+        assert!(!is_encrypted(&mut Cursor::new(b"PK\x03\x04")).unwrap());
+        assert!(!is_encrypted(&mut Cursor::new(b"")).unwrap());
+        assert!(decrypt(b"PK\x03\x04 not encrypted at all", "secret").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{self, Read, Seek},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    constant::BACKUP_MANIFEST_NAME,
+    manifest::{BackupManifest, HashWriter, ManifestFile},
+    t,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    /// file in the source compared from, `None` if added
+    pub from: Option<ManifestFile>,
+    /// file in the source compared to, `None` if removed
+    pub to: Option<ManifestFile>,
+}
+
+/// # changes from one save source to another
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveDiff {
+    /// sorted by path
+    pub entries: Vec<DiffEntry>,
+    pub unchanged: usize,
+}
+
+impl SaveDiff {
+    pub fn count(&self, kind: DiffKind) -> usize {
+        self.entries.iter().filter(|e| e.kind == kind).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return t!("diff.no_change").to_string();
+        }
+        t!(
+            "diff.summary",
+            added = self.count(DiffKind::Added),
+            removed = self.count(DiffKind::Removed),
+            modified = self.count(DiffKind::Modified)
+        )
+    }
+}
+
+/// # files of a backup zip
+///
+/// taken from the manifest if the backup has one, otherwise every entry is
+/// read and hashed
+pub fn zip_files<R: Read + Seek>(reader: R) -> Result<Vec<ManifestFile>, Box<dyn Error>> {
+    let mut zip = ZipArchive::new(reader).map_err(|e| t!("common.backup_read_failed", err = e))?;
+    if let Some(manifest) = BackupManifest::read_from(&mut zip) {
+        return Ok(manifest.files);
+    }
+    let mut files = vec![];
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        if file.is_dir() || name == BACKUP_MANIFEST_NAME {
+            continue;
+        }
+        let mut output = HashWriter::new(io::sink());
+        io::copy(&mut file, &mut output).map_err(|e| format!("{}: {}", name, e))?;
+        let (size, md5) = output.finish();
+        files.push(ManifestFile {
+            path: name,
+            size,
+            md5,
+        });
+    }
+    Ok(files)
+}
+
+/// # files of a backup zip that is read as a stream, e.g. while decrypting
+///
+/// every entry is hashed, the manifest is used instead if the backup has one
+pub fn zip_stream_files<R: Read>(mut reader: R) -> Result<Vec<ManifestFile>, Box<dyn Error>> {
+    let mut files = vec![];
+    let mut manifest = None;
+    while let Some(mut file) = zip::read::read_zipfile_from_stream(&mut reader)? {
+        let name = file.name().to_string();
+        if file.is_dir() {
+            continue;
+        }
+        if name == BACKUP_MANIFEST_NAME {
+            manifest = serde_json::from_reader::<_, BackupManifest>(&mut file).ok();
+            continue;
+        }
+        let mut output = HashWriter::new(io::sink());
+        io::copy(&mut file, &mut output).map_err(|e| format!("{}: {}", name, e))?;
+        let (size, md5) = output.finish();
+        files.push(ManifestFile {
+            path: name,
+            size,
+            md5,
+        });
+    }
+    Ok(manifest.map(|m| m.files).unwrap_or(files))
+}
+
+/// diff files of two save sources, e.g. the current save and a backup
+pub fn diff_files(from: &[ManifestFile], to: &[ManifestFile]) -> SaveDiff {
+    let from = from
+        .iter()
+        .map(|f| (f.path.trim_start_matches('/'), f))
+        .collect::<BTreeMap<_, _>>();
+    let to = to
+        .iter()
+        .map(|f| (f.path.trim_start_matches('/'), f))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut diff = SaveDiff::default();
+    for (&path, &file) in from.iter() {
+        match to.get(path) {
+            Some(&to_file) => {
+                if file.size == to_file.size && file.md5 == to_file.md5 {
+                    diff.unchanged += 1;
+                } else {
+                    diff.entries.push(DiffEntry {
+                        path: path.to_string(),
+                        kind: DiffKind::Modified,
+                        from: Some(file.clone()),
+                        to: Some(to_file.clone()),
+                    });
+                }
+            }
+            None => diff.entries.push(DiffEntry {
+                path: path.to_string(),
+                kind: DiffKind::Removed,
+                from: Some(file.clone()),
+                to: None,
+            }),
+        }
+    }
+    for (&path, &file) in to.iter() {
+        if !from.contains_key(path) {
+            diff.entries.push(DiffEntry {
+                path: path.to_string(),
+                kind: DiffKind::Added,
+                from: None,
+                to: Some(file.clone()),
+            });
+        }
+    }
+    diff.entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    diff
+}
+
+/// diff two backup zips
+pub fn diff_zip<A: Read + Seek, B: Read + Seek>(
+    from: A,
+    to: B,
+) -> Result<SaveDiff, Box<dyn Error>> {
+    Ok(diff_files(&zip_files(from)?, &zip_files(to)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn file(path: &str, size: u64, md5: &str) -> ManifestFile {
+        ManifestFile {
+            path: path.to_string(),
+            size,
+            md5: md5.to_string(),
+        }
+    }
+
+    fn zip(files: &[(&str, &str)], manifest: Option<BackupManifest>) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for (path, data) in files {
+            zip.start_file(*path, FileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        if let Some(manifest) = manifest {
+            manifest.write_to(&mut zip).unwrap();
+        }
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    fn kinds(diff: &SaveDiff) -> Vec<(&str, DiffKind)> {
+        diff.entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn diff_of_files() {
+        let from = [
+            file("/same", 1, "aa"),
+            file("/size", 1, "bb"),
+            file("/md5", 2, "cc"),
+            file("/removed", 3, "dd"),
+        ];
+        let to = [
+            file("same", 1, "aa"),
+            file("added", 4, "ee"),
+            file("size", 2, "bb"),
+            file("md5", 2, "ff"),
+        ];
+        let diff = diff_files(&from, &to);
+        assert_eq!(
+            kinds(&diff),
+            [
+                ("added", DiffKind::Added),
+                ("md5", DiffKind::Modified),
+                ("removed", DiffKind::Removed),
+                ("size", DiffKind::Modified),
+            ]
+        );
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.count(DiffKind::Modified), 2);
+        assert_eq!(diff.entries[0].from, None);
+        assert_eq!(diff.entries[0].to, Some(file("added", 4, "ee")));
+        assert_eq!(diff.entries[2].to, None);
+    }
+
+    #[test]
+    fn same_files_have_no_diff() {
+        let files = [file("a", 1, "aa"), file("b/c", 2, "bb")];
+        let diff = diff_files(&files, &files);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 2);
+        assert!(diff_files(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn diff_of_zips() {
+        let from = zip(&[("a", "1"), ("b", "2"), ("dir/c", "3")], None);
+        let to = zip(&[("a", "1"), ("b", "22"), ("d", "4")], None);
+        let diff = diff_zip(from, to).unwrap();
+        assert_eq!(
+            kinds(&diff),
+            [
+                ("b", DiffKind::Modified),
+                ("d", DiffKind::Added),
+                ("dir/c", DiffKind::Removed),
+            ]
+        );
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn files_are_hashed_without_manifest() {
+        let files = zip_files(zip(&[("a", "hello")], None)).unwrap();
+        assert_eq!(files, [file("a", 5, "5d41402abc4b2a76b9719d911017c592")]);
+        let stream = zip_stream_files(zip(&[("a", "hello")], None)).unwrap();
+        assert_eq!(stream, files);
+    }
+
+    #[test]
+    fn manifest_is_used_if_present() {
+        let mut manifest = BackupManifest::new("0".to_string(), "".to_string(), "sd", "user");
+        manifest.files = vec![file("a", 5, "listed")];
+        let files = zip_files(zip(&[("a", "hello")], Some(manifest.clone()))).unwrap();
+        assert_eq!(files, manifest.files);
+        let stream = zip_stream_files(zip(&[("a", "hello")], Some(manifest.clone()))).unwrap();
+        assert_eq!(stream, manifest.files);
+    }
+
+    #[test]
+    fn invalid_zip_is_an_error() {
+        assert!(zip_files(Cursor::new(b"not a zip".to_vec())).is_err());
+    }
+}
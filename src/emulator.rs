@@ -0,0 +1,508 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{
+    checkpoint::{ExternalBackup, ExternalTool},
+    config,
+    constant::{BACKUP_MANIFEST_NAME, EMULATOR_PATH},
+    manifest::{BackupManifest, HashWriter, ManifestFile},
+    t,
+};
+
+/// id0 and id1 of the sdmc dir, emulators use zeros
+const EMULATOR_ID: &str = "00000000000000000000000000000000";
+const SDMC_DIR: &str = "sdmc";
+const NINTENDO_3DS_DIR: &str = "Nintendo 3DS";
+
+/// # save dir of the title in the sdmc layout of emulators
+///
+/// - user: `Nintendo 3DS/<id0>/<id1>/title/<high id>/<low id>/data/00000001`
+/// - ext: `Nintendo 3DS/<id0>/<id1>/extdata/00000000/<extdata id>/user`
+///
+/// ids are 8 lowercase hex digits, `extdata_id` is the one resolved by
+/// `extdata::extdata_id`, titles may share the extdata of another title
+fn save_dir(save_type: &str, high_id: u32, low_id: u32, extdata_id: u32) -> Option<Vec<String>> {
+    let prefix = [NINTENDO_3DS_DIR, EMULATOR_ID, EMULATOR_ID].map(|s| s.to_string());
+    let rest = match save_type {
+        "user" => vec![
+            "title".to_string(),
+            format!("{:08x}", high_id),
+            format!("{:08x}", low_id),
+            "data".to_string(),
+            "00000001".to_string(),
+        ],
+        "ext" => vec![
+            "extdata".to_string(),
+            "00000000".to_string(),
+            format!("{:08x}", extdata_id),
+            "user".to_string(),
+        ],
+        _ => return None,
+    };
+    Some(prefix.into_iter().chain(rest).collect())
+}
+
+/// map path of a backup entry to the path in an emulator export, `None` if the
+/// save type is not supported by emulators
+pub fn to_emulator_path(
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+    path: &str,
+) -> Option<String> {
+    let dir = save_dir(save_type, high_id, low_id, extdata_id)?;
+    Some(format!(
+        "{}/{}/{}",
+        SDMC_DIR,
+        dir.join("/"),
+        path.trim_start_matches('/')
+    ))
+}
+
+/// # map path in an emulator layout back to the path of a backup entry
+///
+/// the layout may start at any dir, e.g. `sdmc/` or `Nintendo 3DS/`, id0 and
+/// id1 are not checked. `None` if the path is not a save file of the title
+pub fn from_emulator_path(
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+    path: &str,
+) -> Option<String> {
+    let dir = save_dir(save_type, high_id, low_id, extdata_id)?;
+    let parts = path
+        .split('/')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    let start = parts.iter().position(|&p| p == NINTENDO_3DS_DIR)?;
+    let parts = &parts[start..];
+    if parts.len() <= dir.len() {
+        return None;
+    }
+    let is_match = dir
+        .iter()
+        .zip(parts.iter())
+        .enumerate()
+        .all(|(idx, (d, p))| idx == 1 || idx == 2 || d.eq_ignore_ascii_case(p));
+    is_match.then(|| parts[dir.len()..].join("/"))
+}
+
+/// # export backup zip as a zip in the emulator layout
+///
+/// returns count of files
+pub fn export_emulator_zip<R: Read + Seek, W: Write + Seek>(
+    backup: R,
+    writer: W,
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+) -> Result<usize, Box<dyn Error>> {
+    let mut zip = ZipArchive::new(backup).map_err(|e| t!("common.backup_read_failed", err = e))?;
+    let mut output = ZipWriter::new(writer);
+    let options = config::get().backup.file_options();
+    let mut count = 0;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() || file.name() == BACKUP_MANIFEST_NAME {
+            continue;
+        }
+        let path = to_emulator_path(save_type, high_id, low_id, extdata_id, file.name())
+            .ok_or(t!("emulator.unsupported_save"))?;
+        output.start_file(path, options)?;
+        io::copy(&mut file, &mut output)?;
+        count += 1;
+    }
+    output.finish()?;
+    Ok(count)
+}
+
+/// # import zip in the emulator layout as a backup zip
+///
+/// files of other titles are skipped, returns count of files
+pub fn import_emulator_zip<R: Read + Seek, W: Write + Seek>(
+    reader: R,
+    writer: W,
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+    mut manifest: BackupManifest,
+) -> Result<usize, Box<dyn Error>> {
+    let mut zip = ZipArchive::new(reader).map_err(|e| t!("emulator.save_read_failed", err = e))?;
+    let mut output = ZipWriter::new(writer);
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        if let Some(path) = from_emulator_path(save_type, high_id, low_id, extdata_id, file.name())
+        {
+            manifest
+                .files
+                .push(write_entry(&mut output, &path, &mut file)?);
+        }
+    }
+    finish_import(output, manifest)
+}
+
+/// # import folder in the emulator layout as a backup zip
+///
+/// same as `import_emulator_zip`, `dir` is the folder that contains the layout
+pub fn import_emulator_dir<W: Write + Seek>(
+    dir: &str,
+    writer: W,
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+    mut manifest: BackupManifest,
+) -> Result<usize, Box<dyn Error>> {
+    let mut files = vec![];
+    list_files(Path::new(dir), "", &mut files)?;
+    let mut output = ZipWriter::new(writer);
+    for name in files {
+        if let Some(path) = from_emulator_path(save_type, high_id, low_id, extdata_id, &name) {
+            let mut file = fs::File::open(Path::new(dir).join(&name))?;
+            manifest
+                .files
+                .push(write_entry(&mut output, &path, &mut file)?);
+        }
+    }
+    finish_import(output, manifest)
+}
+
+fn list_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &format!("{}/", name), files)?;
+        } else {
+            files.push(name);
+        }
+    }
+    Ok(())
+}
+
+fn write_entry<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    path: &str,
+    reader: &mut impl Read,
+) -> Result<ManifestFile, Box<dyn Error>> {
+    let options = config::get().backup.file_options();
+    zip.start_file(path, options)?;
+    let mut output = HashWriter::new(&mut *zip);
+    io::copy(reader, &mut output)?;
+    let (size, md5) = output.finish();
+    Ok(ManifestFile {
+        path: path.to_string(),
+        size,
+        md5,
+    })
+}
+
+fn finish_import<W: Write + Seek>(
+    mut zip: ZipWriter<W>,
+    manifest: BackupManifest,
+) -> Result<usize, Box<dyn Error>> {
+    if manifest.files.is_empty() {
+        return Err(t!("emulator.save_not_found").into());
+    }
+    manifest.write_to(&mut zip)?;
+    zip.finish()?;
+    Ok(manifest.files.len())
+}
+
+/// whether the zip or folder in the emulator layout has save files of the title
+fn has_title_save(
+    path: &Path,
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+) -> bool {
+    let is_save =
+        |name: &str| from_emulator_path(save_type, high_id, low_id, extdata_id, name).is_some();
+    if path.is_dir() {
+        let mut files = vec![];
+        list_files(path, "", &mut files).is_ok() && files.iter().any(|name| is_save(name))
+    } else {
+        fs::File::open(path)
+            .ok()
+            .and_then(|file| ZipArchive::new(file).ok())
+            .is_some_and(|zip| zip.file_names().any(is_save))
+    }
+}
+
+/// # exports of emulators in `EMULATOR_PATH` that have saves of the title
+///
+/// zips and folders, newest first by name
+pub fn list_emulator_backups(
+    save_type: &str,
+    high_id: u32,
+    low_id: u32,
+    extdata_id: u32,
+) -> Vec<ExternalBackup> {
+    let mut list = fs::read_dir(EMULATOR_PATH)
+        .map(|dirs| {
+            dirs.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir() || path.extension().is_some_and(|ext| ext == "zip"))
+                .filter(|path| has_title_save(path, save_type, high_id, low_id, extdata_id))
+                .filter_map(|path| {
+                    Some(ExternalBackup {
+                        tool: ExternalTool::Emulator,
+                        name: path.file_name()?.to_str()?.to_string(),
+                        path: path.display().to_string(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    list.sort_by(|a, b| b.name.cmp(&a.name));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use zip::write::FileOptions;
+
+    use super::*;
+
+    const HIGH_ID: u32 = 0x00040000;
+    const LOW_ID: u32 = 0x00055d00;
+    const EXT_ID: u32 = 0x0000055d;
+    const USER_DIR: &str = "sdmc/Nintendo 3DS/00000000000000000000000000000000/00000000000000000000000000000000/title/00040000/00055d00/data/00000001";
+    const EXT_DIR: &str = "sdmc/Nintendo 3DS/00000000000000000000000000000000/00000000000000000000000000000000/extdata/00000000/0000055d/user";
+
+    fn manifest() -> BackupManifest {
+        BackupManifest::new("0004000000055D00".to_string(), "".to_string(), "sd", "user")
+    }
+
+    fn zip(files: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for (path, data) in files {
+            zip.start_file(*path, FileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    fn entries(data: Vec<u8>) -> Vec<(String, String)> {
+        let mut zip = ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut entries = (0..zip.len())
+            .map(|i| {
+                let mut file = zip.by_index(i).unwrap();
+                let mut data = String::new();
+                file.read_to_string(&mut data).unwrap();
+                (file.name().to_string(), data)
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn path_of_save_types() {
+        assert_eq!(
+            to_emulator_path("user", HIGH_ID, LOW_ID, EXT_ID, "/main"),
+            Some(format!("{}/main", USER_DIR))
+        );
+        assert_eq!(
+            to_emulator_path("ext", HIGH_ID, LOW_ID, EXT_ID, "dir/file"),
+            Some(format!("{}/dir/file", EXT_DIR))
+        );
+        assert_eq!(
+            to_emulator_path("sys", HIGH_ID, LOW_ID, EXT_ID, "main"),
+            None
+        );
+    }
+
+    #[test]
+    fn path_back_to_backup() {
+        for (save_type, dir) in [("user", USER_DIR), ("ext", EXT_DIR)] {
+            let path = format!("{}/dir/file", dir);
+            assert_eq!(
+                from_emulator_path(save_type, HIGH_ID, LOW_ID, EXT_ID, &path).as_deref(),
+                Some("dir/file")
+            );
+        }
+        // any id0 and id1, any root, case of hex digits
+        assert_eq!(
+            from_emulator_path(
+                "user",
+                HIGH_ID,
+                LOW_ID,
+                EXT_ID,
+                "backup/Nintendo 3DS/aaaa/bbbb/title/00040000/00055D00/data/00000001/main"
+            )
+            .as_deref(),
+            Some("main")
+        );
+    }
+
+    #[test]
+    fn path_of_redirected_extdata() {
+        // Pokemon Y uses the extdata of Pokemon X
+        let low_id = 0x00055e00;
+        let ext_id = crate::extdata::extdata_id(low_id);
+        assert_eq!(ext_id, EXT_ID);
+        let path = to_emulator_path("ext", HIGH_ID, low_id, ext_id, "main").unwrap();
+        assert_eq!(path, format!("{}/main", EXT_DIR));
+        assert_eq!(
+            from_emulator_path("ext", HIGH_ID, low_id, ext_id, &path).as_deref(),
+            Some("main")
+        );
+        // not the folder of `low id >> 8`
+        let other = EXT_DIR.replace("0000055d", "0000055e");
+        assert_eq!(
+            from_emulator_path("ext", HIGH_ID, low_id, ext_id, &format!("{}/main", other)),
+            None
+        );
+    }
+
+    #[test]
+    fn path_of_other_saves() {
+        let path = format!("{}/main", USER_DIR);
+        assert_eq!(
+            from_emulator_path("user", HIGH_ID, LOW_ID + 0x100, EXT_ID, &path),
+            None
+        );
+        assert_eq!(
+            from_emulator_path("ext", HIGH_ID, LOW_ID, EXT_ID, &path),
+            None
+        );
+        assert_eq!(
+            from_emulator_path("sys", HIGH_ID, LOW_ID, EXT_ID, &path),
+            None
+        );
+        // the save dir itself is not a file
+        assert_eq!(
+            from_emulator_path("user", HIGH_ID, LOW_ID, EXT_ID, USER_DIR),
+            None
+        );
+        assert_eq!(
+            from_emulator_path("user", HIGH_ID, LOW_ID, EXT_ID, "title/main"),
+            None
+        );
+    }
+
+    #[test]
+    fn export_and_import_zip() {
+        let backup = zip(&[
+            ("main", "1"),
+            ("dir/file", "2"),
+            (BACKUP_MANIFEST_NAME, "{}"),
+        ]);
+        let mut exported = Cursor::new(vec![]);
+        let count =
+            export_emulator_zip(backup, &mut exported, "user", HIGH_ID, LOW_ID, EXT_ID).unwrap();
+        assert_eq!(count, 2);
+        let exported = exported.into_inner();
+        assert_eq!(
+            entries(exported.clone()),
+            [
+                (format!("{}/dir/file", USER_DIR), "2".to_string()),
+                (format!("{}/main", USER_DIR), "1".to_string()),
+            ]
+        );
+
+        let mut imported = Cursor::new(vec![]);
+        let count = import_emulator_zip(
+            Cursor::new(exported),
+            &mut imported,
+            "user",
+            HIGH_ID,
+            LOW_ID,
+            EXT_ID,
+            manifest(),
+        )
+        .unwrap();
+        assert_eq!(count, 2);
+        let mut zip = ZipArchive::new(Cursor::new(imported.into_inner())).unwrap();
+        let manifest = BackupManifest::read_from(&mut zip).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.get_file("main").map(|f| f.size), Some(1));
+        let mut main = String::new();
+        zip.by_name("main")
+            .unwrap()
+            .read_to_string(&mut main)
+            .unwrap();
+        assert_eq!(main, "1");
+    }
+
+    #[test]
+    fn import_skips_other_titles() {
+        let other = format!("{}/main", USER_DIR).replace("00055d00", "00055e00");
+        let input = zip(&[(&other, "other"), (&format!("{}/main", EXT_DIR), "ext")]);
+        let err = import_emulator_zip(
+            input,
+            Cursor::new(vec![]),
+            "user",
+            HIGH_ID,
+            LOW_ID,
+            EXT_ID,
+            manifest(),
+        );
+        assert!(err.is_err());
+
+        let input = zip(&[(&other, "other"), (&format!("{}/main", USER_DIR), "user")]);
+        let mut imported = Cursor::new(vec![]);
+        let count = import_emulator_zip(
+            input,
+            &mut imported,
+            "user",
+            HIGH_ID,
+            LOW_ID,
+            EXT_ID,
+            manifest(),
+        )
+        .unwrap();
+        assert_eq!(count, 1);
+        let names = entries(imported.into_inner())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, [BACKUP_MANIFEST_NAME, "main"]);
+    }
+
+    #[test]
+    fn import_dir() {
+        let dir = std::env::temp_dir().join(format!("save-cloud-emulator-{}", std::process::id()));
+        let save_dir = dir.join(EXT_DIR).join("sub");
+        fs::create_dir_all(&save_dir).unwrap();
+        fs::write(save_dir.join("file"), "ext").unwrap();
+        fs::write(dir.join("readme.txt"), "not a save").unwrap();
+
+        let mut imported = Cursor::new(vec![]);
+        let res = import_emulator_dir(
+            &dir.to_string_lossy(),
+            &mut imported,
+            "ext",
+            HIGH_ID,
+            LOW_ID,
+            EXT_ID,
+            manifest(),
+        );
+        let has_save = has_title_save(&dir, "ext", HIGH_ID, LOW_ID, EXT_ID);
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(res.unwrap(), 1);
+        assert!(has_save);
+        assert_eq!(
+            entries(imported.into_inner())[1],
+            ("sub/file".to_string(), "ext".to_string())
+        );
+    }
+}
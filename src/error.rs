@@ -0,0 +1,422 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "3ds")]
+use ctru::{error::ResultCode, services::fs::ArchiveID};
+use zip::result::ZipError;
+
+#[cfg(not(feature = "3ds"))]
+use crate::platform::{ArchiveID, ResultCode};
+use crate::{cancel, config, http::http_last_status, restore::RestoreError, t, utils::format_size};
+
+/// curl code of a transfer aborted by the progress callback
+const CURLE_ABORTED_BY_CALLBACK: i64 = 42;
+/// summary of result codes of things that do not exist, e.g. extdata not created yet
+const RS_NOT_FOUND: u32 = 4;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// # errors of the app
+///
+/// messages of `Validation` and `Other` are shown as is
+#[derive(Debug)]
+pub enum AppError {
+    /// file system error, the code is kept if the error comes from fs service
+    Fs {
+        code: Option<ResultCode>,
+        path: Option<PathBuf>,
+        message: String,
+    },
+    ArchiveOpen {
+        arch: ArchiveID,
+        code: ResultCode,
+    },
+    Zip(ZipError),
+    /// http status, or the code of curl if less than 100, negative if the request was not sent
+    Network {
+        status: i64,
+    },
+    AuthExpired,
+    Quota {
+        required: u64,
+        available: u64,
+    },
+    Cancelled,
+    Validation(String),
+    Other(String),
+}
+
+impl AppError {
+    /// attach the path to a file system error
+    pub fn fs(err: io::Error, path: impl AsRef<Path>) -> Self {
+        match Self::from(err) {
+            AppError::Fs { code, message, .. } => AppError::Fs {
+                code,
+                path: Some(path.as_ref().to_path_buf()),
+                message,
+            },
+            err => err,
+        }
+    }
+
+    #[cfg(feature = "3ds")]
+    pub fn archive_open(arch: ArchiveID, err: ctru::Error) -> Self {
+        match err {
+            ctru::Error::Os(code) => AppError::ArchiveOpen {
+                arch,
+                code: ResultCode(code),
+            },
+            err => err.into(),
+        }
+    }
+
+    /// # error of `Api`, classified by the status of the last http request
+    ///
+    /// the api returns plain errors, the status of curl is the only clue
+    pub fn from_api(err: Box<dyn Error>) -> Self {
+        let err = match err.downcast::<AppError>() {
+            Ok(err) => return *err,
+            Err(err) => err,
+        };
+        if cancel::is_cancelled() {
+            return AppError::Cancelled;
+        }
+        Self::from_status(err.to_string(), http_last_status())
+    }
+
+    /// error of a request that ended with the http status or code of curl
+    fn from_status(message: String, status: i64) -> Self {
+        match status {
+            CURLE_ABORTED_BY_CALLBACK => AppError::Cancelled,
+            401 | 403 => AppError::AuthExpired,
+            200 => AppError::Other(message),
+            status => AppError::Network { status },
+        }
+    }
+
+    /// a network error that may succeed if tried again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::Network { status } => *status < 100 || *status >= 500 || *status == 429,
+            _ => false,
+        }
+    }
+
+    pub fn needs_reauth(&self) -> bool {
+        matches!(self, AppError::AuthExpired)
+    }
+
+    /// code of the fs service
+    pub fn result_code(&self) -> Option<ResultCode> {
+        match self {
+            AppError::Fs { code, .. } => *code,
+            AppError::ArchiveOpen { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// the fs service reports the archive or file does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.result_code()
+            .is_some_and(|code| ((code.0 as u32) >> 21) & 0x3F == RS_NOT_FOUND)
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Fs {
+                code,
+                path,
+                message,
+            } => {
+                write!(f, "{}", t!("error.fs", message = message))?;
+                if let Some(path) = path {
+                    write!(f, " ({})", path.display())?;
+                }
+                if let Some(code) = code {
+                    write!(f, " [{:#010X}]", code.0)?;
+                }
+                Ok(())
+            }
+            AppError::ArchiveOpen { arch, code } => {
+                let name = match arch {
+                    ArchiveID::Sdmc => t!("error.archive.sdmc"),
+                    ArchiveID::Extdata | ArchiveID::SharedExtdata => "Extdata",
+                    ArchiveID::SystemSavedata => t!("error.archive.system_savedata"),
+                    ArchiveID::BossExtdata => "BOSS Extdata",
+                    _ => t!("error.archive.save"),
+                };
+                write!(
+                    f,
+                    "{} [{:#010X}]",
+                    t!("error.archive_open", name = name),
+                    code.0
+                )
+            }
+            AppError::Zip(err) => write!(f, "{}", t!("error.zip", err = err)),
+            AppError::Network { status } if *status < 0 => {
+                write!(f, "{}", t!("error.network_request"))
+            }
+            AppError::Network { status } if *status < 100 => {
+                write!(f, "{}", t!("error.network_connect", status = status))
+            }
+            AppError::Network { status } => {
+                write!(f, "{}", t!("error.network_server", status = status))
+            }
+            AppError::AuthExpired => write!(f, "{}", t!("error.auth_expired")),
+            AppError::Quota {
+                required,
+                available,
+            } => write!(
+                f,
+                "{}",
+                t!(
+                    "common.save_space_full",
+                    required = format_size(*required),
+                    capacity = format_size(*available)
+                )
+            ),
+            AppError::Cancelled => write!(f, "{}", t!("error.cancelled")),
+            AppError::Validation(message) | AppError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Zip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        // e.g. `AppError::Cancelled` of `cancel::check`
+        if err.get_ref().is_some_and(|e| e.is::<AppError>()) {
+            // checked above
+            return *err.into_inner().unwrap().downcast::<AppError>().unwrap();
+        }
+        #[cfg(feature = "3ds")]
+        let code = match err.get_ref().and_then(|e| e.downcast_ref::<ctru::Error>()) {
+            Some(ctru::Error::Os(code)) => Some(ResultCode(*code)),
+            _ => None,
+        };
+        #[cfg(not(feature = "3ds"))]
+        let code = None;
+        AppError::Fs {
+            code,
+            path: None,
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "3ds")]
+impl From<ctru::Error> for AppError {
+    fn from(err: ctru::Error) -> Self {
+        match err {
+            ctru::Error::Os(code) => AppError::Fs {
+                code: Some(ResultCode(code)),
+                path: None,
+                message: t!("error.os").to_string(),
+            },
+            err => AppError::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<ZipError> for AppError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::Io(err) => err.into(),
+            err => AppError::Zip(err),
+        }
+    }
+}
+
+/// plain messages are failures of a step, `Validation` is made explicitly
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+/// the save was not touched if aborted, the error of the step is kept
+impl From<RestoreError> for AppError {
+    fn from(err: RestoreError) -> Self {
+        match err {
+            RestoreError::Aborted(err) => err.into(),
+            err => AppError::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for AppError {
+    fn from(err: Box<dyn Error>) -> Self {
+        let err = match err.downcast::<AppError>() {
+            Ok(err) => return *err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<io::Error>() {
+            Ok(err) => return (*err).into(),
+            Err(err) => err,
+        };
+        match err.downcast::<ZipError>() {
+            Ok(err) => (*err).into(),
+            Err(err) => AppError::Other(err.to_string()),
+        }
+    }
+}
+
+/// # run a network request, tried again on retryable errors
+///
+/// errors of the request are classified by `AppError::from_api`
+pub fn with_network_retry<T>(mut f: impl FnMut() -> Result<T, Box<dyn Error>>) -> AppResult<T> {
+    let retry_times = config::get().network.retry_times;
+    let mut times = 0;
+    loop {
+        match f().map_err(AppError::from_api) {
+            Err(err) if err.is_retryable() && times < retry_times => times += 1,
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retryable() {
+        for (status, is_retryable) in [
+            (-1, true),
+            (7, true),
+            (28, true),
+            (400, false),
+            (401, false),
+            (404, false),
+            (429, true),
+            (500, true),
+            (503, true),
+        ] {
+            assert_eq!(
+                AppError::Network { status }.is_retryable(),
+                is_retryable,
+                "{}",
+                status
+            );
+        }
+        assert!(!AppError::AuthExpired.is_retryable());
+        assert!(!AppError::Cancelled.is_retryable());
+        assert!(!AppError::Other("failed".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn not_found() {
+        let open = |code: u32| AppError::ArchiveOpen {
+            arch: ArchiveID::Extdata,
+            code: ResultCode(code as i32),
+        };
+        // archives and files that do not exist
+        assert!(open(0xC8804478).is_not_found());
+        assert!(open(0xC8804464).is_not_found());
+        // e.g. the sd card is not inserted
+        assert!(!open(0xC8A04555).is_not_found());
+        assert!(
+            !AppError::fs(io::Error::new(io::ErrorKind::NotFound, "missing"), "/a").is_not_found()
+        );
+    }
+
+    #[test]
+    fn api_status() {
+        let classify = |status| AppError::from_status("failed".to_string(), status);
+        assert!(matches!(
+            classify(CURLE_ABORTED_BY_CALLBACK),
+            AppError::Cancelled
+        ));
+        assert!(classify(401).needs_reauth());
+        assert!(classify(403).needs_reauth());
+        assert!(matches!(classify(200), AppError::Other(message) if message == "failed"));
+        assert!(matches!(classify(500), AppError::Network { status: 500 }));
+        assert!(matches!(classify(-1), AppError::Network { status: -1 }));
+
+        // errors of the app are kept as they are
+        let err = AppError::from_api(Box::new(AppError::Quota {
+            required: 2,
+            available: 1,
+        }));
+        assert!(matches!(
+            err,
+            AppError::Quota {
+                required: 2,
+                available: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn cancel_survives_io() {
+        let cancelled = || io::Error::other(AppError::Cancelled);
+        assert!(matches!(AppError::from(cancelled()), AppError::Cancelled));
+        assert!(matches!(
+            AppError::from(ZipError::Io(cancelled())),
+            AppError::Cancelled
+        ));
+        let boxed: Box<dyn Error> = Box::new(cancelled());
+        assert!(matches!(AppError::from(boxed), AppError::Cancelled));
+        assert!(matches!(
+            AppError::fs(cancelled(), "/a.bin"),
+            AppError::Cancelled
+        ));
+    }
+
+    #[test]
+    fn from_io() {
+        let err = AppError::fs(io::Error::new(io::ErrorKind::NotFound, "missing"), "/a.bin");
+        match err {
+            AppError::Fs {
+                code,
+                path,
+                message,
+            } => {
+                assert!(code.is_none());
+                assert_eq!(path, Some(PathBuf::from("/a.bin")));
+                assert_eq!(message, "missing");
+            }
+            err => panic!("{:?}", err),
+        }
+        assert!(matches!(
+            AppError::from(ZipError::Io(io::Error::other("io"))),
+            AppError::Fs { .. }
+        ));
+        assert!(matches!(
+            AppError::from(ZipError::FileNotFound),
+            AppError::Zip(ZipError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn plain_messages() {
+        assert!(matches!(AppError::from("failed"), AppError::Other(_)));
+        assert!(matches!(
+            AppError::from("failed".to_string()),
+            AppError::Other(_)
+        ));
+        let boxed: Box<dyn Error> = "failed".into();
+        assert!(matches!(AppError::from(boxed), AppError::Other(message) if message == "failed"));
+        assert_eq!(AppError::Other("failed".to_string()).to_string(), "failed");
+    }
+}
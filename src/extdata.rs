@@ -0,0 +1,167 @@
+use std::{collections::HashMap, error::Error, fs, sync::OnceLock};
+
+use log::error;
+use serde::Deserialize;
+
+use crate::{constant::EXTDATA_REDIRECT_PATH, t};
+
+static EXTDATA_REDIRECTS: OnceLock<ExtdataRedirects> = OnceLock::new();
+/// the bundled table is built in, so it does not depend on romfs
+const BUNDLED_REDIRECTS: &[u8] = include_bytes!("../romfs/extdata.json");
+
+/// # redirect of titles that share extdata of another title
+///
+/// ids are hex strings, e.g. `0x00055E00`
+#[derive(Clone, Debug, Deserialize)]
+pub struct RedirectEntry {
+    #[serde(default)]
+    pub name: String,
+    pub low_ids: Vec<String>,
+    pub extdata_id: String,
+}
+
+/// where the extdata id of a title comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum RedirectSource {
+    /// `low id >> 8`
+    Default,
+    Bundled(String),
+    User(String),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ExtdataRedirects {
+    /// low id -> (extdata id, source)
+    entries: HashMap<u32, (u32, RedirectSource)>,
+}
+
+fn parse_hex(s: &str) -> Result<u32, Box<dyn Error>> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u32::from_str_radix(s, 16).map_err(|_| t!("extdata.invalid_id", id = s).into())
+}
+
+impl ExtdataRedirects {
+    /// # add entries of the json table
+    ///
+    /// entries added later override the earlier, nothing is added if any
+    /// entry of the table is invalid
+    pub fn merge(&mut self, json: &[u8], is_user: bool) -> Result<(), Box<dyn Error>> {
+        let list: Vec<RedirectEntry> = serde_json::from_slice(json)?;
+        let mut entries = vec![];
+        for entry in list {
+            let extdata_id = parse_hex(&entry.extdata_id)?;
+            for low_id in entry.low_ids.iter() {
+                let source = if is_user {
+                    RedirectSource::User(entry.name.clone())
+                } else {
+                    RedirectSource::Bundled(entry.name.clone())
+                };
+                entries.push((parse_hex(low_id)?, (extdata_id, source)));
+            }
+        }
+        self.entries.extend(entries);
+        Ok(())
+    }
+
+    pub fn resolve(&self, low_id: u32) -> (u32, RedirectSource) {
+        self.entries
+            .get(&low_id)
+            .cloned()
+            .unwrap_or((low_id >> 8, RedirectSource::Default))
+    }
+
+    /// # the bundled table overridden by the user table
+    ///
+    /// a broken user table is skipped, the bundled table is still used
+    fn load() -> Self {
+        let mut res = Self::default();
+        if let Err(err) = res.merge(BUNDLED_REDIRECTS, false) {
+            error!("parse bundled extdata redirects failed: {}", err);
+        }
+        if let Ok(json) = fs::read(EXTDATA_REDIRECT_PATH) {
+            if let Err(err) = res.merge(&json, true) {
+                error!("parse user extdata redirects failed: {}", err);
+            }
+        }
+        res
+    }
+}
+
+pub fn extdata_redirects() -> &'static ExtdataRedirects {
+    EXTDATA_REDIRECTS.get_or_init(ExtdataRedirects::load)
+}
+
+/// extdata id of the title
+pub fn extdata_id(low_id: u32) -> u32 {
+    extdata_redirects().resolve(low_id).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled() -> ExtdataRedirects {
+        let mut redirects = ExtdataRedirects::default();
+        redirects.merge(BUNDLED_REDIRECTS, false).unwrap();
+        redirects
+    }
+
+    #[test]
+    fn bundled_table() {
+        let redirects = bundled();
+        assert_eq!(redirects.entries.len(), 10);
+        assert_eq!(
+            redirects.resolve(0x00055E00),
+            (0x0000055D, RedirectSource::Bundled("Pokemon Y".to_string()))
+        );
+        assert_eq!(redirects.resolve(0x0017A800).0, 0x00001795);
+    }
+
+    #[test]
+    fn resolve_without_redirect() {
+        assert_eq!(
+            bundled().resolve(0x00055D00),
+            (0x0000055D, RedirectSource::Default)
+        );
+        assert_eq!(
+            ExtdataRedirects::default().resolve(0x00055E00),
+            (0x0000055E, RedirectSource::Default)
+        );
+    }
+
+    #[test]
+    fn user_table_overrides() {
+        let mut redirects = bundled();
+        let json = br#"[
+            { "name": "mine", "low_ids": ["55e00", "0X00123400"], "extdata_id": "0x00000100" }
+        ]"#;
+        redirects.merge(json, true).unwrap();
+        assert_eq!(
+            redirects.resolve(0x00055E00),
+            (0x100, RedirectSource::User("mine".to_string()))
+        );
+        assert_eq!(redirects.resolve(0x00123400).0, 0x100);
+        assert_eq!(redirects.resolve(0x0011C400).0, 0x000011C5);
+    }
+
+    #[test]
+    fn invalid_table_adds_nothing() {
+        for json in [
+            &br#"[
+                { "low_ids": ["0x00123400"], "extdata_id": "0x100" },
+                { "low_ids": ["0x00055E00", "not hex"], "extdata_id": "0x200" }
+            ]"#[..],
+            br#"[{ "low_ids": ["0x00055E00"], "extdata_id": "0xZZ" }]"#,
+            br#"[{ "low_ids": ["0x00055E00"] }]"#,
+            b"not json",
+        ] {
+            let mut redirects = bundled();
+            assert!(redirects.merge(json, true).is_err());
+            assert_eq!(redirects.entries, bundled().entries);
+        }
+    }
+}
@@ -17,6 +17,12 @@ use std::slice;
 use std::sync::Arc;
 use widestring::{WideCStr, WideCString};
 
+use crate::extdata::extdata_id;
+use crate::platform::{pl_arch_free_bytes, pl_commit_arch_data};
+use crate::t;
+use crate::utils::{copy_buf, round_up_to_block};
+use crate::vfs::{Vfs, VfsEntry, VfsMetadata};
+
 bitflags! {
     #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
     struct FsOpen: u32 {
@@ -58,6 +64,20 @@ pub struct Archive {
     pub handle: u64,
 }
 
+/// Capacity and usage of an archive, see [`arch_info`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArchiveInfo {
+    /// Size of the archive in bytes, extdata may report 0 as it has no fixed size.
+    pub total: u64,
+    pub free: u64,
+    /// Maximum number of directories.
+    pub max_dirs: u32,
+    /// Maximum number of files.
+    pub max_files: u32,
+    /// Allocation unit of the files, a file always takes whole blocks.
+    pub block_size: u64,
+}
+
 /// A reference to an open file on the filesystem.
 ///
 /// An instance of a `File` can be read and/or written to depending
@@ -206,26 +226,6 @@ pub struct OpenOptions {
     arch_handle: u64,
 }
 
-fn extdata_redirect(low_id: u32) -> u32 {
-    match low_id {
-        // Pokemon Y
-        0x00055E00 => 0x0000055D,
-        // Pokemon OR
-        0x0011C400 => 0x000011C5,
-        // Pkmn moon
-        0x00175E00 => 0x00001648,
-        // Ultra moon
-        0x001B5100 => 0x00001B50,
-        // FE Conquest + SE NA
-        0x00179600 | 0x00179800 => 0x00001794,
-        // FE Conquest + SE Euro
-        0x00179700 | 0x0017A800 => 0x00001795,
-        // FE if JP
-        0x0012DD00 | 0x0012DE00 => 0x000012DC,
-        _ => low_id >> 8,
-    }
-}
-
 /// Iterator over the entries in a directory.
 ///
 /// This iterator is returned from the [`read_dir`] function and
@@ -268,7 +268,7 @@ pub fn arch(
     high_id: u32,
     low_id: u32,
 ) -> ctru::Result<Archive> {
-    let ext_data = extdata_redirect(low_id);
+    let ext_data = extdata_id(low_id);
     unsafe {
         let mut handle = 0;
         if let Some(r) = match arch_id {
@@ -363,6 +363,18 @@ impl Archive {
     }
 }
 
+impl ArchiveInfo {
+    /// Bytes in use, 0 if the archive has no fixed size.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// Bytes taken by a file of `size`, rounded up to whole blocks.
+    pub fn blocks_size(&self, size: u64) -> u64 {
+        round_up_to_block(size, self.block_size)
+    }
+}
+
 impl File {
     /// Attempts to open a file in read-only mode.
     ///
@@ -965,6 +977,133 @@ where
     }
 }
 
+/// Queries the capacity of an archive, i.e. its total size, free space and
+/// the maximum number of directories and files.
+///
+/// The format info is read by the path of the archive, so the ids of the
+/// title are needed as for [`arch`].
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * The archive has no format info, e.g. the SD card.
+#[doc(alias = "FSUSER_GetFormatInfo")]
+pub fn arch_info(
+    arch: &Archive,
+    media: MediaType,
+    high_id: u32,
+    low_id: u32,
+) -> IoResult<ArchiveInfo> {
+    let ext_data = extdata_id(low_id);
+    let (path_data, size, block_size): ([u32; 3], u32, u64) = match arch.id {
+        ArchiveID::UserSavedata => ([media.into(), low_id, high_id], 12, 0x200),
+        ArchiveID::SystemSavedata => (
+            [MediaType::Nand.into(), (0x00020000 | (low_id >> 8)), 0],
+            8,
+            0x200,
+        ),
+        ArchiveID::Extdata | ArchiveID::BossExtdata => {
+            ([MediaType::Sd.into(), ext_data, 0], 12, 0x1000)
+        }
+        ArchiveID::SharedExtdata => ([MediaType::Nand.into(), ext_data, 0x00048000], 0xC, 0x1000),
+        _ => return Err(IoError::from(IoErrorKind::Unsupported)),
+    };
+    let free = pl_arch_free_bytes(arch)
+        .ok_or_else(|| IoError::new(IoErrorKind::Other, t!("fsu.free_bytes_failed")))?;
+    let mut info = ArchiveInfo {
+        free,
+        block_size,
+        ..Default::default()
+    };
+    let mut total = 0;
+    let mut duplicate_data = false;
+    unsafe {
+        let path = ctru_sys::FS_Path {
+            type_: PATH_BINARY,
+            size,
+            data: path_data.as_ptr() as *const c_void,
+        };
+        let r = ctru_sys::FSUSER_GetFormatInfo(
+            &mut total,
+            &mut info.max_dirs,
+            &mut info.max_files,
+            &mut duplicate_data,
+            arch.id.into(),
+            path,
+        );
+        if r < 0 {
+            return Err(IoError::new(IoErrorKind::Other, ctru::Error::from(r)));
+        }
+    }
+    info.total = total as u64;
+    Ok(info)
+}
+
+/// Copies a file to another path, the archives may differ, e.g. from a save
+/// archive to the SD card.
+///
+/// The destination is created with the size of the source up front, an
+/// existing one is truncated first so no data of it is left behind. Returns
+/// the number of bytes copied.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is not limited to just
+/// these cases:
+///
+/// * from does not exist or is a directory.
+/// * The parent directory of to does not exist.
+/// * The operation was cancelled, see `cancel::check`.
+pub fn copy<P, Q>(from: (P, &Archive), to: (Q, &Archive)) -> IoResult<u64>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut input = File::open(from.1, from.0.as_ref())?;
+    let size = input.metadata()?.len();
+    let mut output = OpenOptions::new()
+        .input_size(Some(size))
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .archive(to.1)
+        .open(to.0.as_ref())?;
+    copy_buf(&mut input, &mut output)
+}
+
+/// Recursively copies a directory to another path, the archives may differ.
+///
+/// The directory structure is kept, missing directories of to are created.
+/// Returns the total number of bytes of the copied files.
+///
+/// # Errors
+///
+/// see [`copy`] and [`create_dir_all`]
+pub fn copy_dir_all<P, Q>(from: (P, &Archive), to: (Q, &Archive)) -> IoResult<u64>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (from, from_arch) = (from.0.as_ref(), from.1);
+    let (to, to_arch) = (to.0.as_ref(), to.1);
+    if !is_dir_exists(to_arch, to) {
+        create_dir_all(to_arch, to)?;
+    }
+    let mut total = 0;
+    for entry in read_dir(from_arch, from)? {
+        let entry = entry?;
+        let dst = to.join(entry.file_name());
+        if entry.metadata()?.is_dir() {
+            total += copy_dir_all((entry.path(), from_arch), (&dst, to_arch))?;
+        } else {
+            total += copy((entry.path(), from_arch), (&dst, to_arch))?;
+        }
+    }
+    Ok(total)
+}
+
 // TODO: Determine if we should check UTF-16 paths for interior NULs
 fn to_utf16(path: &Path) -> WideCString {
     WideCString::from_str(path).unwrap()
@@ -1063,6 +1202,71 @@ impl Seek for File {
     }
 }
 
+impl Vfs for Archive {
+    type File = File;
+
+    fn open(&self, path: &Path) -> IoResult<File> {
+        File::open(self, path)
+    }
+
+    fn create(&self, path: &Path, size: Option<u64>) -> IoResult<File> {
+        File::create(self, path, size)
+    }
+
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<VfsEntry>> {
+        read_dir(self, path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(VfsEntry {
+                    path: entry.path(),
+                    metadata: entry.metadata()?.into(),
+                })
+            })
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> IoResult<VfsMetadata> {
+        Ok(metadata(self, path)?.into())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> IoResult<()> {
+        create_dir_all(self, path)
+    }
+
+    fn remove_file(&self, path: &Path) -> IoResult<()> {
+        remove_file(self, path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> IoResult<()> {
+        remove_dir_all(self, path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> IoResult<()> {
+        rename(self, from, to)
+    }
+
+    /// save data archives are only written to the card / sd when committed
+    fn commit(&self) -> IoResult<()> {
+        if !pl_commit_arch_data(self) {
+            return Err(IoError::new(IoErrorKind::Other, t!("utils.commit_failed")));
+        }
+        Ok(())
+    }
+
+    fn is_dir_exists(&self, path: &Path) -> bool {
+        is_dir_exists(self, path)
+    }
+}
+
+impl From<Metadata> for VfsMetadata {
+    fn from(metadata: Metadata) -> Self {
+        VfsMetadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+        }
+    }
+}
+
 impl Drop for Archive {
     #[doc(alias = "FSUSER_CloseArchive")]
     fn drop(&mut self) {
@@ -1,4 +1,4 @@
-use std::ffi::{c_char, c_int, c_longlong, c_void};
+use std::ffi::{c_char, c_int, c_long, c_longlong, c_void};
 
 pub type HttpProgressCb = extern "C" fn(
     client_ptr: *mut c_void,
@@ -27,6 +27,28 @@ extern "C" {
         is_follow: c_int,
     ) -> *mut HttpResponseRaw;
     pub fn http_free_response(response: *mut HttpResponseRaw);
+    pub fn http_set_cancelled(cancelled: bool);
+    pub fn http_get_progress(now: *mut c_longlong, total: *mut c_longlong);
+    pub fn http_get_last_status() -> c_long;
+    pub fn http_set_request_options(connect_timeout: c_long, buffer_size: c_long);
+}
+
+/// (bytes done, bytes total) of the running transfer
+pub fn http_transfer_progress() -> (u64, u64) {
+    let mut now = 0;
+    let mut total = 0;
+    unsafe { http_get_progress(&mut now, &mut total) };
+    (now.max(0) as u64, total.max(0) as u64)
+}
+
+/// http status of the last request, or the code of curl if the request failed
+pub fn http_last_status() -> i64 {
+    unsafe { http_get_last_status() as i64 }
+}
+
+/// connect timeout and receive buffer size of the following requests
+pub fn http_set_options(connect_timeout_secs: u32, buffer_size: u32) {
+    unsafe { http_set_request_options(connect_timeout_secs as c_long, buffer_size as c_long) };
 }
 
 pub struct HttpContext;
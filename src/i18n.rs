@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    sync::{OnceLock, RwLock},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+static CATALOGS: OnceLock<HashMap<Lang, Catalog>> = OnceLock::new();
+/// language of the session, `None` until `init`
+static LANG: RwLock<Option<Lang>> = RwLock::new(None);
+
+/// # message of the current language
+///
+/// - `t!("key")` returns `&'static str`
+/// - `t!("key", name = value, ...)` returns `String`, `{name}` of the message is
+///   replaced by the value, `count` selects the plural form
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::tr_static($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Lang {
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "ja")]
+    Ja,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 3] = [Lang::ZhCn, Lang::En, Lang::Ja];
+
+    fn catalog_json(&self) -> &'static str {
+        match self {
+            Lang::ZhCn => include_str!("../i18n/zh-CN.json"),
+            Lang::En => include_str!("../i18n/en.json"),
+            Lang::Ja => include_str!("../i18n/ja.json"),
+        }
+    }
+
+    /// plural form of `count`, only english has `one`
+    fn plural_form(&self, count: i64) -> &'static str {
+        match self {
+            Lang::En if count == 1 => "one",
+            _ => "other",
+        }
+    }
+}
+
+impl Display for Lang {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lang::ZhCn => write!(f, "简体中文"),
+            Lang::En => write!(f, "English"),
+            Lang::Ja => write!(f, "日本語"),
+        }
+    }
+}
+
+/// a plain message, or plural forms keyed by `one` / `other`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+type Catalog = HashMap<String, Message>;
+
+fn catalogs() -> &'static HashMap<Lang, Catalog> {
+    CATALOGS.get_or_init(|| {
+        Lang::ALL
+            .iter()
+            .map(|&lang| {
+                let catalog = serde_json::from_str(lang.catalog_json()).unwrap_or_else(|err| {
+                    error!("parse catalog of {:?} failed: {}", lang, err);
+                    HashMap::new()
+                });
+                (lang, catalog)
+            })
+            .collect()
+    })
+}
+
+fn lookup(lang: Lang, key: &str, count: Option<i64>) -> Option<&'static str> {
+    match catalogs().get(&lang)?.get(key)? {
+        Message::Text(text) => Some(text),
+        Message::Plural(forms) => forms
+            .get(lang.plural_form(count.unwrap_or(0)))
+            .or_else(|| forms.get("other"))
+            .map(|text| text.as_str()),
+    }
+}
+
+/// message of the current language, zh-CN if missing, the key itself if missing in both
+fn message(key: &'static str, count: Option<i64>) -> &'static str {
+    lookup(lang(), key, count)
+        .or_else(|| lookup(Lang::ZhCn, key, count))
+        .unwrap_or(key)
+}
+
+/// replace `{name}` of the text by the value of the arg
+pub fn interpolate(text: &str, args: &[(&str, String)]) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        res.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}').and_then(|end| {
+            args.iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|a| (end, a))
+        }) {
+            Some((end, (_, value))) => {
+                res.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                res.push('{');
+                rest = after;
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+pub fn tr_static(key: &'static str) -> &'static str {
+    message(key, None)
+}
+
+pub fn tr(key: &'static str, args: &[(&str, String)]) -> String {
+    let count = args
+        .iter()
+        .find(|(name, _)| *name == "count")
+        .and_then(|(_, value)| value.parse().ok());
+    interpolate(message(key, count), args)
+}
+
+pub fn lang() -> Lang {
+    LANG.read()
+        .ok()
+        .and_then(|lang| *lang)
+        .unwrap_or(Lang::ZhCn)
+}
+
+pub fn set_lang(lang: Lang) {
+    if let Ok(mut l) = LANG.write() {
+        *l = Some(lang);
+    }
+}
+
+/// the next language of the setting, `None` follows the system
+pub fn next_lang(lang: Option<Lang>) -> Option<Lang> {
+    match lang {
+        None => Some(Lang::ALL[0]),
+        Some(lang) => Lang::ALL
+            .iter()
+            .position(|&l| l == lang)
+            .and_then(|idx| Lang::ALL.get(idx + 1))
+            .copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        args.iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn interpolate_args() {
+        assert_eq!(
+            interpolate("{a} and {b}, {a}", &args(&[("a", "1"), ("b", "2")])),
+            "1 and 2, 1"
+        );
+        assert_eq!(interpolate("no args", &args(&[("a", "1")])), "no args");
+        assert_eq!(interpolate("{å}!", &args(&[("å", "ü")])), "ü!");
+    }
+
+    #[test]
+    fn unknown_args_are_kept() {
+        assert_eq!(interpolate("{a} {b}", &args(&[("a", "1")])), "1 {b}");
+        assert_eq!(interpolate("{ {a}", &args(&[("a", "1")])), "{ 1");
+        assert_eq!(interpolate("{a", &args(&[("a", "1")])), "{a");
+        assert_eq!(interpolate("}{}", &[]), "}{}");
+    }
+
+    #[test]
+    fn catalogs_are_valid() {
+        for lang in Lang::ALL {
+            assert!(!catalogs()[&lang].is_empty(), "{:?}", lang);
+        }
+    }
+
+    #[test]
+    fn plural_forms() {
+        assert_eq!(
+            lookup(Lang::En, "diff.more", Some(1)),
+            Some("... {count} more item")
+        );
+        assert_eq!(
+            lookup(Lang::En, "diff.more", Some(2)),
+            Some("... {count} more items")
+        );
+        assert_eq!(
+            lookup(Lang::ZhCn, "diff.more", Some(1)),
+            lookup(Lang::ZhCn, "diff.more", Some(2))
+        );
+        assert_eq!(lookup(Lang::En, "missing.key", None), None);
+    }
+
+    #[test]
+    fn next_lang_cycles() {
+        let mut lang = None;
+        let mut langs = vec![];
+        for _ in 0..4 {
+            lang = next_lang(lang);
+            langs.push(lang);
+        }
+        assert_eq!(
+            langs,
+            vec![Some(Lang::ZhCn), Some(Lang::En), Some(Lang::Ja), None]
+        );
+    }
+}
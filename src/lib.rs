@@ -1,11 +1,51 @@
+#[cfg(feature = "3ds")]
 pub mod api;
+#[cfg(feature = "3ds")]
 pub mod app;
+#[cfg(feature = "3ds")]
 pub mod c2d;
+pub mod cancel;
+pub mod checkpoint;
+pub mod config;
 pub mod constant;
+pub mod crypto;
+pub mod diff;
+pub mod emulator;
+pub mod error;
+pub mod extdata;
+#[cfg(feature = "3ds")]
 pub mod fsu;
+#[cfg(feature = "3ds")]
 pub mod http;
+pub mod i18n;
+#[cfg(feature = "3ds")]
 pub mod loader;
+pub mod manifest;
+#[cfg(feature = "3ds")]
 pub mod platform;
+pub mod preflight;
+pub mod progress;
+pub mod recovery;
+#[cfg(feature = "3ds")]
 pub mod render;
+#[cfg(feature = "3ds")]
 pub mod resource;
+pub mod restore;
+pub mod retention;
+pub mod save_types;
+pub mod store;
 pub mod utils;
+pub mod vfs;
+
+// the core without the hardware modules, for the host
+#[cfg(not(feature = "3ds"))]
+pub mod app {
+    pub mod list_display_status;
+    pub mod list_wrap_display_status;
+}
+#[cfg(not(feature = "3ds"))]
+#[path = "stub/http.rs"]
+pub mod http;
+#[cfg(not(feature = "3ds"))]
+#[path = "stub/platform.rs"]
+pub mod platform;
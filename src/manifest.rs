@@ -0,0 +1,282 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    io::{self, Read, Seek, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{constant::BACKUP_MANIFEST_NAME, t, utils::get_current_format_time};
+
+/// bump this when the manifest layout changes
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// path inside the save archive, `/` separated, without leading `/`
+    pub path: String,
+    pub size: u64,
+    pub md5: String,
+}
+
+/// # manifest.json of a save backup zip
+///
+/// written as the last entry of every backup, it describes where the backup
+/// comes from without relying on the folder name or file name
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    /// title id in hex, same format as the backup dir prefix, e.g. `0x4000000055D00`
+    pub title_id: String,
+    pub product_code: String,
+    /// `sd`, `nand` or `card`
+    pub media_type: String,
+    /// `user`, `ext`, `sys` or `boss`
+    pub save_type: String,
+    pub app_version: String,
+    /// local time, `YYYY-MM-DD HH.MM.SS.mmm`
+    pub created_at: String,
+    /// unix timestamp in milliseconds
+    pub created_at_ms: u64,
+    /// secure value of the save at backup time, only user saves have it
+    #[serde(default)]
+    pub secure_value: Option<u64>,
+    #[serde(default)]
+    pub files: Vec<ManifestFile>,
+}
+
+impl BackupManifest {
+    pub fn new(title_id: String, product_code: String, media_type: &str, save_type: &str) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            title_id,
+            product_code,
+            media_type: media_type.to_string(),
+            save_type: save_type.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: get_current_format_time().to_string(),
+            created_at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            secure_value: None,
+            files: vec![],
+        }
+    }
+
+    /// total uncompressed size of the save files
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    pub fn get_file(&self, path: &str) -> Option<&ManifestFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// write manifest as the `BACKUP_MANIFEST_NAME` entry of the zip
+    pub fn write_to<W: Write + Seek>(&self, zip: &mut ZipWriter<W>) -> Result<(), Box<dyn Error>> {
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(BACKUP_MANIFEST_NAME, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// read the `BACKUP_MANIFEST_NAME` entry of the zip, `None` if the backup has no manifest
+    pub fn read_from<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Option<Self> {
+        let mut file = zip.by_name(BACKUP_MANIFEST_NAME).ok()?;
+        let mut data = String::new();
+        file.read_to_string(&mut data).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+}
+
+/// # verify backup zip
+///
+/// every entry is read to the end so the crc32 of it is checked, the size and
+/// md5 of the files are checked against the manifest if the backup has one.
+/// nothing is written, so it is safe to call before the save archive is touched
+pub fn verify_backup<R: Read + Seek>(reader: R) -> Result<Option<BackupManifest>, Box<dyn Error>> {
+    let mut zip = ZipArchive::new(reader).map_err(|e| t!("common.backup_read_failed", err = e))?;
+    let manifest = BackupManifest::read_from(&mut zip);
+    let mut checked = HashSet::new();
+    let mut total = 0u64;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        if file.is_dir() || name == BACKUP_MANIFEST_NAME {
+            continue;
+        }
+        let mut output = HashWriter::new(io::sink());
+        io::copy(&mut file, &mut output).map_err(|e| format!("{}: {}", name, e))?;
+        let (size, md5) = output.finish();
+        if size != file.size() {
+            return Err(t!(
+                "manifest.size_mismatch",
+                name = name,
+                size = size,
+                expected = file.size()
+            )
+            .into());
+        }
+        if let Some(manifest) = manifest.as_ref() {
+            match manifest.get_file(&name) {
+                Some(info) if info.size != size => {
+                    return Err(t!("manifest.size_differs", name = name).into());
+                }
+                Some(info) if info.md5 != md5 => {
+                    return Err(t!("manifest.md5_differs", name = name).into());
+                }
+                Some(_) => {}
+                None => {
+                    return Err(t!("manifest.not_listed", name = name).into());
+                }
+            }
+        }
+        checked.insert(name);
+        total += size;
+    }
+
+    if let Some(manifest) = manifest.as_ref() {
+        if let Some(file) = manifest.files.iter().find(|f| !checked.contains(&f.path)) {
+            return Err(t!("manifest.file_missing", name = file.path).into());
+        }
+        if total != manifest.total_size() {
+            return Err(t!(
+                "manifest.total_size_differs",
+                size = total,
+                expected = manifest.total_size()
+            )
+            .into());
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// # hash file content while it is being copied
+pub struct HashWriter<W: Write> {
+    inner: W,
+    context: md5::Context,
+    size: u64,
+}
+
+impl<W: Write> HashWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            context: md5::Context::new(),
+            size: 0,
+        }
+    }
+
+    /// returns (size, md5 hex)
+    pub fn finish(self) -> (u64, String) {
+        (self.size, format!("{:x}", self.context.compute()))
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let size = self.inner.write(buf)?;
+        self.context.consume(&buf[0..size]);
+        self.size += size as u64;
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    const FILES: [(&str, &[u8]); 2] = [("main", b"main data"), ("dir/a.bin", b"a")];
+
+    fn manifest_of(files: &[(&str, &[u8])]) -> BackupManifest {
+        let mut manifest = BackupManifest::new(
+            "0x4000000055D00".to_string(),
+            "CTR-P-EKJA".to_string(),
+            "sd",
+            "user",
+        );
+        manifest.files = files
+            .iter()
+            .map(|(path, data)| ManifestFile {
+                path: path.to_string(),
+                size: data.len() as u64,
+                md5: format!("{:x}", md5::compute(data)),
+            })
+            .collect();
+        manifest
+    }
+
+    fn zip_of(files: &[(&str, &[u8])], manifest: Option<&BackupManifest>) -> Cursor<Vec<u8>> {
+        // stored, so the data can be corrupted in place
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        zip.add_directory("dir", options).unwrap();
+        for (path, data) in files {
+            zip.start_file(*path, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        if let Some(manifest) = manifest {
+            manifest.write_to(&mut zip).unwrap();
+        }
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn valid_backup() {
+        let manifest = manifest_of(&FILES);
+        let res = verify_backup(zip_of(&FILES, Some(&manifest)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.files, manifest.files);
+        assert_eq!(res.total_size(), 10);
+    }
+
+    #[test]
+    fn backup_without_manifest() {
+        assert!(verify_backup(zip_of(&FILES, None)).unwrap().is_none());
+    }
+
+    #[test]
+    fn content_differs_from_manifest() {
+        let manifest = manifest_of(&FILES);
+        let files = [("main", &b"main DATA"[..]), FILES[1]];
+        assert!(verify_backup(zip_of(&files, Some(&manifest))).is_err());
+        let files = [("main", &b"main"[..]), FILES[1]];
+        assert!(verify_backup(zip_of(&files, Some(&manifest))).is_err());
+    }
+
+    #[test]
+    fn files_differ_from_manifest() {
+        // file missing in the zip
+        let manifest = manifest_of(&FILES);
+        assert!(verify_backup(zip_of(&FILES[..1], Some(&manifest))).is_err());
+        // file not listed in the manifest
+        let manifest = manifest_of(&FILES[..1]);
+        assert!(verify_backup(zip_of(&FILES, Some(&manifest))).is_err());
+    }
+
+    #[test]
+    fn corrupted_zip() {
+        let mut data = zip_of(&FILES, None).into_inner();
+        // flip a byte of the data of the first entry, the crc32 check fails
+        let pos = data
+            .windows(FILES[0].1.len())
+            .position(|w| w == FILES[0].1)
+            .unwrap();
+        data[pos] ^= 0xff;
+        assert!(verify_backup(Cursor::new(data)).is_err());
+        assert!(verify_backup(Cursor::new(b"not a zip".to_vec())).is_err());
+    }
+}
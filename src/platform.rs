@@ -9,11 +9,12 @@ use std::{
 };
 
 use ctru::{
-    applets::swkbd::{Button, ButtonConfig, CallbackResult, Kind, SoftwareKeyboard},
+    applets::swkbd::{Button, ButtonConfig, CallbackResult, Kind, PasswordMode, SoftwareKeyboard},
     error::ResultCode,
     services::{
         self,
         am::Am,
+        cfgu::{Cfgu, Language},
         fs::{ArchiveID, MediaType},
         romfs::RomFS,
         soc::Soc,
@@ -21,14 +22,21 @@ use ctru::{
 };
 use ctru_sys::{FS_CardType, CARD_CTR};
 use dioxus::prelude::*;
+use log::error;
 use tokio::task::JoinError;
 
 use crate::{
     app::titles::title_selected::SaveTypes,
-    constant::{CACHE_ICON_NAME, FBI_SC_TITLE_ID, HOME_LOCAL_PATH_CACHE, INVALID_CHARS},
+    constant::{
+        CACHE_ICON_NAME, CACHE_SAVE_TYPES_NAME, FBI_SC_TITLE_ID, HOME_LOCAL_PATH_CACHE,
+        INVALID_CHARS,
+    },
     fsu::{self, Archive},
+    i18n::Lang,
     render::image_data_set::get_image_raw_buf,
     resource::{Resource, TitleInfo},
+    save_types::{read_cache, sync_titles, write_cache, SaveTypesCache},
+    t,
     utils::{join_path, str_to_c_null_term_bytes},
 };
 
@@ -57,9 +65,21 @@ extern "C" {
     fn pl_is_n3ds() -> bool;
     fn pl_commit_data(arch_id: c_uint, arch: c_ulonglong) -> c_int;
     fn pl_delete_sv(arch_id: c_uint, unique_id: c_uint) -> c_int;
+    fn pl_get_sv(unique_id: c_uint, exists: *mut bool, value: *mut c_ulonglong) -> c_int;
+    fn pl_set_sv(unique_id: c_uint, value: c_ulonglong) -> c_int;
+    fn pl_create_ext_data(
+        ext_id: c_uint,
+        title_id: c_ulonglong,
+        media: u8,
+        dirs: c_uint,
+        files: c_uint,
+        size: c_ulonglong,
+    ) -> c_int;
     fn pl_open_title(title_id: c_ulonglong, media: u8, path: *const c_char) -> c_int;
     fn pl_env_is_homebrew() -> bool;
     fn pl_get_storage_info(free: *mut c_ulonglong, total: *mut c_ulonglong) -> c_int;
+    fn pl_get_battery(level: *mut u8, charging: *mut u8) -> c_int;
+    fn pl_get_free_bytes(arch: c_ulonglong, free: *mut c_ulonglong) -> c_int;
     // os function
     fn osSetSpeedupEnable(enable: bool);
 }
@@ -175,23 +195,98 @@ pub fn get_title_id_list_from_device(count: u32, mediatype: MediaType) -> ctru::
     Ok(ids)
 }
 
+fn save_types_cache_path() -> String {
+    join_path(HOME_LOCAL_PATH_CACHE, CACHE_SAVE_TYPES_NAME)
+}
+
+/// `None` if the cache is missing, of other versions or broken
+fn read_save_types_cache() -> Option<SaveTypesCache> {
+    read_cache(fs::File::open(save_types_cache_path()).ok()?)
+}
+
+fn write_save_types_cache(cache: &SaveTypesCache) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(HOME_LOCAL_PATH_CACHE)?;
+    let mut buf = vec![];
+    write_cache(cache, &mut buf)?;
+    fs::write(save_types_cache_path(), buf)?;
+    Ok(())
+}
+
+fn probe_title_save_types(id: u64, media: MediaType) -> u8 {
+    let (high_id, low_id) = ((id >> 32) as u32, id as u32);
+    let mut res = 0u8;
+    if is_exists_user_game_save(high_id, low_id, media) {
+        res = res | 1;
+    }
+    if is_exists_ext_game_save(high_id, low_id, media) {
+        res = res | 2;
+    }
+    if is_exists_sys_game_save(high_id, low_id, media) {
+        res = res | 4;
+    }
+    if is_exists_boss_game_save(high_id, low_id, media) {
+        res = res | 8;
+    }
+    res
+}
+
+/// # save types of the titles
+///
+/// only titles not in the cache are probed, entries of removed titles are
+/// dropped, the cache is rewritten if it changed
 pub fn get_titles_save_types(ids: Vec<(u64, MediaType)>) {
+    let Some(mut cache) = read_save_types_cache() else {
+        refresh_titles_save_types(ids);
+        return;
+    };
+    let keys = ids
+        .iter()
+        .map(|&(id, media)| (id, media as u8))
+        .collect::<Vec<_>>();
+    let count = cache.len();
+    let added = sync_titles(&mut cache, &keys);
+    let is_changed = !added.is_empty() || cache.len() != count;
     for (id, media) in ids.into_iter() {
-        let (high_id, low_id) = ((id >> 32) as u32, id as u32);
-        let mut res = 0u8;
-        if is_exists_user_game_save(high_id, low_id, media) {
-            res = res | 1;
+        if added.contains(&(id, media as u8)) {
+            cache.insert((id, media as u8), probe_title_save_types(id, media));
         }
-        if is_exists_ext_game_save(high_id, low_id, media) {
-            res = res | 2;
-        }
-        if is_exists_sys_game_save(high_id, low_id, media) {
-            res = res | 4;
-        }
-        if is_exists_boss_game_save(high_id, low_id, media) {
-            res = res | 8;
+    }
+    SaveTypes::clear_titles_save_types();
+    for (&(id, _), &save_types) in cache.iter() {
+        SaveTypes::set_title_save_type(id, save_types);
+    }
+    if is_changed {
+        if let Err(err) = write_save_types_cache(&cache) {
+            error!("write save types cache failed: {}", err);
         }
+    }
+}
+
+/// # probe save types of the title again, e.g. after its extdata is created
+///
+/// the entry of the cache is updated, returns the save types
+pub fn update_title_save_types(id: u64, media: MediaType) -> u8 {
+    let res = probe_title_save_types(id, media);
+    SaveTypes::set_title_save_type(id, res);
+    let mut cache = read_save_types_cache().unwrap_or_default();
+    cache.insert((id, media as u8), res);
+    if let Err(err) = write_save_types_cache(&cache) {
+        error!("write save types cache failed: {}", err);
+    }
+    res
+}
+
+/// probe save types of the titles and rewrite the cache
+pub fn refresh_titles_save_types(ids: Vec<(u64, MediaType)>) {
+    SaveTypes::clear_titles_save_types();
+    let mut cache = HashMap::new();
+    for (id, media) in ids.into_iter() {
+        let res = probe_title_save_types(id, media);
         SaveTypes::set_title_save_type(id, res);
+        cache.insert((id, media as u8), res);
+    }
+    if let Err(err) = write_save_types_cache(&cache) {
+        error!("write save types cache failed: {}", err);
     }
 }
 
@@ -349,7 +444,7 @@ pub fn pl_show_swkbd(kind: Kind, resource: &Resource, initial_text: &str) -> Opt
             if str.contains(*c) {
                 return (
                     CallbackResult::Retry,
-                    Some(r#"不能包含此类字符: \ /:*?"'<>|"#.into()),
+                    Some(t!("platform.invalid_chars").into()),
                 );
             }
         }
@@ -375,6 +470,24 @@ pub fn pl_show_swkbd(kind: Kind, resource: &Resource, initial_text: &str) -> Opt
     }
 }
 
+/// input passphrase of encrypted backups, the input is hidden
+pub fn pl_show_swkbd_password(resource: &Resource) -> Option<String> {
+    let mut keyboard = SoftwareKeyboard::new(Kind::Normal, ButtonConfig::LeftRight);
+    keyboard.set_password_mode(PasswordMode::HideDelay);
+    keyboard.set_hint_text(Some(t!("platform.passphrase_hint")));
+
+    match keyboard.launch(&resource.apt, &resource.c2d.gfx) {
+        Ok((text, Button::Right)) => {
+            if text.is_empty() {
+                None
+            } else {
+                Some(text)
+            }
+        }
+        _ => None,
+    }
+}
+
 pub fn pl_commit_arch_data(arch: &Archive) -> bool {
     unsafe { pl_commit_data(arch.id.into(), arch.handle) >= 0 }
 }
@@ -383,6 +496,29 @@ pub fn pl_delete_arch_sv(arch: &Archive, unique_id: u32) -> bool {
     unsafe { pl_delete_sv(arch.id.into(), unique_id) >= 0 }
 }
 
+/// secure value of the user save, `None` if it does not exist
+pub fn pl_get_title_sv(unique_id: u32) -> Option<u64> {
+    let mut exists = false;
+    let mut value = 0;
+    unsafe { (pl_get_sv(unique_id, &mut exists, &mut value) >= 0 && exists).then_some(value) }
+}
+
+pub fn pl_set_title_sv(unique_id: u32, value: u64) -> bool {
+    unsafe { pl_set_sv(unique_id, value) >= 0 }
+}
+
+/// create extdata of the title, `dirs` and `files` are the max count of entries
+pub fn pl_create_ext_save_data(
+    ext_id: u32,
+    title_id: u64,
+    media: u8,
+    dirs: u32,
+    files: u32,
+    size: u64,
+) -> bool {
+    unsafe { pl_create_ext_data(ext_id, title_id, media, dirs, files, size) >= 0 }
+}
+
 pub fn pl_open_the_title(title_id: u64, media: u8, path: &str) -> bool {
     let path = str_to_c_null_term_bytes(path);
     unsafe { pl_open_title(title_id, media, path.as_ptr()) >= 0 }
@@ -401,6 +537,31 @@ pub fn pl_storage_info() -> (u64, u64) {
     (free, total)
 }
 
+/// battery level from 0 to 5 and whether it is charging
+pub fn pl_battery_info() -> Option<(u8, bool)> {
+    let mut level = 0;
+    let mut charging = 0;
+    unsafe { (pl_get_battery(&mut level, &mut charging) >= 0).then_some((level, charging != 0)) }
+}
+
+pub fn pl_arch_free_bytes(arch: &Archive) -> Option<u64> {
+    let mut free = 0;
+    unsafe { (pl_get_free_bytes(arch.handle, &mut free) >= 0).then_some(free) }
+}
+
+/// language of the console, chinese if it can not be read
+pub fn pl_system_lang() -> Lang {
+    match Cfgu::new().and_then(|cfgu| cfgu.language()) {
+        Ok(Language::SimplifiedChinese | Language::TraditionalChinese) => Lang::ZhCn,
+        Ok(Language::Japanese) => Lang::Ja,
+        Ok(_) => Lang::En,
+        Err(err) => {
+            error!("read system language failed: {}", err);
+            Lang::ZhCn
+        }
+    }
+}
+
 pub fn pl_is_fbi_title_exists() -> bool {
     SaveTypes::get_title_save_type(FBI_SC_TITLE_ID).is_some()
 }
@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{t, utils::format_size};
+
+/// warn if free space of sd is less than this after the operation
+const SD_FREE_MARGIN: u64 = 1024 * 1024 * 32;
+/// battery level of ptm, 0: drained, 1: critical, ... 5: full
+const BATTERY_LEVEL_CRITICAL: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightCheck {
+    Battery,
+    SdSpace,
+    SaveSpace,
+    Wifi,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PreflightOp {
+    Backup,
+    CloudBackup,
+    Restore,
+    /// before the cloud backup to restore is downloaded
+    CloudRestore,
+}
+
+/// a blocking issue stops the operation, the others can be ignored
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreflightIssue {
+    pub check: PreflightCheck,
+    pub message: String,
+    pub is_blocking: bool,
+}
+
+/// # preflight config, part of the app config
+///
+/// checks not in the list are skipped
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PreflightConfig {
+    #[serde(default = "default_checks")]
+    pub checks: Vec<PreflightCheck>,
+    /// warn if the battery level is not above this and not charging
+    #[serde(default = "default_min_battery_level")]
+    pub min_battery_level: u8,
+    /// warn if the wifi strength is below this before cloud work, 0 to 3
+    #[serde(default = "default_min_wifi_strength")]
+    pub min_wifi_strength: u8,
+}
+
+fn default_checks() -> Vec<PreflightCheck> {
+    vec![
+        PreflightCheck::Battery,
+        PreflightCheck::SdSpace,
+        PreflightCheck::SaveSpace,
+        PreflightCheck::Wifi,
+    ]
+}
+
+fn default_min_battery_level() -> u8 {
+    2
+}
+
+fn default_min_wifi_strength() -> u8 {
+    1
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            checks: default_checks(),
+            min_battery_level: default_min_battery_level(),
+            min_wifi_strength: default_min_wifi_strength(),
+        }
+    }
+}
+
+/// # state of the console and sizes needed by the operation
+///
+/// a `None` or zero size skips the check that needs it
+#[derive(Clone, Debug, Default)]
+pub struct PreflightState {
+    pub battery_level: Option<u8>,
+    pub is_charging: bool,
+    pub sd_free: u64,
+    pub sd_required: u64,
+    pub wifi_strength: u8,
+    /// free space of the save archive plus space of the current save
+    pub save_capacity: Option<u64>,
+    pub save_required: u64,
+    /// max number of files of the save archive
+    pub save_max_files: Option<u32>,
+    pub save_required_files: usize,
+}
+
+/// run checks of the config against the state
+pub fn run_checks(
+    config: &PreflightConfig,
+    op: PreflightOp,
+    state: &PreflightState,
+) -> Vec<PreflightIssue> {
+    let mut issues = vec![];
+    let mut issue = |check: PreflightCheck, message: String, is_blocking: bool| {
+        issues.push(PreflightIssue {
+            check,
+            message,
+            is_blocking,
+        })
+    };
+    for &check in config.checks.iter() {
+        match check {
+            PreflightCheck::Battery => match state.battery_level {
+                Some(level) if !state.is_charging && level <= BATTERY_LEVEL_CRITICAL => {
+                    issue(check, t!("preflight.battery_critical").to_string(), true)
+                }
+                Some(level) if !state.is_charging && level <= config.min_battery_level => {
+                    issue(check, t!("preflight.battery_low").to_string(), false)
+                }
+                _ => {}
+            },
+            PreflightCheck::SdSpace => {
+                if state.sd_free < state.sd_required {
+                    issue(
+                        check,
+                        t!(
+                            "preflight.sd_full",
+                            required = format_size(state.sd_required),
+                            free = format_size(state.sd_free)
+                        ),
+                        true,
+                    );
+                } else if state.sd_free < state.sd_required + SD_FREE_MARGIN {
+                    issue(
+                        check,
+                        t!("preflight.sd_low", free = format_size(state.sd_free)),
+                        false,
+                    );
+                }
+            }
+            PreflightCheck::SaveSpace if op == PreflightOp::Restore => {
+                if let Some(capacity) = state.save_capacity {
+                    if state.save_required > capacity {
+                        issue(
+                            check,
+                            t!(
+                                "common.save_space_full",
+                                required = format_size(state.save_required),
+                                capacity = format_size(capacity)
+                            ),
+                            true,
+                        );
+                    }
+                }
+                if let Some(max) = state.save_max_files {
+                    if state.save_required_files > max as usize {
+                        issue(
+                            check,
+                            t!(
+                                "common.save_files_full",
+                                required = state.save_required_files,
+                                capacity = max
+                            ),
+                            true,
+                        );
+                    }
+                }
+            }
+            PreflightCheck::SaveSpace => {}
+            PreflightCheck::Wifi => {
+                if matches!(op, PreflightOp::CloudBackup | PreflightOp::CloudRestore)
+                    && state.wifi_strength < config.min_wifi_strength
+                {
+                    issue(check, t!("preflight.wifi_weak").to_string(), false);
+                }
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    /// a full charged console with enough space
+    fn state() -> PreflightState {
+        PreflightState {
+            battery_level: Some(5),
+            sd_free: 1024 * MB,
+            sd_required: MB,
+            wifi_strength: 3,
+            save_capacity: Some(MB),
+            save_required: MB / 2,
+            save_max_files: Some(10),
+            save_required_files: 2,
+            ..Default::default()
+        }
+    }
+
+    fn checks(op: PreflightOp, state: &PreflightState) -> Vec<(PreflightCheck, bool)> {
+        run_checks(&PreflightConfig::default(), op, state)
+            .into_iter()
+            .map(|issue| (issue.check, issue.is_blocking))
+            .collect()
+    }
+
+    #[test]
+    fn no_issues() {
+        for op in [
+            PreflightOp::Backup,
+            PreflightOp::CloudBackup,
+            PreflightOp::Restore,
+            PreflightOp::CloudRestore,
+        ] {
+            assert!(checks(op, &state()).is_empty(), "{:?}", op);
+        }
+        // unknown battery and save capacity skip the checks
+        let unknown = PreflightState {
+            battery_level: None,
+            save_capacity: None,
+            save_max_files: None,
+            save_required: u64::MAX,
+            save_required_files: usize::MAX,
+            ..state()
+        };
+        assert!(checks(PreflightOp::Restore, &unknown).is_empty());
+    }
+
+    #[test]
+    fn battery() {
+        for (level, is_charging, res) in [
+            (0, false, vec![(PreflightCheck::Battery, true)]),
+            (1, false, vec![(PreflightCheck::Battery, true)]),
+            (2, false, vec![(PreflightCheck::Battery, false)]),
+            (3, false, vec![]),
+            (1, true, vec![]),
+        ] {
+            let state = PreflightState {
+                battery_level: Some(level),
+                is_charging,
+                ..state()
+            };
+            assert_eq!(checks(PreflightOp::Backup, &state), res, "{}", level);
+        }
+    }
+
+    #[test]
+    fn sd_space() {
+        let full = PreflightState {
+            sd_free: MB / 2,
+            ..state()
+        };
+        assert_eq!(
+            checks(PreflightOp::Backup, &full),
+            [(PreflightCheck::SdSpace, true)]
+        );
+        // enough space but less than the margin after the operation
+        let low = PreflightState {
+            sd_free: 2 * MB,
+            ..state()
+        };
+        assert_eq!(
+            checks(PreflightOp::Backup, &low),
+            [(PreflightCheck::SdSpace, false)]
+        );
+    }
+
+    #[test]
+    fn save_space() {
+        let full = PreflightState {
+            save_required: 2 * MB,
+            save_required_files: 11,
+            ..state()
+        };
+        assert_eq!(
+            checks(PreflightOp::Restore, &full),
+            [
+                (PreflightCheck::SaveSpace, true),
+                (PreflightCheck::SaveSpace, true)
+            ]
+        );
+        // only restore writes the save archive
+        assert!(checks(PreflightOp::Backup, &full).is_empty());
+        // capacity of extdata is unknown
+        let ext = PreflightState {
+            save_capacity: None,
+            save_max_files: None,
+            ..full
+        };
+        assert!(checks(PreflightOp::Restore, &ext).is_empty());
+    }
+
+    #[test]
+    fn wifi() {
+        let weak = PreflightState {
+            wifi_strength: 0,
+            ..state()
+        };
+        for (op, res) in [
+            (PreflightOp::Backup, vec![]),
+            (PreflightOp::Restore, vec![]),
+            (
+                PreflightOp::CloudBackup,
+                vec![(PreflightCheck::Wifi, false)],
+            ),
+            (
+                PreflightOp::CloudRestore,
+                vec![(PreflightCheck::Wifi, false)],
+            ),
+        ] {
+            assert_eq!(checks(op, &weak), res, "{:?}", op);
+        }
+    }
+
+    #[test]
+    fn only_configured_checks() {
+        let state = PreflightState {
+            battery_level: Some(0),
+            sd_free: 0,
+            wifi_strength: 0,
+            ..state()
+        };
+        let config = PreflightConfig {
+            checks: vec![PreflightCheck::Wifi],
+            ..Default::default()
+        };
+        let issues = run_checks(&config, PreflightOp::CloudBackup, &state);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].check, PreflightCheck::Wifi);
+        assert!(!issues[0].is_blocking);
+        assert!(!issues[0].message.is_empty());
+        let config = PreflightConfig {
+            checks: vec![],
+            ..Default::default()
+        };
+        assert!(run_checks(&config, PreflightOp::CloudBackup, &state).is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{http::http_transfer_progress, t};
+
+/// progress of the running operation, there is only one at a time
+static PROGRESS: Mutex<Option<ProgressTracker>> = Mutex::new(None);
+/// (index, count) of the batch, index starts from 1
+static BATCH: Mutex<Option<(usize, usize)>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgressStage {
+    Compress,
+    Extract,
+    Encrypt,
+    Decrypt,
+    Upload,
+    Download,
+}
+
+impl ProgressStage {
+    /// bytes of network stages are read from the running transfer of curl
+    pub fn is_network(&self) -> bool {
+        matches!(self, ProgressStage::Upload | ProgressStage::Download)
+    }
+}
+
+impl Display for ProgressStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProgressStage::Compress => t!("progress.compress"),
+            ProgressStage::Extract => t!("progress.extract"),
+            ProgressStage::Encrypt => t!("progress.encrypt"),
+            ProgressStage::Decrypt => t!("progress.decrypt"),
+            ProgressStage::Upload => t!("progress.upload"),
+            ProgressStage::Download => t!("progress.download"),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// # a snapshot of the progress
+///
+/// a zero `count` or `bytes_total` means unknown
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressEvent {
+    pub stage: ProgressStage,
+    /// current item, starts from 1, 0 if no item started
+    pub item: usize,
+    pub count: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// bytes per second
+    pub throughput: f64,
+    pub eta: Option<Duration>,
+    /// (index, count) of the batch
+    pub batch: Option<(usize, usize)>,
+}
+
+impl ProgressEvent {
+    /// 0 to 100, `None` if the total is unknown
+    pub fn percent(&self) -> Option<f64> {
+        if self.bytes_total > 0 {
+            Some((self.bytes_done as f64 / self.bytes_total as f64 * 100.0).min(100.0))
+        } else if self.count > 0 {
+            Some((self.item.saturating_sub(1) as f64 / self.count as f64 * 100.0).min(100.0))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProgressTracker {
+    stage: ProgressStage,
+    started: Instant,
+    item: usize,
+    count: usize,
+    bytes_done: u64,
+    bytes_total: u64,
+}
+
+impl ProgressTracker {
+    pub fn new(stage: ProgressStage, count: usize, bytes_total: u64) -> Self {
+        Self {
+            stage,
+            started: Instant::now(),
+            item: 0,
+            count,
+            bytes_done: 0,
+            bytes_total,
+        }
+    }
+
+    pub fn next_item(&mut self) {
+        self.item += 1;
+    }
+
+    pub fn add_bytes(&mut self, size: u64) {
+        self.bytes_done += size;
+    }
+
+    pub fn set_bytes(&mut self, done: u64, total: u64) {
+        self.bytes_done = done;
+        self.bytes_total = total;
+    }
+
+    /// event after `elapsed` since the start, throughput is the average of the whole stage
+    pub fn event_at(&self, elapsed: Duration, batch: Option<(usize, usize)>) -> ProgressEvent {
+        let secs = elapsed.as_secs_f64();
+        let throughput = if secs > 0.0 {
+            self.bytes_done as f64 / secs
+        } else {
+            0.0
+        };
+        let eta = if throughput > 0.0 && self.bytes_total > 0 {
+            Some(Duration::from_secs_f64(
+                self.bytes_total.saturating_sub(self.bytes_done) as f64 / throughput,
+            ))
+        } else {
+            None
+        };
+        ProgressEvent {
+            stage: self.stage,
+            item: self.item,
+            count: self.count,
+            bytes_done: self.bytes_done,
+            bytes_total: self.bytes_total,
+            throughput,
+            eta,
+            batch,
+        }
+    }
+}
+
+fn with_tracker(f: impl FnOnce(&mut ProgressTracker)) {
+    if let Some(tracker) = PROGRESS.lock().unwrap().as_mut() {
+        f(tracker);
+    }
+}
+
+/// start a stage, the previous stage is replaced
+pub fn start(stage: ProgressStage, count: usize, bytes_total: u64) {
+    PROGRESS
+        .lock()
+        .unwrap()
+        .replace(ProgressTracker::new(stage, count, bytes_total));
+}
+
+pub fn next_item() {
+    with_tracker(|tracker| tracker.next_item());
+}
+
+/// bytes copied by `copy_buf`, ignored if no stage started
+pub fn add_bytes(size: u64) {
+    with_tracker(|tracker| tracker.add_bytes(size));
+}
+
+/// end the stage, the batch is kept
+pub fn finish() {
+    PROGRESS.lock().unwrap().take();
+}
+
+/// set `(index, count)` of the batch, index starts from 1
+pub fn set_batch(batch: Option<(usize, usize)>) {
+    *BATCH.lock().unwrap() = batch;
+}
+
+/// clear the stage and the batch, called when the loading is shown or hidden
+pub fn clear() {
+    finish();
+    set_batch(None);
+}
+
+/// event of the running stage
+pub fn current() -> Option<ProgressEvent> {
+    let batch = *BATCH.lock().unwrap();
+    let mut progress = PROGRESS.lock().unwrap();
+    let tracker = progress.as_mut()?;
+    if tracker.stage.is_network() {
+        let (done, total) = http_transfer_progress();
+        tracker.set_bytes(done, total);
+    }
+    Some(tracker.event_at(tracker.started.elapsed(), batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(count: usize, item: usize, bytes_total: u64, bytes_done: u64) -> ProgressEvent {
+        let mut tracker = ProgressTracker::new(ProgressStage::Compress, count, bytes_total);
+        for _ in 0..item {
+            tracker.next_item();
+        }
+        tracker.add_bytes(bytes_done);
+        tracker.event_at(Duration::from_secs(1), None)
+    }
+
+    #[test]
+    fn percent() {
+        for (count, item, bytes_total, bytes_done, percent) in [
+            // by bytes if the total is known
+            (4, 1, 200, 50, Some(25.0)),
+            (0, 0, 200, 200, Some(100.0)),
+            (0, 0, 200, 300, Some(100.0)),
+            // by finished items otherwise
+            (4, 0, 0, 10, Some(0.0)),
+            (4, 3, 0, 10, Some(50.0)),
+            (4, 5, 0, 10, Some(100.0)),
+            (0, 3, 0, 10, None),
+        ] {
+            assert_eq!(
+                event(count, item, bytes_total, bytes_done).percent(),
+                percent,
+                "{} {} {} {}",
+                count,
+                item,
+                bytes_total,
+                bytes_done
+            );
+        }
+    }
+
+    #[test]
+    fn rate_and_eta() {
+        let mut tracker = ProgressTracker::new(ProgressStage::Upload, 1, 100);
+        // nothing is known right after the start
+        let event = tracker.event_at(Duration::ZERO, Some((1, 2)));
+        assert_eq!((event.throughput, event.eta), (0.0, None));
+        assert_eq!(event.batch, Some((1, 2)));
+        let event = tracker.event_at(Duration::from_secs(1), None);
+        assert_eq!((event.throughput, event.eta), (0.0, None));
+
+        tracker.add_bytes(50);
+        let event = tracker.event_at(Duration::from_secs(1), None);
+        assert_eq!(event.throughput, 50.0);
+        assert_eq!(event.eta, Some(Duration::from_secs(1)));
+
+        // the average drops while the transfer stalls, the eta grows
+        let event = tracker.event_at(Duration::from_secs(5), None);
+        assert_eq!(event.throughput, 10.0);
+        assert_eq!(event.eta, Some(Duration::from_secs(5)));
+
+        tracker.set_bytes(100, 100);
+        let event = tracker.event_at(Duration::from_secs(10), None);
+        assert_eq!(event.eta, Some(Duration::ZERO));
+        assert_eq!(event.percent(), Some(100.0));
+    }
+
+    #[test]
+    fn eta_of_unknown_total() {
+        let mut tracker = ProgressTracker::new(ProgressStage::Extract, 0, 0);
+        tracker.add_bytes(100);
+        let event = tracker.event_at(Duration::from_secs(2), None);
+        assert_eq!(event.throughput, 50.0);
+        assert_eq!(event.eta, None);
+        assert_eq!(event.percent(), None);
+    }
+}
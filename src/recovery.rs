@@ -0,0 +1,93 @@
+use std::{error::Error, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constant::HOME_LOCAL_PATH_CACHE,
+    utils::{create_parent_if_not_exists, join_path},
+};
+
+/// storage sizes of the local and the cloud panel, `None` if not read yet
+pub type StorageInfo = (Option<(f64, f64)>, Option<(f64, f64)>);
+
+/// # state of the cloud panels, kept when the app exits to install a title
+///
+/// lists are saved as json of their items
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryData {
+    pub local_list: String,
+    pub local_list_right: String,
+    pub cloud_list: String,
+    pub storage_info: StorageInfo,
+}
+
+impl RecoveryData {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        create_parent_if_not_exists(path).ok();
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub fn recovery_data_path() -> String {
+    join_path(HOME_LOCAL_PATH_CACHE, "recovery")
+}
+
+pub fn remove_recovery_data() {
+    let path = recovery_data_path();
+    if Path::new(&path).exists() {
+        fs::remove_file(&path).ok();
+    }
+}
+
+/// recovery data is used only once, it is removed after read
+pub fn get_recovery_data() -> Result<RecoveryData, Box<dyn Error>> {
+    let path = recovery_data_path();
+    if !Path::new(&path).exists() {
+        return Err("recovery data not found".into());
+    }
+    let data = RecoveryData::load(&path)?;
+    remove_recovery_data();
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let path = env::temp_dir().join(format!("save-cloud-recovery-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        RecoveryData {
+            local_list: r#"[{"path":"/a"}]"#.to_string(),
+            local_list_right: "[]".to_string(),
+            cloud_list: "[]".to_string(),
+            storage_info: (Some((1024.0, 4096.0)), None),
+        }
+        .save(path)
+        .unwrap();
+        let data = RecoveryData::load(path).unwrap();
+        fs::remove_file(path).ok();
+        assert_eq!(data.local_list, r#"[{"path":"/a"}]"#);
+        assert_eq!(data.local_list_right, "[]");
+        assert_eq!(data.cloud_list, "[]");
+        assert_eq!(data.storage_info, (Some((1024.0, 4096.0)), None));
+    }
+
+    #[test]
+    fn load_broken() {
+        let path =
+            env::temp_dir().join(format!("save-cloud-recovery-broken-{}", std::process::id()));
+        fs::write(&path, r#"{"local_list":"[]"}"#).unwrap();
+        assert!(RecoveryData::load(&path).is_err());
+        fs::remove_file(&path).ok();
+        assert!(RecoveryData::load(&path).is_err());
+    }
+}
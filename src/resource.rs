@@ -4,9 +4,12 @@ use ctru::{
     prelude::*,
     services::{am::Am, fs::MediaType},
 };
+use log::warn;
 
 use crate::{
     c2d::C2D,
+    config,
+    constant::CONFIG_PATH,
     http::HttpContext,
     platform::{enable_hight_performance_for_new_3ds, is_new_3ds, setup_log_redirect},
 };
@@ -30,6 +33,14 @@ impl TitleInfo {
         }
     }
 
+    pub fn media_name(&self) -> &'static str {
+        match self.fs_media_type {
+            MediaType::Sd => "sd",
+            MediaType::GameCard => "card",
+            _ => "nand",
+        }
+    }
+
     pub fn high_id(&self) -> u32 {
         (self.id >> 32) as u32
     }
@@ -149,6 +160,10 @@ impl Resource {
         let c2d = Rc::new(C2D::new()?);
         // http init
         let http = HttpContext::new();
+        // config init, invalid values are reset to defaults
+        for issue in config::init(CONFIG_PATH) {
+            warn!("{}", issue);
+        }
 
         Ok(Rc::new(Self {
             soc,
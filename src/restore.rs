@@ -0,0 +1,270 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+use crate::t;
+
+/// # target of a save restore
+///
+/// the steps of a restore, split out so the rollback logic does not depend on
+/// the 3DS filesystem
+pub trait RestoreTarget {
+    /// back up the current save to `snapshot`, `Ok(false)` if the save is empty
+    fn snapshot(&mut self, snapshot: &str) -> Result<bool, Box<dyn Error>>;
+    /// remove all files and dirs of the save
+    fn clear(&mut self) -> Result<(), Box<dyn Error>>;
+    /// extract the backup zip into the save
+    fn extract(&mut self, backup: &str) -> Result<(), Box<dyn Error>>;
+    /// make the written data persistent
+    fn commit(&mut self) -> Result<(), Box<dyn Error>>;
+    /// called before the snapshot is extracted back, e.g. when cancelled
+    fn begin_rollback(&mut self) {}
+}
+
+pub enum RestoreError {
+    /// failed before the save was touched
+    Aborted(Box<dyn Error>),
+    /// restore failed, the save was rolled back to the snapshot
+    RolledBack(Box<dyn Error>),
+    /// restore failed, and the rollback failed too
+    RollbackFailed(Box<dyn Error>, Box<dyn Error>),
+}
+
+impl Display for RestoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreError::Aborted(err) => write!(f, "{}", err),
+            RestoreError::RolledBack(err) => write!(f, "{}", t!("restore.rolled_back", err = err)),
+            RestoreError::RollbackFailed(err, rollback_err) => {
+                write!(
+                    f,
+                    "{}",
+                    t!(
+                        "restore.rollback_failed",
+                        err = err,
+                        rollback_err = rollback_err
+                    )
+                )
+            }
+        }
+    }
+}
+
+impl Debug for RestoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Error for RestoreError {}
+
+/// # restore `backup` into `target` as a transaction
+///
+/// snapshot -> clear -> extract -> commit, if any step after the snapshot
+/// fails, the snapshot is extracted back into the save
+pub fn restore_transaction(
+    target: &mut impl RestoreTarget,
+    backup: &str,
+    snapshot: &str,
+) -> Result<(), RestoreError> {
+    let has_snapshot = target
+        .snapshot(snapshot)
+        .map_err(|err| RestoreError::Aborted(t!("restore.snapshot_failed", err = err).into()))?;
+
+    let res = target
+        .clear()
+        .and_then(|_| target.extract(backup))
+        .and_then(|_| target.commit());
+
+    match res {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            target.begin_rollback();
+            let rollback = target
+                .clear()
+                .and_then(|_| {
+                    if has_snapshot {
+                        target.extract(snapshot)
+                    } else {
+                        Ok(())
+                    }
+                })
+                .and_then(|_| target.commit());
+            match rollback {
+                Ok(_) => Err(RestoreError::RolledBack(err)),
+                Err(rollback_err) => Err(RestoreError::RollbackFailed(err, rollback_err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use super::*;
+    use crate::vfs::{MemFs, Vfs};
+
+    /// target on a `MemFs`, backups are files by name, steps fail on demand
+    #[derive(Default)]
+    struct MemTarget {
+        save: MemFs,
+        backups: HashMap<String, Vec<(String, Vec<u8>)>>,
+        fail_snapshot: bool,
+        /// extract of these backups fails after the first file is written
+        fail_extract: Vec<&'static str>,
+        /// the next commit fails
+        fail_commit: bool,
+        rollbacks: usize,
+    }
+
+    impl MemTarget {
+        fn new(save: &[(&str, &str)], backup: &[(&str, &str)]) -> Self {
+            let mut target = MemTarget::default();
+            for (path, data) in save {
+                target.save.write(path, data.as_bytes());
+            }
+            target.backups.insert(
+                "backup".to_string(),
+                backup
+                    .iter()
+                    .map(|(path, data)| (path.to_string(), data.as_bytes().to_vec()))
+                    .collect(),
+            );
+            target
+        }
+
+        fn files(&self) -> Vec<(String, String)> {
+            let mut files = self
+                .save
+                .read_dir(Path::new("/"))
+                .unwrap()
+                .into_iter()
+                .map(|entry| {
+                    let path = entry.path();
+                    let data = self.save.read(&path).unwrap();
+                    (
+                        path.to_string_lossy().to_string(),
+                        String::from_utf8(data).unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        }
+    }
+
+    impl RestoreTarget for MemTarget {
+        fn snapshot(&mut self, snapshot: &str) -> Result<bool, Box<dyn Error>> {
+            if self.fail_snapshot {
+                return Err("snapshot failed".into());
+            }
+            let files = self
+                .files()
+                .into_iter()
+                .map(|(path, data)| (path, data.into_bytes()))
+                .collect::<Vec<_>>();
+            let has_files = !files.is_empty();
+            self.backups.insert(snapshot.to_string(), files);
+            Ok(has_files)
+        }
+
+        fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+            for entry in self.save.read_dir(Path::new("/"))? {
+                self.save.remove_file(&entry.path())?;
+            }
+            Ok(())
+        }
+
+        fn extract(&mut self, backup: &str) -> Result<(), Box<dyn Error>> {
+            let files = self.backups.get(backup).ok_or("no backup")?;
+            for (path, data) in files {
+                self.save.write(path, data.clone());
+                if self.fail_extract.contains(&backup) {
+                    return Err("extract failed".into());
+                }
+            }
+            Ok(())
+        }
+
+        fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+            if self.fail_commit {
+                self.fail_commit = false;
+                return Err("commit failed".into());
+            }
+            Ok(self.save.commit()?)
+        }
+
+        fn begin_rollback(&mut self) {
+            self.rollbacks += 1;
+        }
+    }
+
+    fn files(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(path, data)| (path.to_string(), data.to_string()))
+            .collect()
+    }
+
+    const SAVE: [(&str, &str); 2] = [("/a.bin", "old a"), ("/b.bin", "old b")];
+    const BACKUP: [(&str, &str); 2] = [("/a.bin", "new a"), ("/c.bin", "new c")];
+
+    #[test]
+    fn restore_replaces_the_save() {
+        let mut target = MemTarget::new(&SAVE, &BACKUP);
+        assert!(restore_transaction(&mut target, "backup", "snapshot").is_ok());
+        assert_eq!(target.files(), files(&BACKUP));
+        assert_eq!(target.save.commits(), 1);
+        assert_eq!(target.rollbacks, 0);
+    }
+
+    #[test]
+    fn failed_extract_rolls_back() {
+        let mut target = MemTarget::new(&SAVE, &BACKUP);
+        target.fail_extract = vec!["backup"];
+        let res = restore_transaction(&mut target, "backup", "snapshot");
+        assert!(matches!(res, Err(RestoreError::RolledBack(_))));
+        assert_eq!(target.files(), files(&SAVE));
+        assert_eq!(target.save.commits(), 1);
+        assert_eq!(target.rollbacks, 1);
+    }
+
+    #[test]
+    fn failed_commit_rolls_back() {
+        let mut target = MemTarget::new(&SAVE, &BACKUP);
+        target.fail_commit = true;
+        let res = restore_transaction(&mut target, "backup", "snapshot");
+        assert!(matches!(res, Err(RestoreError::RolledBack(_))));
+        assert_eq!(target.files(), files(&SAVE));
+        assert_eq!(target.save.commits(), 1);
+    }
+
+    #[test]
+    fn empty_save_is_cleared_on_rollback() {
+        let mut target = MemTarget::new(&[], &BACKUP);
+        target.fail_extract = vec!["backup"];
+        let res = restore_transaction(&mut target, "backup", "snapshot");
+        assert!(matches!(res, Err(RestoreError::RolledBack(_))));
+        assert!(target.files().is_empty());
+    }
+
+    #[test]
+    fn failed_rollback_is_reported() {
+        let mut target = MemTarget::new(&SAVE, &BACKUP);
+        target.fail_extract = vec!["backup", "snapshot"];
+        let res = restore_transaction(&mut target, "backup", "snapshot");
+        assert!(matches!(res, Err(RestoreError::RollbackFailed(_, _))));
+        assert_eq!(target.save.commits(), 0);
+    }
+
+    #[test]
+    fn failed_snapshot_leaves_the_save() {
+        let mut target = MemTarget::new(&SAVE, &BACKUP);
+        target.fail_snapshot = true;
+        let res = restore_transaction(&mut target, "backup", "snapshot");
+        assert!(matches!(res, Err(RestoreError::Aborted(_))));
+        assert_eq!(target.files(), files(&SAVE));
+        assert_eq!(target.save.commits(), 0);
+    }
+}
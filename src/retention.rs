@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+const SECS_PER_DAY: i64 = 60 * 60 * 24;
+
+/// # retention rule of backups
+///
+/// a backup is kept if any of the rules keeps it, a rule of `None` is
+/// disabled, backups are never pruned if all rules are disabled
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionRule {
+    /// keep the newest N backups
+    #[serde(default)]
+    pub keep_last: Option<usize>,
+    /// keep the newest backup of each day in the last M weeks
+    #[serde(default)]
+    pub keep_daily_weeks: Option<u32>,
+    /// keep the newest backup of each week in the last M weeks
+    #[serde(default)]
+    pub keep_weekly_weeks: Option<u32>,
+    /// keep auto backups made before restore for D days
+    #[serde(default)]
+    pub keep_auto_days: Option<u32>,
+}
+
+impl RetentionRule {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none()
+            && self.keep_daily_weeks.is_none()
+            && self.keep_weekly_weeks.is_none()
+            && self.keep_auto_days.is_none()
+    }
+}
+
+/// # retention config, part of the app config
+///
+/// the global rule is flattened, so the fields of it are at the top level
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(flatten)]
+    pub global: RetentionRule,
+    /// rules by title id, e.g. `0x4000000055D00`, used instead of the global rule
+    #[serde(default)]
+    pub titles: HashMap<String, RetentionRule>,
+    /// protected backups, see `protect_key`
+    #[serde(default)]
+    pub protected: HashSet<String>,
+}
+
+impl RetentionConfig {
+    pub fn rule(&self, title_id: &str) -> &RetentionRule {
+        self.titles.get(title_id).unwrap_or(&self.global)
+    }
+
+    /// `<local|cloud>/<title id>/<save type>/<backup name>`
+    pub fn protect_key(is_cloud: bool, title_id: &str, save_type: &str, name: &str) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            if is_cloud { "cloud" } else { "local" },
+            title_id,
+            save_type,
+            name
+        )
+    }
+
+    pub fn is_protected(&self, key: &str) -> bool {
+        self.protected.contains(key)
+    }
+
+    /// returns whether the backup is protected after toggle
+    pub fn toggle_protected(&mut self, key: String) -> bool {
+        if self.protected.remove(&key) {
+            false
+        } else {
+            self.protected.insert(key);
+            true
+        }
+    }
+}
+
+/// auto backups are made by restore, named `<time> auto.zip`
+pub fn is_auto_backup(name: &str) -> bool {
+    name.ends_with(" auto.zip")
+}
+
+/// parse time of backup name `YYYY-MM-DD HH.MM.SS...`, the format of
+/// `get_current_format_time`, returns seconds since 1970-01-01 local time
+pub fn parse_backup_time(name: &str) -> Option<i64> {
+    let bytes = name.as_bytes();
+    if bytes.len() < 19 {
+        return None;
+    }
+    for (idx, sep) in [(4, b'-'), (7, b'-'), (10, b' '), (13, b'.'), (16, b'.')] {
+        if bytes[idx] != sep {
+            return None;
+        }
+    }
+    let num = |from: usize, to: usize| -> Option<i64> {
+        let s = name.get(from..to)?;
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+    let (year, month, day) = (num(0, 4)?, num(5, 7)?, num(8, 10)?);
+    let (hour, min, sec) = (num(11, 13)?, num(14, 16)?, num(17, 19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + min * 60 + sec)
+}
+
+/// days since 1970-01-01
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// # select backups to prune
+///
+/// `now` is in the same unit as `parse_backup_time`. backups with a name that
+/// has no time and protected backups are always kept, auto backups only
+/// follow `keep_auto_days`
+pub fn select_prune(
+    names: &[String],
+    rule: &RetentionRule,
+    now: i64,
+    is_protected: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut prune = vec![];
+    let mut backups = vec![];
+    for name in names {
+        if is_protected(name) {
+            continue;
+        }
+        if let Some(time) = parse_backup_time(name) {
+            if is_auto_backup(name) {
+                if let Some(days) = rule.keep_auto_days {
+                    if now - time > days as i64 * SECS_PER_DAY {
+                        prune.push(name.clone());
+                    }
+                }
+            } else {
+                backups.push((time, name));
+            }
+        }
+    }
+
+    if rule.keep_last.is_none()
+        && rule.keep_daily_weeks.is_none()
+        && rule.keep_weekly_weeks.is_none()
+    {
+        return prune;
+    }
+
+    // newest first
+    backups.sort_by(|a, b| b.cmp(a));
+    let today = now.div_euclid(SECS_PER_DAY);
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (idx, (time, name)) in backups.into_iter().enumerate() {
+        let day = time.div_euclid(SECS_PER_DAY);
+        // 1970-01-01 is thursday, weeks start on monday
+        let week = (day + 3).div_euclid(7);
+        let mut keep = rule.keep_last.is_some_and(|n| idx < n);
+        if rule
+            .keep_daily_weeks
+            .is_some_and(|w| today - day < w as i64 * 7)
+            && days.insert(day)
+        {
+            keep = true;
+        }
+        if rule
+            .keep_weekly_weeks
+            .is_some_and(|w| today - day < w as i64 * 7)
+            && weeks.insert(week)
+        {
+            keep = true;
+        }
+        if !keep {
+            prune.push(name.clone());
+        }
+    }
+
+    prune
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-15 12.00.00
+    const NOW: i64 = 1710504000;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn prune(list: &[&str], rule: &RetentionRule) -> Vec<String> {
+        let mut prune = select_prune(&names(list), rule, NOW, |_| false);
+        prune.sort();
+        prune
+    }
+
+    #[test]
+    fn parse_time() {
+        assert_eq!(parse_backup_time("1970-01-01 00.00.00.zip"), Some(0));
+        assert_eq!(parse_backup_time("2024-03-15 12.00.00"), Some(NOW));
+        assert_eq!(
+            parse_backup_time("2024-02-29 23.59.59 auto.zip"),
+            Some(1709251199)
+        );
+        assert_eq!(parse_backup_time("2024-03-15 12.00.0"), None);
+        assert_eq!(parse_backup_time("2024/03/15 12.00.00.zip"), None);
+        assert_eq!(parse_backup_time("2024-13-15 12.00.00.zip"), None);
+        assert_eq!(parse_backup_time("2024-03-15 24.00.00.zip"), None);
+        assert_eq!(parse_backup_time("2024-03-15 1a.00.00.zip"), None);
+        assert_eq!(parse_backup_time("my backup.zip"), None);
+        assert_eq!(parse_backup_time("二〇二四-03-15 12.00.00.zip"), None);
+    }
+
+    #[test]
+    fn keep_last() {
+        let rule = RetentionRule {
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        let list = [
+            "2024-03-12 10.00.00.zip",
+            "2024-03-15 10.00.00.zip",
+            "2024-03-13 10.00.00.snap",
+            "2024-03-14 10.00.00.zip",
+        ];
+        assert_eq!(
+            prune(&list, &rule),
+            names(&["2024-03-12 10.00.00.zip", "2024-03-13 10.00.00.snap"])
+        );
+        // exactly N backups are all kept
+        assert!(prune(&list[1..3], &rule).is_empty());
+    }
+
+    #[test]
+    fn keep_auto_days() {
+        let rule = RetentionRule {
+            keep_auto_days: Some(3),
+            ..Default::default()
+        };
+        let list = [
+            // exactly 3 days old
+            "2024-03-12 12.00.00 auto.zip",
+            "2024-03-12 11.59.59 auto.zip",
+            "2024-03-01 12.00.00.zip",
+        ];
+        assert_eq!(
+            prune(&list, &rule),
+            names(&["2024-03-12 11.59.59 auto.zip"])
+        );
+    }
+
+    #[test]
+    fn keep_daily_weeks() {
+        let rule = RetentionRule {
+            keep_daily_weeks: Some(1),
+            ..Default::default()
+        };
+        let list = [
+            "2024-03-15 09.00.00.zip",
+            "2024-03-15 08.00.00.zip",
+            // 6 days ago is in the week, 7 days ago is not
+            "2024-03-09 08.00.00.zip",
+            "2024-03-08 23.00.00.zip",
+        ];
+        assert_eq!(
+            prune(&list, &rule),
+            names(&["2024-03-08 23.00.00.zip", "2024-03-15 08.00.00.zip"])
+        );
+    }
+
+    #[test]
+    fn keep_weekly_weeks() {
+        let rule = RetentionRule {
+            keep_weekly_weeks: Some(2),
+            ..Default::default()
+        };
+        let list = [
+            // friday and monday of this week
+            "2024-03-15 08.00.00.zip",
+            "2024-03-11 08.00.00.zip",
+            // sunday of the last week
+            "2024-03-10 08.00.00.zip",
+            "2024-03-01 08.00.00.zip",
+        ];
+        assert_eq!(
+            prune(&list, &rule),
+            names(&["2024-03-01 08.00.00.zip", "2024-03-11 08.00.00.zip"])
+        );
+    }
+
+    #[test]
+    fn unparsed_and_protected_are_kept() {
+        let rule = RetentionRule {
+            keep_last: Some(0),
+            keep_auto_days: Some(0),
+            ..Default::default()
+        };
+        let list = names(&[
+            "my backup.zip",
+            "before update auto.zip",
+            "2024-03-01 08.00.00.zip",
+            "2024-03-02 08.00.00.zip",
+            "2024-03-03 08.00.00 auto.zip",
+        ]);
+        let prune = select_prune(&list, &rule, NOW, |name| name.starts_with("2024-03-02"));
+        assert_eq!(
+            prune,
+            names(&["2024-03-03 08.00.00 auto.zip", "2024-03-01 08.00.00.zip"])
+        );
+    }
+
+    #[test]
+    fn empty_rule_prunes_nothing() {
+        let list = ["2000-01-01 00.00.00.zip", "2000-01-01 00.00.00 auto.zip"];
+        assert!(prune(&list, &RetentionRule::default()).is_empty());
+    }
+}
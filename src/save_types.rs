@@ -0,0 +1,123 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read, Write},
+};
+
+/// magic and version of the save types cache
+const SAVE_TYPES_CACHE_MAGIC: &[u8; 4] = b"SCST";
+const SAVE_TYPES_CACHE_VERSION: u8 = 1;
+
+/// (title id, media type) -> save types bitmask, `1` user, `2` ext, `4` sys, `8` boss
+pub type SaveTypesCache = HashMap<(u64, u8), u8>;
+
+/// # read the save types cache
+///
+/// header: magic, version, count (u32 be), then entries of title id (u64 be),
+/// media type and save types bitmask. `None` if the cache is of other
+/// versions or broken
+pub fn read_cache(mut reader: impl Read) -> Option<SaveTypesCache> {
+    let mut header = [0u8; 9];
+    reader.read_exact(&mut header).ok()?;
+    if &header[..4] != SAVE_TYPES_CACHE_MAGIC || header[4] != SAVE_TYPES_CACHE_VERSION {
+        return None;
+    }
+    let count = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+    let mut res = HashMap::new();
+    for _ in 0..count {
+        let mut entry = [0u8; 10];
+        reader.read_exact(&mut entry).ok()?;
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&entry[..8]);
+        res.insert((u64::from_be_bytes(id), entry[8]), entry[9]);
+    }
+    Some(res)
+}
+
+pub fn write_cache(cache: &SaveTypesCache, mut writer: impl Write) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(9 + cache.len() * 10);
+    buf.extend_from_slice(SAVE_TYPES_CACHE_MAGIC);
+    buf.push(SAVE_TYPES_CACHE_VERSION);
+    buf.extend_from_slice(&(cache.len() as u32).to_be_bytes());
+    for (&(id, media), &save_types) in cache.iter() {
+        buf.extend_from_slice(&id.to_be_bytes());
+        buf.push(media);
+        buf.push(save_types);
+    }
+    writer.write_all(&buf)
+}
+
+/// # match the cache to the installed titles
+///
+/// entries of removed titles are dropped, returns the titles that are not in
+/// the cache yet, only they have to be probed
+pub fn sync_titles(cache: &mut SaveTypesCache, ids: &[(u64, u8)]) -> Vec<(u64, u8)> {
+    let installed = ids.iter().copied().collect::<HashSet<_>>();
+    cache.retain(|key, _| installed.contains(key));
+    ids.iter()
+        .filter(|key| !cache.contains_key(key))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> SaveTypesCache {
+        HashMap::from([
+            ((0x0004000000055D00, 1), 0b0011),
+            ((0x0004001000021700, 2), 0b1100),
+        ])
+    }
+
+    fn bytes(cache: &SaveTypesCache) -> Vec<u8> {
+        let mut buf = vec![];
+        write_cache(cache, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn roundtrip() {
+        let cache = cache();
+        assert_eq!(read_cache(bytes(&cache).as_slice()), Some(cache));
+        let empty = SaveTypesCache::new();
+        assert_eq!(read_cache(bytes(&empty).as_slice()), Some(empty));
+    }
+
+    #[test]
+    fn other_magic_or_version() {
+        let mut data = bytes(&cache());
+        data[0] = b'X';
+        assert_eq!(read_cache(data.as_slice()), None);
+
+        let mut data = bytes(&cache());
+        data[4] = SAVE_TYPES_CACHE_VERSION + 1;
+        assert_eq!(read_cache(data.as_slice()), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = bytes(&cache());
+        for len in [0, 4, 8, 9 + 10, data.len() - 1] {
+            assert_eq!(read_cache(&data[..len]), None, "{}", len);
+        }
+    }
+
+    #[test]
+    fn only_added_titles_are_probed() {
+        let mut cache = cache();
+        let added = sync_titles(
+            &mut cache,
+            &[(0x0004000000055D00, 1), (0x0004000000055E00, 1)],
+        );
+        assert_eq!(added, [(0x0004000000055E00, 1)]);
+        // the removed title is dropped, the kept one is not probed again
+        assert_eq!(cache, HashMap::from([((0x0004000000055D00, 1), 0b0011)]));
+        // same title on another media is another entry
+        assert_eq!(
+            sync_titles(&mut cache, &[(0x0004000000055D00, 2)]),
+            [(0x0004000000055D00, 2)]
+        );
+        assert!(cache.is_empty());
+    }
+}
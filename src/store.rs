@@ -0,0 +1,434 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::ZipWriter;
+
+use crate::{
+    config,
+    constant::{SNAPSHOT_EXT, STORE_CHUNK_SIZE},
+    manifest::{BackupManifest, ManifestFile},
+    t,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    #[serde(flatten)]
+    pub file: ManifestFile,
+    /// md5 of the chunks of the file, in order
+    pub chunks: Vec<String>,
+}
+
+/// # snapshot index of a deduplicated backup
+///
+/// only the index is written per backup, the file data lives in the
+/// `ObjectStore` and is shared by all snapshots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// `files` of the manifest is filled when the snapshot is exported
+    pub manifest: BackupManifest,
+    /// dirs inside the save archive, `/` separated, without leading `/`
+    #[serde(default)]
+    pub dirs: Vec<String>,
+    pub files: Vec<SnapshotFile>,
+}
+
+impl Snapshot {
+    pub fn new(manifest: BackupManifest) -> Self {
+        Self {
+            manifest,
+            dirs: vec![],
+            files: vec![],
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// write to a temp file first, an existing snapshot is only replaced when done
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &String> {
+        self.files.iter().flat_map(|f| f.chunks.iter())
+    }
+
+    /// manifest with the files of the snapshot
+    pub fn full_manifest(&self) -> BackupManifest {
+        let mut manifest = self.manifest.clone();
+        manifest.files = self.files.iter().map(|f| f.file.clone()).collect();
+        manifest
+    }
+
+    /// # export as a plain backup zip
+    ///
+    /// same layout as the zips made by `backup_game_save`, manifest included
+    pub fn export_zip<W: Write + Seek>(
+        &self,
+        store: &ObjectStore,
+        writer: W,
+        mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+    ) -> Result<(), Box<dyn Error>> {
+        let options = config::get().backup.file_options();
+        let mut zip = ZipWriter::new(writer);
+        for dir in self.dirs.iter() {
+            zip.add_directory(dir.as_str(), options)?;
+        }
+        for file in self.files.iter() {
+            notify(None, Some(file.file.path.clone()));
+            zip.start_file(file.file.path.as_str(), options)?;
+            store.read_file(file, &mut zip)?;
+        }
+        self.full_manifest().write_to(&mut zip)?;
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// dirs that have snapshots of the store, a json list in the root of the store
+const SNAPSHOT_DIRS_NAME: &str = "dirs.json";
+
+/// # content-addressed chunk store
+///
+/// chunks are saved as `objects/<md5 prefix>/<md5>` under `root`, a chunk
+/// already in the store is never written again
+pub struct ObjectStore {
+    root: PathBuf,
+}
+
+impl ObjectStore {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.objects_dir().join(&hash[..2]).join(hash)
+    }
+
+    /// save chunk, returns md5 of it
+    pub fn put(&self, data: &[u8]) -> io::Result<String> {
+        let hash = format!("{:x}", md5::compute(data));
+        let path = self.object_path(&hash);
+        if !path.exists() {
+            fs::create_dir_all(path.parent().unwrap())?;
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp, data)?;
+            fs::rename(&tmp, &path)?;
+        }
+        Ok(hash)
+    }
+
+    /// read chunk, the content is checked against the hash
+    pub fn get(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        if hash.len() < 2 {
+            return Err(t!("store.invalid_object", hash = hash).into());
+        }
+        let data = fs::read(self.object_path(hash))
+            .map_err(|e| t!("store.object_read_failed", hash = hash, err = e))?;
+        if format!("{:x}", md5::compute(&data)) != hash {
+            return Err(t!("store.object_corrupted", hash = hash).into());
+        }
+        Ok(data)
+    }
+
+    /// split `reader` into chunks of `STORE_CHUNK_SIZE` and save them
+    pub fn put_file(&self, path: String, reader: &mut impl Read) -> io::Result<SnapshotFile> {
+        let mut context = md5::Context::new();
+        let mut size = 0u64;
+        let mut chunks = vec![];
+        let mut buf = vec![0; STORE_CHUNK_SIZE];
+        loop {
+            let len = read_full(reader, &mut buf)?;
+            if len == 0 {
+                break;
+            }
+            context.consume(&buf[0..len]);
+            size += len as u64;
+            chunks.push(self.put(&buf[0..len])?);
+            if len < buf.len() {
+                break;
+            }
+        }
+        Ok(SnapshotFile {
+            file: ManifestFile {
+                path,
+                size,
+                md5: format!("{:x}", context.compute()),
+            },
+            chunks,
+        })
+    }
+
+    /// write chunks of the file to `writer`
+    pub fn read_file(
+        &self,
+        file: &SnapshotFile,
+        writer: &mut impl Write,
+    ) -> Result<(), Box<dyn Error>> {
+        for hash in file.chunks.iter() {
+            writer.write_all(&self.get(hash)?)?;
+        }
+        Ok(())
+    }
+
+    /// # dirs that have snapshots of the store
+    ///
+    /// the local save dir can be changed, snapshots in the old dir still use
+    /// the chunks of the store
+    pub fn snapshot_dirs(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        match fs::read(self.root.join(SNAPSHOT_DIRS_NAME)) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// remember `dir` as a dir of snapshots, before a snapshot is written to it
+    pub fn add_snapshot_dir(&self, dir: &str) -> Result<(), Box<dyn Error>> {
+        let mut dirs = self.snapshot_dirs()?;
+        if dirs.iter().any(|d| d == dir) {
+            return Ok(());
+        }
+        dirs.push(dir.to_string());
+        fs::create_dir_all(&self.root)?;
+        let path = self.root.join(SNAPSHOT_DIRS_NAME);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&dirs)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// # remove chunks no snapshot uses anymore
+    ///
+    /// snapshots of every dir of `snapshot_dirs` are counted, `dir` is added
+    /// to them first. returns count of removed chunks
+    pub fn gc_snapshots(&self, dir: &str) -> Result<usize, Box<dyn Error>> {
+        self.add_snapshot_dir(dir)?;
+        let mut chunks = HashSet::new();
+        for dir in self.snapshot_dirs()? {
+            chunks.extend(collect_chunks(&dir)?);
+        }
+        Ok(self.gc(&chunks)?)
+    }
+
+    /// remove chunks not in `referenced`, returns count of removed chunks
+    pub fn gc(&self, referenced: &HashSet<String>) -> io::Result<usize> {
+        let mut count = 0;
+        let objects_dir = self.objects_dir();
+        if !objects_dir.exists() {
+            return Ok(0);
+        }
+        for dir in fs::read_dir(&objects_dir)?.flatten() {
+            if !dir.path().is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(dir.path())?.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !referenced.contains(&name) {
+                    fs::remove_file(entry.path())?;
+                    count += 1;
+                }
+            }
+            if fs::read_dir(dir.path())?.next().is_none() {
+                fs::remove_dir(dir.path()).ok();
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// collect chunks referenced by all snapshots under `root`, fails if any
+/// snapshot can not be read, so `gc` never removes chunks still in use
+pub fn collect_chunks(root: impl AsRef<Path>) -> Result<HashSet<String>, Box<dyn Error>> {
+    let mut chunks = HashSet::new();
+    let root = root.as_ref();
+    if !root.exists() {
+        return Ok(chunks);
+    }
+    for entry in fs::read_dir(root)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            chunks.extend(collect_chunks(&path)?);
+        } else if path.to_string_lossy().ends_with(SNAPSHOT_EXT) {
+            let snapshot = Snapshot::load(&path)
+                .map_err(|e| t!("store.snapshot_read_failed", path = path.display(), err = e))?;
+            chunks.extend(snapshot.chunks().cloned());
+        }
+    }
+    Ok(chunks)
+}
+
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(size) => len += size,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, io::Cursor};
+
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::manifest::verify_backup;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("save-cloud-store-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn snapshot(store: &ObjectStore, files: &[(&str, &[u8])]) -> Snapshot {
+        let mut snapshot = Snapshot::new(BackupManifest::new(
+            "0x4000000055D00".to_string(),
+            "CTR-P-EKJA".to_string(),
+            "sd",
+            "user",
+        ));
+        snapshot.dirs.push("dir/".to_string());
+        for (path, data) in files {
+            let file = store
+                .put_file(path.to_string(), &mut Cursor::new(data))
+                .unwrap();
+            snapshot.files.push(file);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn store_and_export() {
+        let dir = temp_dir("export");
+        let store = ObjectStore::new(&dir);
+        let big = (0..STORE_CHUNK_SIZE * 2 + 1)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        let files: [(&str, &[u8]); 3] = [("main", &big), ("dir/a.bin", b"a"), ("empty", b"")];
+        let snapshot = snapshot(&store, &files);
+        assert_eq!(snapshot.files[0].chunks.len(), 3);
+        assert_eq!(snapshot.files[2].chunks.len(), 0);
+
+        // saved and loaded again
+        let path = dir.join(format!("backup{}", SNAPSHOT_EXT));
+        snapshot.save(&path).unwrap();
+        let snapshot = Snapshot::load(&path).unwrap();
+
+        let mut zip = Cursor::new(vec![]);
+        snapshot.export_zip(&store, &mut zip, |_, _| {}).unwrap();
+        zip.set_position(0);
+        let manifest = verify_backup(&mut zip).unwrap().unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        let mut zip = ZipArchive::new(zip).unwrap();
+        for (path, data) in files {
+            let mut content = vec![];
+            zip.by_name(path)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, data, "{}", path);
+        }
+        assert!(zip.by_name("dir/").unwrap().is_dir());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn chunks_are_shared() {
+        let dir = temp_dir("shared");
+        let store = ObjectStore::new(&dir);
+        let first = snapshot(&store, &[("main", b"same"), ("a", b"first")]);
+        let second = snapshot(&store, &[("main", b"same"), ("a", b"second")]);
+        assert_eq!(first.files[0].chunks, second.files[0].chunks);
+
+        let mut referenced = second.chunks().cloned().collect::<HashSet<_>>();
+        assert_eq!(store.gc(&referenced).unwrap(), 1);
+        assert!(store.get(&first.files[1].chunks[0]).is_err());
+        assert_eq!(store.get(&first.files[0].chunks[0]).unwrap(), b"same");
+
+        referenced.clear();
+        assert_eq!(store.gc(&referenced).unwrap(), 2);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn chunks_of_old_dirs_are_kept() {
+        let dir = temp_dir("dirs");
+        let store = ObjectStore::new(dir.join("store"));
+        let old_dir = dir.join("old").to_string_lossy().to_string();
+        let new_dir = dir.join("new").to_string_lossy().to_string();
+
+        // backup to the old dir, then the local save dir is changed
+        store.add_snapshot_dir(&old_dir).unwrap();
+        let old = snapshot(&store, &[("main", b"old")]);
+        fs::create_dir_all(&old_dir).unwrap();
+        old.save(Path::new(&old_dir).join(format!("old{}", SNAPSHOT_EXT)))
+            .unwrap();
+        let unused = store.put(b"unused").unwrap();
+
+        assert_eq!(store.gc_snapshots(&new_dir).unwrap(), 1);
+        assert_eq!(store.snapshot_dirs().unwrap(), [old_dir.clone(), new_dir]);
+        assert_eq!(store.get(&old.files[0].chunks[0]).unwrap(), b"old");
+        assert!(store.get(&unused).is_err());
+
+        // the old dir is counted as long as it has snapshots
+        fs::remove_dir_all(&old_dir).unwrap();
+        assert_eq!(store.gc_snapshots(&old_dir).unwrap(), 1);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn corrupted_chunk() {
+        let dir = temp_dir("corrupted");
+        let store = ObjectStore::new(&dir);
+        let hash = store.put(b"data").unwrap();
+        fs::write(store.object_path(&hash), b"DATA").unwrap();
+        assert!(store.get(&hash).is_err());
+        assert!(store.get("x").is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn chunks_of_snapshots() {
+        let dir = temp_dir("collect");
+        let store = ObjectStore::new(&dir);
+        let snapshot = snapshot(&store, &[("main", b"main"), ("a", b"a")]);
+        fs::create_dir_all(dir.join("title")).unwrap();
+        snapshot
+            .save(dir.join("title").join(format!("backup{}", SNAPSHOT_EXT)))
+            .unwrap();
+        let chunks = collect_chunks(&dir).unwrap();
+        assert_eq!(chunks, snapshot.chunks().cloned().collect());
+
+        // an unreadable snapshot fails, so gc never runs on a partial set
+        fs::write(dir.join(format!("broken{}", SNAPSHOT_EXT)), b"{").unwrap();
+        assert!(collect_chunks(&dir).is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,20 @@
+// http of the host
+// there is no curl on the host, transfers never run
+
+/// # Safety
+///
+/// always safe, unsafe only to match the C function of the 3DS
+pub unsafe fn http_set_cancelled(_cancelled: bool) {}
+
+/// (bytes done, bytes total) of the running transfer
+pub fn http_transfer_progress() -> (u64, u64) {
+    (0, 0)
+}
+
+/// http status of the last request, or the code of curl if the request failed
+pub fn http_last_status() -> i64 {
+    -1
+}
+
+/// connect timeout and receive buffer size of the following requests
+pub fn http_set_options(_connect_timeout_secs: u32, _buffer_size: u32) {}
@@ -0,0 +1,23 @@
+// platform of the host
+// the types and services of the 3DS the core depends on, enough to build
+// and test it on the host
+
+use crate::i18n::Lang;
+
+/// result code of the 3DS services
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResultCode(pub i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveID {
+    Sdmc,
+    UserSavedata,
+    Extdata,
+    SharedExtdata,
+    SystemSavedata,
+    BossExtdata,
+}
+
+pub fn pl_system_lang() -> Lang {
+    Lang::En
+}
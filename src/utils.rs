@@ -1,9 +1,10 @@
 use std::{
+    collections::HashSet,
     error::Error,
     ffi::{c_char, CStr},
     fmt::{Display, Formatter},
     fs,
-    io::{self, Read, Write},
+    io::{self, Read, Seek, SeekFrom, Write},
     ops::Deref,
     path::Path,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -12,12 +13,25 @@ use std::{
 use base64::{engine::general_purpose, Engine as _};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 
-use zip::ZipWriter;
+use zip::{read::ZipFile, ZipWriter};
 
+#[cfg(feature = "3ds")]
+use crate::{c2d::rgba, config::Theme};
 use crate::{
-    c2d::rgba,
-    constant::INVALID_CHARS,
-    fsu::{self, Archive},
+    cancel, config,
+    constant::{
+        BACKUP_MANIFEST_NAME, ENCRYPTED_EXT, EXT_DATA_MIN_ENTRIES, HOME_LOCAL_PATH_STORE,
+        INVALID_CHARS, SNAPSHOT_EXT,
+    },
+    crypto::{is_encrypted, is_encrypted_file, session_passphrase, DecryptReader, EncryptWriter},
+    diff::{zip_files, zip_stream_files},
+    error::{AppError, AppResult},
+    manifest::{verify_backup, BackupManifest, HashWriter, ManifestFile},
+    progress::{self, ProgressStage},
+    restore::{restore_transaction, RestoreTarget},
+    store::{ObjectStore, Snapshot},
+    t,
+    vfs::Vfs,
 };
 
 extern "C" {
@@ -90,17 +104,17 @@ pub fn ease_out_expo(elapsed: Duration, duration: Duration, start: f64, end: f64
 pub fn get_active_color() -> u32 {
     let from = (168, 254, 255) as (i32, i32, i32);
     let to = (0, 168, 255) as (i32, i32, i32);
-    let mut current = (0, 0, 0) as (i32, i32, i32);
+    let mut _current = (0, 0, 0) as (i32, i32, i32);
     let p = (current_time() % 1000) as i32;
 
     if p < 400 {
-        current.0 = from.0 + (to.0 - from.0) * p / 400;
-        current.1 = from.1 + (to.1 - from.1) * p / 400;
-        current.2 = from.2 + (to.2 - from.2) * p / 400;
+        _current.0 = from.0 + (to.0 - from.0) * p / 400;
+        _current.1 = from.1 + (to.1 - from.1) * p / 400;
+        _current.2 = from.2 + (to.2 - from.2) * p / 400;
     } else {
-        current.0 = from.0 + (to.0 - from.0) * (1000 - p) / 600;
-        current.1 = from.1 + (to.1 - from.1) * (1000 - p) / 600;
-        current.2 = from.2 + (to.2 - from.2) * (1000 - p) / 600;
+        _current.0 = from.0 + (to.0 - from.0) * (1000 - p) / 600;
+        _current.1 = from.1 + (to.1 - from.1) * (1000 - p) / 600;
+        _current.2 = from.2 + (to.2 - from.2) * (1000 - p) / 600;
     }
 
     // TODO
@@ -146,7 +160,10 @@ pub fn get_local_game_saves(path: &str) -> Vec<String> {
                     Some(name) => name.to_str(),
                     None => None,
                 } {
-                    if name.ends_with(".zip") {
+                    if name.ends_with(".zip")
+                        || name.ends_with(SNAPSHOT_EXT)
+                        || name.ends_with(ENCRYPTED_EXT)
+                    {
                         list.push(name.to_string());
                     }
                 }
@@ -158,17 +175,17 @@ pub fn get_local_game_saves(path: &str) -> Vec<String> {
     list
 }
 
-pub fn zip_dir_with(
-    zip: &mut ZipWriter<fsu::File>,
+pub fn zip_dir_with<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
     input_path: &Path,
     prefix: &str,
-    arch: &Archive,
+    arch: &impl Vfs,
     back_list: &[&str],
+    files: &mut Vec<ManifestFile>,
     mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
-    let options =
-        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-    for entry in fsu::read_dir(arch, input_path)?.flatten() {
+) -> AppResult<()> {
+    let options = config::get().backup.file_options();
+    for entry in arch.read_dir(input_path)? {
         let path = entry.path();
         let name = path.strip_prefix(Path::new(prefix)).unwrap();
         if back_list
@@ -177,39 +194,43 @@ pub fn zip_dir_with(
         {
             continue;
         }
+        cancel::check()?;
         notify(None, Some(entry.file_name().to_string_lossy().to_string()));
         // Write file or directory explicitly
         // Some unzip tools unzip files with directory paths correctly, some do not!
         if entry.metadata()?.is_file() {
+            progress::next_item();
             #[allow(deprecated)]
             zip.start_file_from_path(name, options)?;
-            let mut input_file = fsu::File::open(arch, path)?;
-            loop {
-                let mut buffer = vec![0; 1024 * 512];
-                let size = input_file.read(&mut buffer)?;
-                if size == 0 {
-                    break;
-                }
-                zip.write_all(&buffer[0..size])?;
-            }
+            let mut input_file = arch.open(&path).map_err(|e| AppError::fs(e, &path))?;
+            let mut output = HashWriter::new(&mut *zip);
+            copy_buf(&mut input_file, &mut output)?;
+            let (size, md5) = output.finish();
+            files.push(ManifestFile {
+                path: name.to_string_lossy().to_string(),
+                size,
+                md5,
+            });
         } else if !name.as_os_str().is_empty() {
             // Only if not root! Avoids path spec / warning
             // and mapname conversion failed error on unzip
             #[allow(deprecated)]
             zip.add_directory_from_path(name, options)?;
-            zip_dir_with(zip, path.as_path(), prefix, arch, back_list, notify)?;
+            zip_dir_with(zip, path.as_path(), prefix, arch, back_list, files, notify)?;
         }
     }
 
     Ok(())
 }
 
+/// zip dir of `from` archive to `to`, the manifest will be written as the last entry if provided
 pub fn zip_dir(
-    from: (&str, &Archive),
-    to: (&str, &Archive),
+    from: (&str, &impl Vfs),
+    to: (&str, &impl Vfs),
     back_list: &[&str],
+    manifest: Option<BackupManifest>,
     notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
+) -> AppResult<()> {
     let (from, from_arch) = from;
     let (to, to_arch) = to;
     let from = if from.ends_with('/') {
@@ -218,31 +239,46 @@ pub fn zip_dir(
         format!("{}/", from)
     };
     let output_path = Path::new(to);
-    if !output_path.parent().unwrap().exists() {
-        fsu::create_dir_all(to_arch, output_path.parent().unwrap())?;
+    if !to_arch.is_dir_exists(output_path.parent().unwrap()) {
+        to_arch.create_dir_all(output_path.parent().unwrap())?;
     }
-    let mut zip = zip::ZipWriter::new(fsu::File::create(&to_arch, output_path, None)?);
+    let mut zip = zip::ZipWriter::new(to_arch.create(output_path, None)?);
+    let mut files = vec![];
+    progress::start(
+        ProgressStage::Compress,
+        0,
+        save_arch_size(Path::new(&from), from_arch),
+    );
     let res = zip_dir_with(
         &mut zip,
         Path::new(&from),
         &from,
         from_arch,
         back_list,
+        &mut files,
         notify,
     )
-    .map(|_| zip.finish());
+    .and_then(|_| {
+        if let Some(mut manifest) = manifest {
+            manifest.files = files;
+            manifest.write_to(&mut zip)?;
+        }
+        zip.finish()?;
+        Ok(())
+    });
     if let Err(e) = res {
-        fs::remove_file(&output_path).ok();
+        drop(zip);
+        to_arch.remove_file(output_path).ok();
         return Err(e);
     }
+    progress::finish();
     Ok(())
 }
 
-pub fn zip_file(from: &str, name: &str, to: &str) -> Result<(), Box<dyn Error>> {
+pub fn zip_file(from: &str, name: &str, to: &str) -> AppResult<()> {
     let from_path = Path::new(from).join(name);
     let mut zip = zip::ZipWriter::new(fs::File::create(to)?);
-    let options =
-        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let options = config::get().backup.file_options();
     let mut buffer = vec![0; 1024 * 512];
     #[allow(deprecated)]
     zip.start_file_from_path(Path::new(name), options)?;
@@ -258,36 +294,102 @@ pub fn zip_file(from: &str, name: &str, to: &str) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
+/// extract zip of `from` archive to `to`, entries in `back_list` will be skipped
 pub fn zip_extract(
-    from: (impl AsRef<Path>, &Archive),
-    to: (impl AsRef<Path>, &Archive),
+    from: (impl AsRef<Path>, &impl Vfs),
+    to: (impl AsRef<Path>, &impl Vfs),
+    back_list: &[&str],
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    zip_extract_with(from, to, |name| !back_list.contains(&name), notify)
+}
+
+/// extract zip of `from` archive to `to`, only entries that `filter` returns true are extracted
+///
+/// an encrypted backup is decrypted while reading with the passphrase of the session
+pub fn zip_extract_with(
+    from: (impl AsRef<Path>, &impl Vfs),
+    to: (impl AsRef<Path>, &impl Vfs),
+    filter: impl Fn(&str) -> bool,
     mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
-    let mut zip = zip::ZipArchive::new(fsu::File::open(&from.1, from.0)?)?;
+) -> AppResult<()> {
+    let mut file = from.1.open(from.0.as_ref())?;
+    let is_encrypted = is_encrypted(&mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    if is_encrypted {
+        let passphrase = session_passphrase().ok_or(t!("menu.passphrase_required"))?;
+        // zip entries are read one by one, the decrypted zip is not seekable
+        let mut reader = DecryptReader::new(file, &passphrase)?;
+        let mut idx = 0;
+        // count and size are unknown until the whole stream is read
+        progress::start(ProgressStage::Extract, 0, 0);
+        while let Some(mut file_name) = zip::read::read_zipfile_from_stream(&mut reader)? {
+            cancel::check()?;
+            progress::next_item();
+            idx += 1;
+            notify(Some(t!("utils.extracting", idx = idx)), None);
+            if filter(file_name.name()) {
+                zip_extract_entry(&mut file_name, (to.0.as_ref(), to.1), notify)?;
+            }
+        }
+        progress::finish();
+        return Ok(());
+    }
+
+    let mut zip = zip::ZipArchive::new(file)?;
+    let mut total = 0;
+    for i in 0..zip.len() {
+        let file_name = zip.by_index_raw(i)?;
+        if filter(file_name.name()) {
+            total += file_name.size();
+        }
+    }
+    progress::start(ProgressStage::Extract, zip.len(), total);
     for i in 0..zip.len() {
-        notify(Some(format!("正在解压 {}/{}", i + 1, zip.len())), None);
+        cancel::check()?;
+        progress::next_item();
+        notify(
+            Some(t!("utils.extracting_of", idx = i + 1, count = zip.len())),
+            None,
+        );
         let mut file_name = zip.by_index(i)?;
-        let output_path = match file_name.enclosed_name() {
-            Some(file_name) => {
-                notify(None, Some(file_name.to_string_lossy().to_string()));
-                to.0.as_ref().join(file_name).to_owned()
-            }
-            None => continue,
-        };
+        if filter(file_name.name()) {
+            zip_extract_entry(&mut file_name, (to.0.as_ref(), to.1), notify)?;
+        }
+    }
+    progress::finish();
 
-        if (*file_name.name()).ends_with('/') {
-            if !fsu::is_dir_exists(to.1, &output_path) {
-                fsu::create_dir_all(&to.1, &output_path)?;
-            }
-        } else {
-            if let Some(p) = output_path.parent() {
-                if !fsu::is_dir_exists(to.1, p) {
-                    fsu::create_dir_all(&to.1, p)?;
-                }
+    Ok(())
+}
+
+fn zip_extract_entry(
+    file_name: &mut ZipFile,
+    to: (&Path, &impl Vfs),
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    let output_path = match file_name.enclosed_name() {
+        Some(file_name) => {
+            notify(None, Some(file_name.to_string_lossy().to_string()));
+            to.0.join(file_name).to_owned()
+        }
+        None => return Ok(()),
+    };
+
+    if (*file_name.name()).ends_with('/') {
+        if !to.1.is_dir_exists(&output_path) {
+            to.1.create_dir_all(&output_path)?;
+        }
+    } else {
+        if let Some(p) = output_path.parent() {
+            if !to.1.is_dir_exists(p) {
+                to.1.create_dir_all(p)?;
             }
-            let mut output_file = fsu::File::create(&to.1, &output_path, Some(file_name.size()))?;
-            copy_buf(&mut file_name, &mut output_file)?;
         }
+        let mut output_file =
+            to.1.create(&output_path, Some(file_name.size()))
+                .map_err(|e| AppError::fs(e, &output_path))?;
+        copy_buf(file_name, &mut output_file)?;
     }
 
     Ok(())
@@ -299,11 +401,13 @@ pub fn copy_buf(from: &mut impl Read, to: &mut impl Write) -> io::Result<u64> {
     let mut current_cache_size = 0;
     let mut buf = vec![0; 1024 * 128];
     loop {
+        cancel::check()?;
         let size = from.read(&mut buf)?;
         if size == 0 {
             break;
         }
         total += size as u64;
+        progress::add_bytes(size as u64);
         if current_cache_size + size > cache.len() {
             // fill gap
             let gap = cache.len() - current_cache_size;
@@ -358,16 +462,33 @@ pub fn join_path(base: &str, path: &str) -> String {
     }
 }
 
-pub fn check_save_arch_is_empty(path: impl AsRef<Path>, arch: &Archive) -> bool {
-    if let Ok(dirs) = fsu::read_dir(arch, path) {
-        for entry in dirs.flatten() {
+/// # quota of extdata created for the files of a backup
+///
+/// returns max count of dirs, files and the size limit, doubled for the files
+/// the game creates later, at least `EXT_DATA_MIN_ENTRIES` entries and 1 MiB
+pub fn ext_data_quota(files: &[ManifestFile]) -> (u32, u32, u64) {
+    let dirs = files
+        .iter()
+        .filter_map(|f| Path::new(f.path.trim_start_matches('/')).parent())
+        .filter(|p| !p.as_os_str().is_empty())
+        .flat_map(|p| p.ancestors().filter(|a| !a.as_os_str().is_empty()))
+        .collect::<HashSet<_>>()
+        .len() as u32;
+    let size = files.iter().map(|f| f.size).sum::<u64>();
+    (
+        (dirs * 2).max(EXT_DATA_MIN_ENTRIES),
+        (files.len() as u32 * 2).max(EXT_DATA_MIN_ENTRIES),
+        (size * 2).max(1024 * 1024),
+    )
+}
+
+pub fn check_save_arch_is_empty(path: impl AsRef<Path>, arch: &impl Vfs) -> bool {
+    if let Ok(dirs) = arch.read_dir(path.as_ref()) {
+        for entry in dirs {
             if let Ok(m) = entry.metadata() {
-                if m.is_file() {
+                // a dir is empty if all dirs in it are empty
+                if m.is_file() || !check_save_arch_is_empty(entry.path(), arch) {
                     return false;
-                } else if m.is_dir() {
-                    if !check_save_arch_is_empty(entry.path(), arch) {
-                        return false;
-                    }
                 }
             }
         }
@@ -376,47 +497,357 @@ pub fn check_save_arch_is_empty(path: impl AsRef<Path>, arch: &Archive) -> bool
 }
 
 pub fn backup_game_save(
-    from: (&str, &Archive),
-    to: (&str, &Archive),
+    from: (&str, &impl Vfs),
+    to: (&str, &impl Vfs),
+    manifest: BackupManifest,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    zip_dir(from, to, &[], Some(manifest), notify)
+}
+
+fn store_dir_with(
+    store: &ObjectStore,
+    snapshot: &mut Snapshot,
+    input_path: &Path,
+    prefix: &str,
+    arch: &impl Vfs,
+    mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    for entry in arch.read_dir(input_path)? {
+        let path = entry.path();
+        let name = path.strip_prefix(Path::new(prefix)).unwrap();
+        notify(None, Some(entry.file_name().to_string_lossy().to_string()));
+        if entry.metadata()?.is_file() {
+            let mut input_file = arch.open(&path)?;
+            snapshot
+                .files
+                .push(store.put_file(name.to_string_lossy().to_string(), &mut input_file)?);
+        } else if !name.as_os_str().is_empty() {
+            snapshot.dirs.push(name.to_string_lossy().to_string());
+            store_dir_with(store, snapshot, path.as_path(), prefix, arch, notify)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// # backup game save into the dedup store
+///
+/// files are saved as chunks in `HOME_LOCAL_PATH_STORE`, only the snapshot
+/// index is written to `to`
+pub fn backup_game_save_to_store(
+    from: (&str, &impl Vfs),
+    to: &str,
+    manifest: BackupManifest,
     notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
-    zip_dir(from, to, &[], notify)
+) -> AppResult<()> {
+    let (from, from_arch) = from;
+    let from = if from.ends_with('/') {
+        from.to_string()
+    } else {
+        format!("{}/", from)
+    };
+    let store = ObjectStore::new(HOME_LOCAL_PATH_STORE);
+    // so that `store_gc` still counts the snapshot if the local save dir is changed
+    store.add_snapshot_dir(&config::local_save_dir())?;
+    let mut snapshot = Snapshot::new(manifest);
+    store_dir_with(
+        &store,
+        &mut snapshot,
+        Path::new(&from),
+        &from,
+        from_arch,
+        notify,
+    )?;
+    if let Some(parent) = Path::new(to).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(snapshot.save(to)?)
+}
+
+/// export snapshot of the dedup store as a plain backup zip
+pub fn export_snapshot(
+    from: &str,
+    to: &str,
+    notify: impl FnMut(Option<String>, Option<String>) + Copy,
+) -> AppResult<()> {
+    let snapshot = Snapshot::load(from)?;
+    let store = ObjectStore::new(HOME_LOCAL_PATH_STORE);
+    let res = fs::File::create(to)
+        .map_err(|e| AppError::fs(e, to))
+        .and_then(|file| Ok(snapshot.export_zip(&store, file, notify)?));
+    if res.is_err() {
+        fs::remove_file(to).ok();
+    }
+    res
+}
+
+/// encrypt backup of `from` to `to`, partial file is removed on error
+pub fn encrypt_backup(from: &str, to: &str, passphrase: &str) -> AppResult<()> {
+    let res = fs::File::open(from)
+        .and_then(|mut input| {
+            progress::start(ProgressStage::Encrypt, 1, input.metadata()?.len());
+            let mut output = EncryptWriter::new(fs::File::create(to)?, passphrase)?;
+            copy_buf(&mut input, &mut output)?;
+            output.finish().map(|_| ())
+        })
+        .map_err(|e| e.into());
+    if res.is_err() {
+        fs::remove_file(to).ok();
+    }
+    res
+}
+
+/// decrypt backup of `from` to `to`, partial file is removed on error
+pub fn decrypt_backup(from: &str, to: &str, passphrase: &str) -> AppResult<()> {
+    let res = fs::File::open(from)
+        .and_then(|input| {
+            progress::start(ProgressStage::Decrypt, 1, input.metadata()?.len());
+            let mut input = DecryptReader::new(input, passphrase)?;
+            copy_buf(&mut input, &mut fs::File::create(to)?)
+        })
+        .map(|_| ())
+        .map_err(|e| e.into());
+    if res.is_err() {
+        fs::remove_file(to).ok();
+    }
+    res
+}
+
+fn save_arch_files_with(
+    files: &mut Vec<ManifestFile>,
+    input_path: &Path,
+    prefix: &str,
+    arch: &impl Vfs,
+) -> AppResult<()> {
+    for entry in arch.read_dir(input_path)? {
+        let path = entry.path();
+        let name = path.strip_prefix(Path::new(prefix)).unwrap();
+        if entry.metadata()?.is_file() {
+            let mut input_file = arch.open(&path)?;
+            let mut output = HashWriter::new(io::sink());
+            copy_buf(&mut input_file, &mut output)?;
+            let (size, md5) = output.finish();
+            files.push(ManifestFile {
+                path: name.to_string_lossy().to_string(),
+                size,
+                md5,
+            });
+        } else if !name.as_os_str().is_empty() {
+            save_arch_files_with(files, path.as_path(), prefix, arch)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// total size of the files in the save archive, files are not read
+pub fn save_arch_size(path: impl AsRef<Path>, arch: &impl Vfs) -> u64 {
+    save_arch_blocks_size(path, arch, 1)
+}
+
+/// space taken by the files in the save archive, every file takes whole blocks
+pub fn save_arch_blocks_size(path: impl AsRef<Path>, arch: &impl Vfs, block_size: u64) -> u64 {
+    arch.read_dir(path.as_ref())
+        .map(|dirs| {
+            dirs.into_iter()
+                .map(|entry| match entry.metadata() {
+                    Ok(m) if m.is_file() => round_up_to_block(m.len(), block_size),
+                    Ok(m) if m.is_dir() => save_arch_blocks_size(entry.path(), arch, block_size),
+                    _ => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// `size` rounded up to whole blocks, `block_size` 0 is taken as 1
+pub fn round_up_to_block(size: u64, block_size: u64) -> u64 {
+    let block_size = block_size.max(1);
+    size.div_ceil(block_size) * block_size
 }
 
+/// files of the save archive, every file is read and hashed
+pub fn save_arch_files(from: (&str, &impl Vfs)) -> AppResult<Vec<ManifestFile>> {
+    let (from, arch) = from;
+    let from = if from.ends_with('/') {
+        from.to_string()
+    } else {
+        format!("{}/", from)
+    };
+    let mut files = vec![];
+    save_arch_files_with(&mut files, Path::new(&from), &from, arch)?;
+    Ok(files)
+}
+
+/// files of a local backup, zip or snapshot of the dedup store
+pub fn backup_files(path: &str) -> AppResult<Vec<ManifestFile>> {
+    if path.ends_with(SNAPSHOT_EXT) {
+        Ok(Snapshot::load(path)?.full_manifest().files)
+    } else if is_encrypted_file(path) {
+        let passphrase = session_passphrase().ok_or(t!("menu.passphrase_required"))?;
+        Ok(zip_stream_files(DecryptReader::new(
+            fs::File::open(path)?,
+            &passphrase,
+        )?)?)
+    } else {
+        Ok(zip_files(fs::File::open(path)?)?)
+    }
+}
+
+/// # remove chunks of the dedup store that no snapshot uses anymore
+///
+/// snapshots of every local save dir used so far are counted, not only the
+/// current one
+pub fn store_gc() -> AppResult<usize> {
+    Ok(ObjectStore::new(HOME_LOCAL_PATH_STORE).gc_snapshots(&config::local_save_dir())?)
+}
+
+/// read the manifest of backup zip, `None` if the backup was made without manifest
+pub fn read_backup_manifest(path: impl AsRef<Path>, arch: &impl Vfs) -> Option<BackupManifest> {
+    let file = arch.open(path.as_ref()).ok()?;
+    let mut zip = zip::ZipArchive::new(file).ok()?;
+    BackupManifest::read_from(&mut zip)
+}
+
+/// # restore game save
+///
+/// `manifest` describes the save archive of `to`, it is used to check the
+/// backup and to make the auto backup before restore. the save is rolled back
+/// to the auto backup if the restore fails, data is committed only on success.
+/// only `files` of the backup are restored if provided, other files of the
+/// save are kept
 pub fn restore_game_save(
-    from: (&str, &Archive),
-    to: (&str, &Archive),
+    from: (&str, &impl Vfs),
+    to: (&str, &impl Vfs),
+    manifest: BackupManifest,
+    files: Option<Vec<String>>,
     mut notify: impl FnMut(Option<String>, Option<String>) + Copy,
-) -> Result<(), Box<dyn Error>> {
-    if !check_save_arch_is_empty("/", &to.1) {
-        if let Some(from_parent) = Path::new(from.0).parent() {
-            if let Some(auto_backup_path) = from_parent
-                .join(format!("{} auto.zip", get_current_format_time()))
-                .to_str()
-            {
-                notify(Some("正在自动备份".to_string()), None);
-                let _ = backup_game_save(to, (auto_backup_path, from.1), notify);
-            }
+) -> AppResult<()> {
+    // verify backup before anything of the save archive is touched
+    notify(Some(t!("utils.verifying").to_string()), None);
+    let backup_manifest = verify_backup(
+        from.1
+            .open(Path::new(from.0))
+            .map_err(|e| AppError::fs(e, from.0))?,
+    )
+    .map_err(|e| t!("utils.verify_failed", err = e))?;
+    if let Some(backup_manifest) = backup_manifest {
+        if backup_manifest.save_type != manifest.save_type {
+            return Err(AppError::Validation(t!(
+                "utils.save_type_mismatch",
+                backup = backup_manifest.save_type,
+                save = manifest.save_type
+            )));
         }
     }
 
-    // clear save dir
-    if let Ok(dirs) = fsu::read_dir(&to.1, "/") {
-        for entry in dirs.flatten() {
-            if let Ok(m) = entry.metadata() {
-                if m.is_file() {
-                    fsu::remove_file(&to.1, entry.path())?;
-                } else if m.is_dir() {
-                    fsu::remove_dir_all(&to.1, entry.path())?;
+    // the snapshot is kept next to the backup as an auto backup
+    let local_save_dir = config::local_save_dir();
+    let snapshot_path = Path::new(from.0)
+        .parent()
+        .unwrap_or(Path::new(&local_save_dir))
+        .join(format!("{} auto.zip", get_current_format_time()));
+    let snapshot_path = snapshot_path
+        .to_str()
+        .ok_or(t!("utils.snapshot_path_invalid"))?;
+
+    let mut target = SaveArchiveTarget {
+        sdmc: from.1,
+        save: to,
+        manifest,
+        files,
+        notify,
+    };
+    restore_transaction(&mut target, from.0, snapshot_path)?;
+    Ok(())
+}
+
+/// # save archive as restore target
+///
+/// backup and snapshot zips are read from / written to `sdmc`, only `files`
+/// are cleared and extracted if provided
+pub struct SaveArchiveTarget<'a, S, V, N>
+where
+    S: Vfs,
+    V: Vfs,
+    N: FnMut(Option<String>, Option<String>) + Copy,
+{
+    pub sdmc: &'a S,
+    pub save: (&'a str, &'a V),
+    pub manifest: BackupManifest,
+    pub files: Option<Vec<String>>,
+    pub notify: N,
+}
+
+impl<'a, S, V, N> RestoreTarget for SaveArchiveTarget<'a, S, V, N>
+where
+    S: Vfs,
+    V: Vfs,
+    N: FnMut(Option<String>, Option<String>) + Copy,
+{
+    fn snapshot(&mut self, snapshot: &str) -> Result<bool, Box<dyn Error>> {
+        if check_save_arch_is_empty(self.save.0, self.save.1) {
+            return Ok(false);
+        }
+        (self.notify)(Some(t!("utils.snapshotting").to_string()), None);
+        backup_game_save(
+            self.save,
+            (snapshot, self.sdmc),
+            self.manifest.clone(),
+            self.notify,
+        )?;
+        Ok(true)
+    }
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(files) = self.files.as_ref() {
+            for file in files {
+                let path = Path::new(self.save.0).join(file.trim_start_matches('/'));
+                if self.save.1.metadata(&path).is_ok_and(|m| m.is_file()) {
+                    self.save.1.remove_file(&path)?;
+                }
+            }
+            return Ok(());
+        }
+        if let Ok(dirs) = self.save.1.read_dir(Path::new(self.save.0)) {
+            for entry in dirs {
+                if entry.metadata.is_file() {
+                    self.save.1.remove_file(&entry.path)?;
+                } else {
+                    self.save.1.remove_dir_all(&entry.path)?;
                 }
             }
         }
+        Ok(())
     }
 
-    notify(Some("正在恢复存档".to_string()), None);
+    fn extract(&mut self, backup: &str) -> Result<(), Box<dyn Error>> {
+        (self.notify)(Some(t!("menu.restoring").to_string()), None);
+        // manifest is not part of the save data
+        Ok(zip_extract_with(
+            (backup, self.sdmc),
+            self.save,
+            |name| {
+                name != BACKUP_MANIFEST_NAME
+                    && self.files.as_ref().is_none_or(|files| {
+                        files
+                            .iter()
+                            .any(|f| f.trim_start_matches('/') == name.trim_start_matches('/'))
+                    })
+            },
+            self.notify,
+        )?)
+    }
 
-    // extract zip
-    zip_extract(from, to, notify)
+    fn begin_rollback(&mut self) {
+        // the rollback must run to the end even if the restore is cancelled
+        cancel::reset();
+    }
+
+    fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(self.save.1.commit()?)
+    }
 }
 
 pub fn url_encode(param: &str) -> String {
@@ -435,7 +866,7 @@ pub fn get_str_md5(data: &[u8]) -> String {
     format!("{:x}", md5::compute(data))
 }
 
-pub fn delete_dir_if_empty(path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+pub fn delete_dir_if_empty(path: impl AsRef<Path>) -> AppResult<()> {
     let path = path.as_ref();
     if path.exists() && path.is_dir() && path.read_dir()?.next().is_none() {
         fs::remove_dir(path)?;
@@ -443,19 +874,22 @@ pub fn delete_dir_if_empty(path: impl AsRef<Path>) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
-pub fn create_parent_if_not_exists(path: &str) -> Result<(), Box<dyn Error>> {
-    match Path::new(path).parent() {
-        Some(parent) => {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-            }
+pub fn create_parent_if_not_exists(path: &str) -> AppResult<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
         }
-        None => {}
     }
     Ok(())
 }
 
+#[cfg(feature = "3ds")]
 pub fn color_name_rgba(color: &str) -> u32 {
+    if config::theme() == Theme::Light {
+        if let Some(color) = light_color_name_rgba(color) {
+            return color;
+        }
+    }
     match color {
         // if there is a color tag, translate it
         "red" => rgba(0xff, 0x00, 0x00, 0xff),
@@ -478,6 +912,23 @@ pub fn color_name_rgba(color: &str) -> u32 {
     }
 }
 
+/// colors of the light theme, the same as the dark theme if `None`
+#[cfg(feature = "3ds")]
+fn light_color_name_rgba(color: &str) -> Option<u32> {
+    let color = match color {
+        "main-text" => rgba(0x22, 0x22, 0x22, 0xff),
+        "main_bg" => rgba(0xee, 0xee, 0xee, 0xff),
+        "selected_bg" => rgba(0xcc, 0xcc, 0xcc, 0xff),
+        "selected_bg_info" => rgba(0xdd, 0xdd, 0xdd, 0xff),
+        "selected_bg_dark" => rgba(0xe4, 0xe4, 0xe4, 0xff),
+        "selected_bg_light" => rgba(0xb0, 0xb0, 0xb0, 0xff),
+        "tips" => rgba(0x55, 0x55, 0x55, 0xff),
+        "panel_bg" => rgba(0xe8, 0xe8, 0xe8, 0xff),
+        _ => return None,
+    };
+    Some(color)
+}
+
 pub async fn sleep_micros(micros: u64) {
     tokio::time::sleep(Duration::from_micros(micros)).await;
 }
@@ -499,3 +950,282 @@ pub fn storage_size_to_info(size: f64) -> (f64, &'static str) {
         (1.0, "B")
     }
 }
+
+/// size with two decimals and the unit of `storage_size_to_info`, e.g. `1.50 M`
+pub fn format_size(size: u64) -> String {
+    let (p, unit) = storage_size_to_info(size as f64);
+    format!("{:.2} {}", size as f64 / p, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use super::*;
+    use crate::vfs::{HostFs, MemFs};
+
+    fn notify(_: Option<String>, _: Option<String>) {}
+
+    fn manifest(save_type: &str) -> BackupManifest {
+        BackupManifest::new(
+            "0004000000055D00".to_string(),
+            "CTR-P-EKJA".to_string(),
+            "sd",
+            save_type,
+        )
+    }
+
+    /// files under `dir` with the data, sorted by path
+    fn tree(fs: &impl Vfs, dir: &str) -> Vec<(String, Vec<u8>)> {
+        let mut files = vec![];
+        let mut dirs = vec![PathBuf::from(dir)];
+        while let Some(cur) = dirs.pop() {
+            for entry in fs.read_dir(&cur).unwrap() {
+                let path = entry.path();
+                if entry.metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let mut data = vec![];
+                fs.open(&path).unwrap().read_to_end(&mut data).unwrap();
+                let name = path.strip_prefix(dir).unwrap().to_string_lossy();
+                files.push((name.to_string(), data));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    fn save(files: &[(&str, &str)]) -> MemFs {
+        let save = MemFs::new();
+        for (path, data) in files {
+            save.write(path, data.as_bytes());
+        }
+        save
+    }
+
+    fn backup(files: &[(&str, &str)], save_type: &str) -> MemFs {
+        let sdmc = MemFs::new();
+        zip_dir(
+            ("/", &save(files)),
+            ("/backups/backup.zip", &sdmc),
+            &[],
+            Some(manifest(save_type)),
+            notify,
+        )
+        .unwrap();
+        sdmc
+    }
+
+    fn entries(files: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        let mut entries = files
+            .iter()
+            .map(|(path, data)| {
+                (
+                    path.trim_start_matches('/').to_string(),
+                    data.as_bytes().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    const OLD_SAVE: [(&str, &str); 2] = [("/main", "old main"), ("/dir/old.bin", "old")];
+    const BACKUP: [(&str, &str); 3] = [
+        ("/main", "new main"),
+        ("/dir/a.bin", "a"),
+        ("/dir/sub/b.bin", "b"),
+    ];
+
+    #[test]
+    fn join_paths() {
+        for (base, path, res) in [
+            ("", "a", "a"),
+            ("/", "a", "/a"),
+            ("sdmc:/save-cloud", "a.zip", "sdmc:/save-cloud/a.zip"),
+            ("sdmc:/save-cloud/", "a.zip", "sdmc:/save-cloud/a.zip"),
+            ("/a", "b/c", "/a/b/c"),
+        ] {
+            assert_eq!(join_path(base, path), res, "{} {}", base, path);
+        }
+    }
+
+    #[test]
+    fn normalize_paths() {
+        for (path, res) in [
+            ("Pokémon Y", "Pokémon Y"),
+            (" a/b\\c ", "a_b_c"),
+            ("a:*?\"'<>|b", "a________b"),
+            ("", ""),
+        ] {
+            assert_eq!(normalize_path(path), res, "{}", path);
+        }
+    }
+
+    #[test]
+    fn size_in_blocks() {
+        assert_eq!(round_up_to_block(0, 0x200), 0);
+        assert_eq!(round_up_to_block(1, 0x200), 0x200);
+        assert_eq!(round_up_to_block(0x200, 0x200), 0x200);
+        assert_eq!(round_up_to_block(0x201, 0x200), 0x400);
+        assert_eq!(round_up_to_block(7, 0), 7);
+
+        let save = save(&BACKUP);
+        assert_eq!(save_arch_size("/", &save), 10);
+        assert_eq!(save_arch_blocks_size("/", &save, 0x200), 0x600);
+    }
+
+    #[test]
+    fn zip_roundtrip_in_memory() {
+        let sdmc = backup(&BACKUP, "user");
+        let manifest = read_backup_manifest("/backups/backup.zip", &sdmc).unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(manifest.get_file("dir/sub/b.bin").map(|f| f.size), Some(1));
+
+        let out = MemFs::new();
+        zip_extract(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &out),
+            &[BACKUP_MANIFEST_NAME],
+            notify,
+        )
+        .unwrap();
+        assert_eq!(tree(&out, "/"), entries(&BACKUP));
+    }
+
+    #[test]
+    fn zip_roundtrip_on_host() {
+        let root = env::temp_dir().join(format!("save-cloud-vfs-{}", std::process::id()));
+        let host = HostFs::new(&root);
+        for (path, data) in BACKUP {
+            let path = format!("/save{}", path);
+            host.create_dir_all(Path::new(&path).parent().unwrap())
+                .unwrap();
+            host.create(Path::new(&path), None)
+                .unwrap()
+                .write_all(data.as_bytes())
+                .unwrap();
+        }
+        let res = zip_dir(("/save", &host), ("/backup.zip", &host), &[], None, notify)
+            .and_then(|_| {
+                zip_extract_with(("/backup.zip", &host), ("/out", &host), |_| true, notify)
+            })
+            .map(|_| tree(&host, "/out"));
+        fs::remove_dir_all(&root).ok();
+        assert_eq!(res.unwrap(), entries(&BACKUP));
+    }
+
+    #[test]
+    fn extract_with_filter() {
+        let sdmc = backup(&BACKUP, "user");
+        let out = MemFs::new();
+        zip_extract_with(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &out),
+            |name| name.starts_with("dir/") && name != BACKUP_MANIFEST_NAME,
+            notify,
+        )
+        .unwrap();
+        assert_eq!(tree(&out, "/"), entries(&BACKUP[1..]));
+    }
+
+    #[test]
+    fn restore_replaces_the_save() {
+        let sdmc = backup(&BACKUP, "user");
+        let save = save(&OLD_SAVE);
+        restore_game_save(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &save),
+            manifest("user"),
+            None,
+            notify,
+        )
+        .unwrap();
+        assert_eq!(tree(&save, "/"), entries(&BACKUP));
+        assert_eq!(save.commits(), 1);
+
+        // the old save is kept as an auto backup next to the backup
+        let auto = sdmc
+            .read_dir(Path::new("/backups"))
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path().to_string_lossy().to_string())
+            .find(|path| path.ends_with(" auto.zip"))
+            .unwrap();
+        let out = MemFs::new();
+        zip_extract((&auto, &sdmc), ("/", &out), &[BACKUP_MANIFEST_NAME], notify).unwrap();
+        assert_eq!(tree(&out, "/"), entries(&OLD_SAVE));
+    }
+
+    #[test]
+    fn restore_selected_files() {
+        let sdmc = backup(&BACKUP, "user");
+        let save = save(&OLD_SAVE);
+        restore_game_save(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &save),
+            manifest("user"),
+            Some(vec!["/main".to_string(), "dir/a.bin".to_string()]),
+            notify,
+        )
+        .unwrap();
+        assert_eq!(
+            tree(&save, "/"),
+            entries(&[
+                ("/main", "new main"),
+                ("/dir/a.bin", "a"),
+                ("/dir/old.bin", "old")
+            ])
+        );
+    }
+
+    #[test]
+    fn save_file_named_like_the_manifest() {
+        let files = [("/manifest.json", "{\"game\":1}"), ("/main", "new main")];
+        let sdmc = backup(&files, "user");
+        let backup_manifest = read_backup_manifest("/backups/backup.zip", &sdmc).unwrap();
+        assert_eq!(
+            backup_manifest.get_file("manifest.json").map(|f| f.size),
+            Some(10)
+        );
+
+        let save = save(&OLD_SAVE);
+        restore_game_save(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &save),
+            manifest("user"),
+            None,
+            notify,
+        )
+        .unwrap();
+        assert_eq!(tree(&save, "/"), entries(&files));
+    }
+
+    #[test]
+    fn restore_checks_the_backup_first() {
+        let save = save(&OLD_SAVE);
+        // backup of another save type
+        let sdmc = backup(&BACKUP, "ext");
+        assert!(restore_game_save(
+            ("/backups/backup.zip", &sdmc),
+            ("/", &save),
+            manifest("user"),
+            None,
+            notify,
+        )
+        .is_err());
+        // broken zip
+        sdmc.write("/backups/broken.zip", b"PK broken".to_vec());
+        assert!(restore_game_save(
+            ("/backups/broken.zip", &sdmc),
+            ("/", &save),
+            manifest("user"),
+            None,
+            notify,
+        )
+        .is_err());
+        assert_eq!(tree(&save, "/"), entries(&OLD_SAVE));
+        assert_eq!(save.commits(), 0);
+    }
+}
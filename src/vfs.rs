@@ -0,0 +1,580 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// metadata of a file or dir of a `Vfs`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VfsMetadata {
+    pub is_dir: bool,
+    /// 0 for dirs
+    pub len: u64,
+}
+
+impl VfsMetadata {
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// entry of `Vfs::read_dir`, `path` is the full path inside the filesystem
+#[derive(Clone, Debug)]
+pub struct VfsEntry {
+    pub path: PathBuf,
+    pub metadata: VfsMetadata,
+}
+
+impl VfsEntry {
+    pub fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    pub fn metadata(&self) -> io::Result<VfsMetadata> {
+        Ok(self.metadata)
+    }
+
+    pub fn file_name(&self) -> OsString {
+        self.path
+            .file_name()
+            .map(OsStr::to_os_string)
+            .unwrap_or_default()
+    }
+}
+
+/// # filesystem of backup and restore
+///
+/// paths are absolute inside the filesystem, e.g. `/data.bin` of a save
+/// archive. implemented by `fsu::Archive` on the 3DS, `HostFs` and `MemFs`
+/// run the same backup and restore code on any host
+pub trait Vfs {
+    type File: Read + Write + Seek;
+
+    fn open(&self, path: &Path) -> io::Result<Self::File>;
+    /// create or truncate the file, `size` is allocated up front if provided
+    fn create(&self, path: &Path, size: Option<u64>) -> io::Result<Self::File>;
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>>;
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    /// make the written data persistent, nothing to do for most filesystems
+    fn commit(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn is_dir_exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_dir())
+    }
+}
+
+/// normalized path without `.` and `..`, always starts with `/`
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => out.push(name),
+            Component::ParentDir => {
+                out.pop();
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{}", path.to_string_lossy()),
+    )
+}
+
+/// # a dir of the host as filesystem
+///
+/// `/` of the filesystem is `root`
+pub struct HostFs {
+    root: PathBuf,
+}
+
+impl HostFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn host_path(&self, path: &Path) -> PathBuf {
+        self.root
+            .join(normalize(path).strip_prefix("/").unwrap_or(Path::new("")))
+    }
+}
+
+impl Vfs for HostFs {
+    type File = fs::File;
+
+    fn open(&self, path: &Path) -> io::Result<Self::File> {
+        fs::File::open(self.host_path(path))
+    }
+
+    fn create(&self, path: &Path, size: Option<u64>) -> io::Result<Self::File> {
+        let file = fs::File::create(self.host_path(path))?;
+        if let Some(size) = size {
+            file.set_len(size)?;
+        }
+        Ok(file)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let path = normalize(path);
+        let mut entries = vec![];
+        for entry in fs::read_dir(self.host_path(&path))? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            entries.push(VfsEntry {
+                path: path.join(entry.file_name()),
+                metadata: VfsMetadata {
+                    is_dir: metadata.is_dir(),
+                    len: if metadata.is_dir() { 0 } else { metadata.len() },
+                },
+            });
+        }
+        Ok(entries)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let metadata = fs::metadata(self.host_path(path))?;
+        Ok(VfsMetadata {
+            is_dir: metadata.is_dir(),
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+        })
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(self.host_path(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(self.host_path(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(self.host_path(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(self.host_path(from), self.host_path(to))
+    }
+}
+
+#[derive(Default)]
+struct MemData {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    /// `/` is not included
+    dirs: BTreeSet<PathBuf>,
+    commits: usize,
+}
+
+impl MemData {
+    fn is_dir(&self, path: &Path) -> bool {
+        path == Path::new("/") || self.dirs.contains(path)
+    }
+
+    fn check_parent(&self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if self.is_dir(parent) => Ok(()),
+            Some(parent) => Err(not_found(parent)),
+            None => Err(not_found(path)),
+        }
+    }
+}
+
+/// # filesystem in memory
+///
+/// clones share the same data, data of a file is written back when the
+/// file is flushed or dropped
+#[derive(Clone, Default)]
+pub struct MemFs {
+    data: Arc<Mutex<MemData>>,
+}
+
+impl MemFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// data of the file, `None` if it does not exist
+    pub fn read(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        let data = self.data.lock().unwrap();
+        data.files.get(&normalize(path.as_ref())).cloned()
+    }
+
+    /// write the file, parent dirs are created
+    pub fn write(&self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        let path = normalize(path.as_ref());
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent).unwrap();
+        }
+        let mut data = self.data.lock().unwrap();
+        data.files.insert(path, contents.into());
+    }
+
+    /// times of `Vfs::commit`
+    pub fn commits(&self) -> usize {
+        self.data.lock().unwrap().commits
+    }
+}
+
+/// file of `MemFs`
+pub struct MemFile {
+    data: Arc<Mutex<MemData>>,
+    path: PathBuf,
+    cursor: Cursor<Vec<u8>>,
+    dirty: bool,
+}
+
+impl Read for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+impl Write for MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.dirty = true;
+        self.cursor.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.dirty {
+            let mut data = self.data.lock().unwrap();
+            data.files
+                .insert(self.path.clone(), self.cursor.get_ref().clone());
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl Seek for MemFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+impl Drop for MemFile {
+    fn drop(&mut self) {
+        self.flush().ok();
+    }
+}
+
+impl Vfs for MemFs {
+    type File = MemFile;
+
+    fn open(&self, path: &Path) -> io::Result<Self::File> {
+        let path = normalize(path);
+        let data = self.data.lock().unwrap();
+        let contents = data.files.get(&path).ok_or_else(|| not_found(&path))?;
+        Ok(MemFile {
+            data: self.data.clone(),
+            cursor: Cursor::new(contents.clone()),
+            path,
+            dirty: false,
+        })
+    }
+
+    fn create(&self, path: &Path, size: Option<u64>) -> io::Result<Self::File> {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+        data.check_parent(&path)?;
+        if data.is_dir(&path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{}", path.to_string_lossy()),
+            ));
+        }
+        let contents = vec![0; size.unwrap_or(0) as usize];
+        data.files.insert(path.clone(), contents.clone());
+        Ok(MemFile {
+            data: self.data.clone(),
+            cursor: Cursor::new(contents),
+            path,
+            dirty: false,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let path = normalize(path);
+        let data = self.data.lock().unwrap();
+        if !data.is_dir(&path) {
+            return Err(not_found(&path));
+        }
+        let dirs = data
+            .dirs
+            .iter()
+            .filter(|dir| dir.parent() == Some(path.as_path()))
+            .map(|dir| VfsEntry {
+                path: dir.clone(),
+                metadata: VfsMetadata {
+                    is_dir: true,
+                    len: 0,
+                },
+            });
+        let files = data
+            .files
+            .iter()
+            .filter(|(file, _)| file.parent() == Some(path.as_path()))
+            .map(|(file, contents)| VfsEntry {
+                path: file.clone(),
+                metadata: VfsMetadata {
+                    is_dir: false,
+                    len: contents.len() as u64,
+                },
+            });
+        Ok(dirs.chain(files).collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let path = normalize(path);
+        let data = self.data.lock().unwrap();
+        if data.is_dir(&path) {
+            return Ok(VfsMetadata {
+                is_dir: true,
+                len: 0,
+            });
+        }
+        data.files
+            .get(&path)
+            .map(|contents| VfsMetadata {
+                is_dir: false,
+                len: contents.len() as u64,
+            })
+            .ok_or_else(|| not_found(&path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+        for dir in path.ancestors().filter(|dir| *dir != Path::new("/")) {
+            if data.files.contains_key(dir) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{}", dir.to_string_lossy()),
+                ));
+            }
+            data.dirs.insert(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+        data.files
+            .remove(&path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(&path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+        if !data.dirs.contains(&path) {
+            return Err(not_found(&path));
+        }
+        data.dirs.retain(|dir| !dir.starts_with(&path));
+        data.files.retain(|file, _| !file.starts_with(&path));
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut data = self.data.lock().unwrap();
+        data.check_parent(&to)?;
+        if let Some(contents) = data.files.remove(&from) {
+            data.files.insert(to, contents);
+            return Ok(());
+        }
+        if !data.dirs.contains(&from) {
+            return Err(not_found(&from));
+        }
+        let rebase = |p: &Path| to.join(p.strip_prefix(&from).unwrap());
+        let dirs: Vec<_> = data
+            .dirs
+            .iter()
+            .filter(|dir| dir.starts_with(&from))
+            .cloned()
+            .collect();
+        for dir in dirs {
+            data.dirs.remove(&dir);
+            data.dirs.insert(rebase(&dir));
+        }
+        let files: Vec<_> = data
+            .files
+            .keys()
+            .filter(|file| file.starts_with(&from))
+            .cloned()
+            .collect();
+        for file in files {
+            let contents = data.files.remove(&file).unwrap();
+            data.files.insert(rebase(&file), contents);
+        }
+        Ok(())
+    }
+
+    fn commit(&self) -> io::Result<()> {
+        self.data.lock().unwrap().commits += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn write(fs: &impl Vfs, path: &str, size: Option<u64>, contents: &str) {
+        fs.create(Path::new(path), size)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+    }
+
+    fn read(fs: &impl Vfs, path: &str) -> String {
+        let mut contents = String::new();
+        fs.open(Path::new(path))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    fn list(fs: &impl Vfs, path: &str) -> Vec<(String, bool)> {
+        let mut entries = fs
+            .read_dir(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.path().to_string_lossy().to_string(),
+                    entry.metadata().unwrap().is_dir(),
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    /// behavior both filesystems share with `fsu::Archive`
+    fn check(fs: &impl Vfs) {
+        fs.create_dir_all(Path::new("/a/b")).unwrap();
+        assert!(fs.is_dir_exists(Path::new("/a")));
+        assert!(fs.is_dir_exists(Path::new("/a/b")));
+
+        write(fs, "/a/b/c.bin", None, "data");
+        assert_eq!(read(fs, "/a/b/c.bin"), "data");
+        // `.` and `..` are resolved inside the filesystem
+        assert_eq!(read(fs, "/a/./b/../b/c.bin"), "data");
+        let metadata = fs.metadata(Path::new("/a/b/c.bin")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), 4);
+        assert!(!fs.is_dir_exists(Path::new("/a/b/c.bin")));
+
+        // create truncates, the size is allocated up front
+        write(fs, "/a/b/c.bin", None, "x");
+        assert_eq!(read(fs, "/a/b/c.bin"), "x");
+        fs.create(Path::new("/a/d.bin"), Some(8)).unwrap();
+        assert_eq!(fs.metadata(Path::new("/a/d.bin")).unwrap().len(), 8);
+
+        assert_eq!(
+            list(fs, "/a"),
+            [("/a/b".to_string(), true), ("/a/d.bin".to_string(), false)]
+        );
+        assert_eq!(list(fs, "/a/b"), [("/a/b/c.bin".to_string(), false)]);
+
+        let missing = Path::new("/missing/e.bin");
+        assert_eq!(
+            fs.open(missing).err().map(|e| e.kind()),
+            Some(io::ErrorKind::NotFound)
+        );
+        assert!(fs.create(missing, None).is_err());
+        assert!(fs.read_dir(Path::new("/missing")).is_err());
+        assert!(fs.metadata(missing).is_err());
+
+        fs.rename(Path::new("/a/d.bin"), Path::new("/a/b/d.bin"))
+            .unwrap();
+        fs.rename(Path::new("/a/b"), Path::new("/f")).unwrap();
+        assert_eq!(list(fs, "/a"), []);
+        assert_eq!(
+            list(fs, "/f"),
+            [
+                ("/f/c.bin".to_string(), false),
+                ("/f/d.bin".to_string(), false)
+            ]
+        );
+
+        fs.remove_file(Path::new("/f/d.bin")).unwrap();
+        assert!(fs.metadata(Path::new("/f/d.bin")).is_err());
+        assert!(fs.remove_file(Path::new("/f/d.bin")).is_err());
+        fs.remove_dir_all(Path::new("/f")).unwrap();
+        assert!(!fs.is_dir_exists(Path::new("/f")));
+        assert!(fs.remove_dir_all(Path::new("/f")).is_err());
+        fs.commit().unwrap();
+    }
+
+    #[test]
+    fn mem_fs() {
+        let fs = MemFs::new();
+        check(&fs);
+        assert_eq!(fs.commits(), 1);
+    }
+
+    #[test]
+    fn mem_fs_shares_data() {
+        let fs = MemFs::new();
+        fs.write("/a/b.bin", "data");
+        assert!(fs.is_dir_exists(Path::new("/a")));
+        let clone = fs.clone();
+        assert_eq!(read(&clone, "/a/b.bin"), "data");
+
+        // written back when the file is flushed or dropped
+        let mut file = clone.create(Path::new("/a/c.bin"), None).unwrap();
+        file.write_all(b"new").unwrap();
+        assert_eq!(fs.read("/a/c.bin"), Some(vec![]));
+        file.flush().unwrap();
+        assert_eq!(fs.read("/a/c.bin"), Some(b"new".to_vec()));
+        file.write_all(b" data").unwrap();
+        drop(file);
+        assert_eq!(fs.read("/a/c.bin"), Some(b"new data".to_vec()));
+
+        // a file can not take the place of a dir and the other way round
+        assert!(fs.create(Path::new("/a"), None).is_err());
+        assert!(fs.create_dir_all(Path::new("/a/b.bin/c")).is_err());
+        assert_eq!(fs.read("/missing"), None);
+    }
+
+    #[test]
+    fn host_fs() {
+        let root = env::temp_dir().join(format!("save-cloud-host-fs-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        check(&HostFs::new(&root));
+        // paths do not leave the root
+        let host = HostFs::new(root.join("a"));
+        write(&host, "/../b.bin", None, "data");
+        let res = fs::read_to_string(root.join("a/b.bin"));
+        fs::remove_dir_all(&root).ok();
+        assert_eq!(res.unwrap(), "data");
+    }
+}